@@ -0,0 +1,342 @@
+use std::{fmt, path::PathBuf, str::FromStr};
+
+use crate::{
+    dashboard::Side,
+    export::{ExportFormat, ExportTarget},
+    settings::SettingField,
+    views::ViewId,
+};
+
+/// User intents, decoupled from the keys that trigger them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Action {
+    Navigate(ViewId),
+    ToggleTheme,
+    ScrollUp,
+    ScrollDown,
+    ScrollTop,
+    ScrollBottom,
+    PageUp,
+    PageDown,
+    /// Search the text of the current view
+    Search,
+    NextMatch,
+    PrevMatch,
+    /// Put the cursor on a row of the data table, by position from the top
+    GoToRow(usize),
+    /// Ask for a row number and put the cursor on it
+    JumpToRow,
+    ColumnLeft,
+    ColumnRight,
+    ToggleSort,
+    HideColumn,
+    ShowAllColumns,
+    ToggleMark,
+    ToggleMarkAll,
+    ToggleDetail,
+    OpenFile,
+    Filter,
+    Export,
+    EditCell,
+    UndoEdit,
+    SaveData,
+    ToggleChanges,
+    NextPanel,
+    PrevPanel,
+    /// Move the selected dashboard panel a cell towards the side
+    MovePanel(Side),
+    /// Widen (right), narrow (left), lengthen (down) or shorten (up) the selected panel
+    ResizePanel(Side),
+    /// Show the selected panel over the whole dashboard, or put it back
+    MaximisePanel,
+    /// Write the dashboard layout to the config file, and nothing else
+    SaveLayout,
+    FocusNext,
+    FocusPrev,
+    Activate,
+    Increase,
+    Decrease,
+    SaveConfig,
+    RevertConfig,
+    /// Go to a field of the Settings form and change it as Enter would
+    ChangeSetting(SettingField),
+    /// List every command, to run one by name
+    OpenPalette,
+    Suspend,
+    Quit,
+}
+
+impl Action {
+    /// Human readable description used in on-screen hints
+    pub fn description(&self) -> String {
+        match self {
+            Action::Navigate(view) => view.title().to_string(),
+            Action::ToggleTheme => "Toggle theme".to_string(),
+            Action::ScrollUp => "Scroll up".to_string(),
+            Action::ScrollDown => "Scroll down".to_string(),
+            Action::ScrollTop => "Scroll to top".to_string(),
+            Action::ScrollBottom => "Scroll to bottom".to_string(),
+            Action::PageUp => "Page up".to_string(),
+            Action::PageDown => "Page down".to_string(),
+            Action::Search => "Search".to_string(),
+            Action::NextMatch => "Next match".to_string(),
+            Action::PrevMatch => "Previous match".to_string(),
+            Action::GoToRow(row) => format!("Go to row {}", row + 1),
+            Action::JumpToRow => "Jump to row".to_string(),
+            Action::ColumnLeft => "Previous column".to_string(),
+            Action::ColumnRight => "Next column".to_string(),
+            Action::ToggleSort => "Sort by column".to_string(),
+            Action::HideColumn => "Hide column".to_string(),
+            Action::ShowAllColumns => "Show all columns".to_string(),
+            Action::ToggleMark => "Select row".to_string(),
+            Action::ToggleMarkAll => "Select all rows".to_string(),
+            Action::ToggleDetail => "Toggle row details".to_string(),
+            Action::OpenFile => "Open data file".to_string(),
+            Action::Filter => "Filter rows".to_string(),
+            Action::Export => "Export rows".to_string(),
+            Action::EditCell => "Edit cell".to_string(),
+            Action::UndoEdit => "Undo edit".to_string(),
+            Action::SaveData => "Save changes".to_string(),
+            Action::ToggleChanges => "Toggle pending changes".to_string(),
+            Action::NextPanel => "Next panel".to_string(),
+            Action::PrevPanel => "Previous panel".to_string(),
+            Action::MovePanel(side) => format!("Move panel {}", side),
+            Action::ResizePanel(Side::Left) => "Narrow panel".to_string(),
+            Action::ResizePanel(Side::Right) => "Widen panel".to_string(),
+            Action::ResizePanel(Side::Up) => "Shorten panel".to_string(),
+            Action::ResizePanel(Side::Down) => "Lengthen panel".to_string(),
+            Action::MaximisePanel => "Maximise panel".to_string(),
+            Action::SaveLayout => "Save layout".to_string(),
+            Action::FocusNext => "Focus next element".to_string(),
+            Action::FocusPrev => "Focus previous element".to_string(),
+            Action::Activate => "Activate/Confirm".to_string(),
+            Action::Increase => "Next value".to_string(),
+            Action::Decrease => "Previous value".to_string(),
+            Action::SaveConfig => "Save settings".to_string(),
+            Action::RevertConfig => "Revert settings".to_string(),
+            Action::ChangeSetting(field) => format!("Change setting: {}", field.label()),
+            Action::OpenPalette => "Command palette".to_string(),
+            Action::Suspend => "Suspend".to_string(),
+            Action::Quit => "Quit".to_string(),
+        }
+    }
+
+    /// The view whose contents the action works on, when it only works on one
+    pub fn view(&self) -> Option<ViewId> {
+        match self {
+            Action::GoToRow(_)
+            | Action::JumpToRow
+            | Action::ColumnLeft
+            | Action::ColumnRight
+            | Action::ToggleSort
+            | Action::HideColumn
+            | Action::ShowAllColumns
+            | Action::ToggleMark
+            | Action::ToggleMarkAll
+            | Action::ToggleDetail
+            | Action::OpenFile
+            | Action::Filter
+            | Action::Export
+            | Action::EditCell
+            | Action::UndoEdit
+            | Action::SaveData
+            | Action::ToggleChanges => Some(ViewId::Data),
+            Action::NextPanel
+            | Action::PrevPanel
+            | Action::MovePanel(_)
+            | Action::ResizePanel(_)
+            | Action::MaximisePanel => Some(ViewId::Dashboard),
+            Action::Activate | Action::Increase | Action::Decrease | Action::ChangeSetting(_) => {
+                Some(ViewId::Settings)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Action {
+    /// The name used to refer to the action in configuration files
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Action::Navigate(view) => write!(f, "navigate:{}", view.as_str()),
+            Action::ToggleTheme => write!(f, "toggle_theme"),
+            Action::ScrollUp => write!(f, "scroll_up"),
+            Action::ScrollDown => write!(f, "scroll_down"),
+            Action::ScrollTop => write!(f, "scroll_top"),
+            Action::ScrollBottom => write!(f, "scroll_bottom"),
+            Action::PageUp => write!(f, "page_up"),
+            Action::PageDown => write!(f, "page_down"),
+            Action::Search => write!(f, "search"),
+            Action::NextMatch => write!(f, "next_match"),
+            Action::PrevMatch => write!(f, "prev_match"),
+            Action::GoToRow(row) => write!(f, "go_to_row:{}", row + 1),
+            Action::JumpToRow => write!(f, "jump_to_row"),
+            Action::ColumnLeft => write!(f, "column_left"),
+            Action::ColumnRight => write!(f, "column_right"),
+            Action::ToggleSort => write!(f, "toggle_sort"),
+            Action::HideColumn => write!(f, "hide_column"),
+            Action::ShowAllColumns => write!(f, "show_all_columns"),
+            Action::ToggleMark => write!(f, "toggle_mark"),
+            Action::ToggleMarkAll => write!(f, "toggle_mark_all"),
+            Action::ToggleDetail => write!(f, "toggle_detail"),
+            Action::OpenFile => write!(f, "open_file"),
+            Action::Filter => write!(f, "filter"),
+            Action::Export => write!(f, "export"),
+            Action::EditCell => write!(f, "edit_cell"),
+            Action::UndoEdit => write!(f, "undo_edit"),
+            Action::SaveData => write!(f, "save_data"),
+            Action::ToggleChanges => write!(f, "toggle_changes"),
+            Action::NextPanel => write!(f, "next_panel"),
+            Action::PrevPanel => write!(f, "prev_panel"),
+            Action::MovePanel(side) => write!(f, "move_panel:{}", side),
+            Action::ResizePanel(side) => write!(f, "resize_panel:{}", side),
+            Action::MaximisePanel => write!(f, "maximise_panel"),
+            Action::SaveLayout => write!(f, "save_layout"),
+            Action::FocusNext => write!(f, "focus_next"),
+            Action::FocusPrev => write!(f, "focus_prev"),
+            Action::Activate => write!(f, "activate"),
+            Action::Increase => write!(f, "increase"),
+            Action::Decrease => write!(f, "decrease"),
+            Action::SaveConfig => write!(f, "save_config"),
+            Action::RevertConfig => write!(f, "revert_config"),
+            Action::ChangeSetting(field) => write!(f, "change_setting:{}", field.as_str()),
+            Action::OpenPalette => write!(f, "open_palette"),
+            Action::Suspend => write!(f, "suspend"),
+            Action::Quit => write!(f, "quit"),
+        }
+    }
+}
+
+impl FromStr for Action {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(view) = s.strip_prefix("navigate:") {
+            return view.parse().map(Action::Navigate);
+        }
+        // Rows are numbered from 1 in names, as on screen
+        if let Some(row) = s.strip_prefix("go_to_row:") {
+            return match row.parse::<usize>() {
+                Ok(row @ 1..) => Ok(Action::GoToRow(row - 1)),
+                _ => Err(format!("invalid row in '{}'", s)),
+            };
+        }
+        if let Some(side) = s.strip_prefix("move_panel:") {
+            return side.parse().map(Action::MovePanel);
+        }
+        if let Some(side) = s.strip_prefix("resize_panel:") {
+            return side.parse().map(Action::ResizePanel);
+        }
+        if let Some(field) = s.strip_prefix("change_setting:") {
+            return field.parse().map(Action::ChangeSetting);
+        }
+
+        match s {
+            "toggle_theme" => Ok(Action::ToggleTheme),
+            "scroll_up" => Ok(Action::ScrollUp),
+            "scroll_down" => Ok(Action::ScrollDown),
+            "scroll_top" => Ok(Action::ScrollTop),
+            "scroll_bottom" => Ok(Action::ScrollBottom),
+            "page_up" => Ok(Action::PageUp),
+            "page_down" => Ok(Action::PageDown),
+            "search" => Ok(Action::Search),
+            "next_match" => Ok(Action::NextMatch),
+            "prev_match" => Ok(Action::PrevMatch),
+            "jump_to_row" => Ok(Action::JumpToRow),
+            "column_left" => Ok(Action::ColumnLeft),
+            "column_right" => Ok(Action::ColumnRight),
+            "toggle_sort" => Ok(Action::ToggleSort),
+            "hide_column" => Ok(Action::HideColumn),
+            "show_all_columns" => Ok(Action::ShowAllColumns),
+            "toggle_mark" => Ok(Action::ToggleMark),
+            "toggle_mark_all" => Ok(Action::ToggleMarkAll),
+            "toggle_detail" => Ok(Action::ToggleDetail),
+            "open_file" => Ok(Action::OpenFile),
+            "filter" => Ok(Action::Filter),
+            "export" => Ok(Action::Export),
+            "edit_cell" => Ok(Action::EditCell),
+            "undo_edit" => Ok(Action::UndoEdit),
+            "save_data" => Ok(Action::SaveData),
+            "toggle_changes" => Ok(Action::ToggleChanges),
+            "next_panel" => Ok(Action::NextPanel),
+            "prev_panel" => Ok(Action::PrevPanel),
+            "maximise_panel" => Ok(Action::MaximisePanel),
+            "save_layout" => Ok(Action::SaveLayout),
+            "focus_next" => Ok(Action::FocusNext),
+            "focus_prev" => Ok(Action::FocusPrev),
+            "activate" => Ok(Action::Activate),
+            "increase" => Ok(Action::Increase),
+            "decrease" => Ok(Action::Decrease),
+            "save_config" => Ok(Action::SaveConfig),
+            "revert_config" => Ok(Action::RevertConfig),
+            "open_palette" => Ok(Action::OpenPalette),
+            "suspend" => Ok(Action::Suspend),
+            "quit" => Ok(Action::Quit),
+            _ => Err(format!("unknown action '{}'", s)),
+        }
+    }
+}
+
+/// Side effects requested by the reducer for the app shell to carry out
+#[derive(Debug, Clone, PartialEq)]
+pub enum Effect {
+    /// State visible on screen changed; refresh derived widgets and redraw
+    Render,
+    /// Hand the terminal back to the shell until the process is resumed
+    Suspend,
+    /// The configuration changed; apply its theme, layout and log level
+    ApplyConfig,
+    /// Write the configuration to disk
+    SaveConfig,
+    /// Write the dashboard layout to disk, leaving other settings as the file has them
+    SaveLayout,
+    /// List a directory in the file picker
+    ListDirectory(PathBuf),
+    /// Load a data file into the Data Browser in the background
+    LoadData(PathBuf),
+    /// Write the Data Browser's rows out as they are shown
+    Export(ExportTarget, ExportFormat),
+    /// Write edited rows back to the file they came from
+    SaveData,
+    /// Write the recently run palette commands to disk
+    SaveHistory,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_action_names_round_trip() {
+        let actions = [
+            Action::Navigate(ViewId::Settings),
+            Action::ToggleTheme,
+            Action::ScrollTop,
+            Action::NextMatch,
+            Action::GoToRow(41),
+            Action::JumpToRow,
+            Action::ToggleSort,
+            Action::UndoEdit,
+            Action::MovePanel(Side::Up),
+            Action::ResizePanel(Side::Right),
+            Action::SaveLayout,
+            Action::SaveConfig,
+            Action::ChangeSetting(SettingField::ShowStatusBar),
+            Action::OpenPalette,
+            Action::Quit,
+        ];
+
+        for action in actions {
+            assert_eq!(action.to_string().parse::<Action>(), Ok(action));
+        }
+    }
+
+    #[test]
+    fn test_unknown_action() {
+        assert!("explode".parse::<Action>().is_err());
+        assert!("navigate:nowhere".parse::<Action>().is_err());
+        assert!("go_to_row:0".parse::<Action>().is_err());
+        assert!("move_panel:sideways".parse::<Action>().is_err());
+        assert!("change_setting:volume".parse::<Action>().is_err());
+    }
+}
@@ -1,212 +1,1483 @@
-use anyhow::Result;
-use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind},
-    execute,
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+use anyhow::{anyhow, Result};
+use crossterm::event::{
+    self as terminal_event, KeyEvent, KeyEventKind, MouseButton, MouseEvent, MouseEventKind,
 };
-use ratatui::{
-    backend::{Backend, CrosstermBackend},
-    layout::{Constraint, Direction, Layout, Rect},
-    Terminal,
+use log::{debug, trace, warn};
+use ratatui::{backend::Backend, Terminal};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant},
 };
-use std::io;
 
 use crate::{
-    styles::StyleManager,
-    views::ViewManager,
-    widgets::WidgetManager,
+    action::{Action, Effect},
+    clock::{Clock, SystemClock},
+    context::{DataContext, RenderContext},
+    data::Dataset,
+    event::{Event, EventLoop, Signal, TaskMessage, TaskSender},
+    export::{Export, ExportFormat, ExportTarget},
+    focus::FocusId,
+    keymap::{KeyCombo, KeyResolution, Keymap},
+    logs::LogTail,
+    metrics::MetricsSampler,
+    modal::Dialog,
+    palette, picker,
+    record::Recorder,
+    script::{ScriptError, ScriptEvent},
+    settings::{FormNotice, SettingField},
+    source::{self, LoadStatus},
+    state::{self, AppState},
+    styles::{self, Theme, ThemeError},
+    table::DataTable,
+    views::{ViewId, ViewManager},
+    watcher::{ThemeWatcher, THEME_POLL_INTERVAL},
+    widgets::{EventResult, Hit, Toast, Widget, WidgetManager},
 };
 
-/// Application state
-#[derive(Debug, Clone, PartialEq)]
-pub enum View {
-    Dashboard,
-    Data,
-    Settings,
-    Help,
-}
-
-impl View {
-    pub fn as_str(&self) -> &'static str {
-        match self {
-            View::Dashboard => "dashboard",
-            View::Data => "data",
-            View::Settings => "settings",
-            View::Help => "help",
-        }
-    }
-}
-
 /// Main application structure
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub struct App {
-    pub current_view: View,
-    pub should_quit: bool,
-    pub style_manager: StyleManager,
+    pub state: AppState,
+    pub keymap: Keymap,
     pub widget_manager: WidgetManager,
     pub view_manager: ViewManager,
+    /// Whether the terminal reports mouse events; reapplied after a suspend
+    pub mouse_capture: bool,
+    /// Set when the mouse setting changed, for the run loop to update the terminal
+    mouse_capture_changed: bool,
+    /// Whether the sidebar border is being dragged
+    resizing_sidebar: bool,
+    /// Whether the app draws to a screen in memory, leaving the real terminal alone
+    pub headless: bool,
+    /// Where input is logged when the session is being recorded
+    pub recorder: Option<Recorder>,
+    /// Where the configuration is saved and themes are found
+    pub config_dir: Option<PathBuf>,
+    /// Data file to load once the event loop is running
+    pub data_file: Option<PathBuf>,
+    /// Exports to print once the terminal is restored
+    stdout_exports: Vec<Export>,
+    /// System metrics for the dashboard, sampled on ticks
+    pub metrics: MetricsSampler,
+    /// Where the dashboard's time, sampling intervals and chord timeouts come from
+    pub clock: Box<dyn Clock>,
+    /// Latest log lines, for the dashboard's log panel
+    pub log_tail: Option<LogTail>,
+    /// How many log lines the dashboard has been given
+    log_written: u64,
+    pending_keys: Vec<KeyCombo>,
+    pending_since: Option<Instant>,
+    tasks: Option<TaskSender>,
+    theme_watcher: Option<ThemeWatcher>,
+    dirty: bool,
+    suspend_requested: bool,
+}
+
+/// How long to wait for the rest of a chord before discarding it
+const CHORD_TIMEOUT: Duration = Duration::from_secs(1);
+
+impl Default for App {
+    fn default() -> Self {
+        Self::with_keymap(Keymap::default())
+    }
 }
 
 impl App {
-    /// Create a new application instance
-    pub fn new() -> Self {
-        let style_manager = StyleManager::default();
-        let widget_manager = WidgetManager::new(style_manager.clone());
-        let view_manager = ViewManager::new(style_manager.clone());
-
-        Self {
-            current_view: View::Dashboard,
-            should_quit: false,
-            style_manager,
-            widget_manager,
-            view_manager,
-        }
-    }
-
-    /// Run the application
-    pub async fn run<B: Backend>(&mut self, terminal: &mut Terminal<B>) -> Result<()> {
-        loop {
-            terminal.draw(|f| self.render(f))?;
-
-            if let Event::Key(key) = event::read()? {
-                if key.kind == KeyEventKind::Press {
-                    if self.handle_key_event(key.code) {
-                        break;
+    /// Create a new application instance using the given keymap
+    pub fn with_keymap(keymap: Keymap) -> Self {
+        Self::with_state(keymap, AppState::default())
+    }
+
+    /// Create an application that starts from the given state, e.g. a view and theme picked on the command line
+    pub fn with_state(keymap: Keymap, state: AppState) -> Self {
+        let mut app = Self {
+            state,
+            keymap,
+            widget_manager: WidgetManager::default(),
+            view_manager: ViewManager::default(),
+            mouse_capture: true,
+            mouse_capture_changed: false,
+            resizing_sidebar: false,
+            headless: false,
+            recorder: None,
+            config_dir: None,
+            data_file: None,
+            stdout_exports: Vec::new(),
+            metrics: MetricsSampler::default(),
+            clock: Box::new(SystemClock),
+            log_tail: None,
+            log_written: 0,
+            pending_keys: Vec::new(),
+            pending_since: None,
+            tasks: None,
+            theme_watcher: None,
+            dirty: true,
+            suspend_requested: false,
+        };
+        app.update_widgets();
+        app
+    }
+
+    /// Run the application until it quits, redrawing only when state changed
+    pub async fn run<B: Backend>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+        events: &mut EventLoop,
+    ) -> Result<()> {
+        self.tasks = Some(events.sender());
+        let themes_dir = self.themes_dir();
+        let active = Theme::locate(&self.state.config.theme, themes_dir.as_deref())
+            .ok()
+            .flatten();
+        self.theme_watcher = Some(ThemeWatcher::spawn(
+            themes_dir,
+            active,
+            events.sender(),
+            THEME_POLL_INTERVAL,
+        ));
+        if let Some(path) = self.data_file.take() {
+            self.run_effects(vec![Effect::LoadData(path)]);
+        }
+        // The first reading is the baseline the next one's rates are worked out from
+        self.sample_metrics();
+        self.refresh_dashboard();
+
+        while !self.state.should_quit {
+            let Some(event) = events.next().await else {
+                break;
+            };
+            if let Some(recorder) = &self.recorder {
+                recorder.input(&event);
+            }
+
+            match event {
+                Event::Render => {
+                    if self.dirty {
+                        terminal.draw(|f| self.render(f))?;
+                        self.dirty = false;
+                    }
+                }
+                Event::Error(err) => return Err(anyhow!("terminal input failed: {}", err)),
+                Event::Script(ScriptEvent::Expect { line, expect }) => {
+                    // Check what the steps before it drew, changed or not
+                    let frame = terminal.draw(|f| self.render(f))?;
+                    self.dirty = false;
+                    if !expect.check(frame.buffer, &self.widget_manager.areas()) {
+                        return Err(ScriptError::Failed { line, expect }.into());
                     }
                 }
+                Event::Script(ScriptEvent::End) => break,
+                Event::Signal(Signal::Resume) if !self.headless => {
+                    // The shell may have reset the terminal while we were stopped
+                    crate::terminal::setup_terminal(self.mouse_capture)?;
+                    terminal.clear()?;
+                    self.dirty = true;
+                }
+                event => self.handle_event(event),
+            }
+
+            if std::mem::take(&mut self.suspend_requested) && !self.headless {
+                self.suspend(terminal)?;
+            }
+            if std::mem::take(&mut self.mouse_capture_changed) && !self.headless {
+                crate::terminal::set_mouse_capture(self.mouse_capture)?;
             }
         }
 
         Ok(())
     }
 
-    /// Handle key events
-    fn handle_key_event(&mut self, key: KeyCode) -> bool {
-        match key {
-            KeyCode::Char('q') | KeyCode::Esc => {
-                self.should_quit = true;
+    /// Stop the process until it is resumed, then redraw everything
+    #[cfg(unix)]
+    fn suspend<B: Backend>(&mut self, terminal: &mut Terminal<B>) -> Result<()> {
+        debug!("Suspending");
+        crate::terminal::suspend(self.mouse_capture)?;
+        terminal.clear()?;
+        self.dirty = true;
+        Ok(())
+    }
+
+    #[cfg(not(unix))]
+    fn suspend<B: Backend>(&mut self, _terminal: &mut Terminal<B>) -> Result<()> {
+        Ok(())
+    }
+
+    /// Update state in response to an event from the event loop
+    pub fn handle_event(&mut self, event: Event) {
+        match event {
+            Event::Key(key) if key.kind == KeyEventKind::Press => self.handle_key_event(key),
+            Event::Resize(width, height) => {
+                debug!("Terminal resized to {}x{}", width, height);
+                self.dirty = true;
+            }
+            Event::Mouse(mouse) => self.handle_mouse_event(mouse),
+            Event::Tick => self.on_tick(),
+            Event::Task(message) => self.handle_task_message(message),
+            // Signals quit without asking about unsaved edits
+            Event::Signal(Signal::Interrupt | Signal::Terminate) => self.state.should_quit = true,
+            Event::Signal(Signal::Suspend) => self.dispatch(Action::Suspend),
+            Event::Key(_)
+            | Event::Render
+            | Event::Signal(Signal::Resume)
+            | Event::Error(_)
+            | Event::Script(_) => {}
+        }
+    }
+
+    fn on_tick(&mut self) {
+        if self.widget_manager.expire_toast() {
+            self.dirty = true;
+        }
+        self.sample_metrics();
+        self.refresh_dashboard();
+        let now = self.clock.instant();
+        if self
+            .pending_since
+            .is_some_and(|since| now.saturating_duration_since(since) >= CHORD_TIMEOUT)
+        {
+            self.pending_keys.clear();
+            self.pending_since = None;
+        }
+    }
+
+    /// Take a metrics sample if one is due, redrawing if the dashboard shows it
+    fn sample_metrics(&mut self) {
+        if self.metrics.sample(self.clock.instant()) {
+            self.view_manager
+                .dashboard
+                .set_metrics(self.metrics.metrics());
+            self.dirty |= self.state.current_view == ViewId::Dashboard;
+        }
+    }
+
+    /// Keep the dashboard's clock and log tail current, redrawing if the dashboard shows them
+    fn refresh_dashboard(&mut self) {
+        let dashboard = &mut self.view_manager.dashboard;
+        let mut changed = dashboard.set_time(self.clock.now());
+        if let Some(tail) = &self.log_tail {
+            let written = tail.written();
+            if written != self.log_written {
+                self.log_written = written;
+                dashboard.set_log(tail.recent());
+                changed = true;
+            }
+        }
+        self.dirty |= changed && self.state.current_view == ViewId::Dashboard;
+    }
+
+    fn handle_task_message(&mut self, message: TaskMessage) {
+        match message {
+            TaskMessage::ThemesChanged => self.reload_themes(),
+            TaskMessage::DataProgress { path, progress } => {
+                if self.state.load.is_loading(&path) {
+                    self.state.load = LoadStatus::Loading { path, progress };
+                    self.update_widgets();
+                    self.dirty = true;
+                }
+            }
+            TaskMessage::DataLoaded { path, result } => self.data_loaded(path, result),
+            TaskMessage::Exported { path, result } => {
+                match result {
+                    Ok(rows) => {
+                        let message = format!("Exported {} rows to {}", rows, path.display());
+                        self.widget_manager.show_toast(Toast::success(message));
+                    }
+                    Err(error) => {
+                        warn!("Export failed: {}", error);
+                        self.alert("Cannot export", error);
+                    }
+                }
+                self.dirty = true;
+            }
+            TaskMessage::DataSaved { path, result } => self.data_saved(path, result),
+        }
+    }
+
+    /// Start reading a data file in the background; the current rows stay until it arrives
+    fn load_data(&mut self, path: PathBuf) {
+        let Some(tasks) = self.tasks.clone() else {
+            warn!(
+                "Cannot load {} before the event loop starts",
+                path.display()
+            );
+            return;
+        };
+        self.state.load = LoadStatus::Loading {
+            path: path.clone(),
+            progress: 0.0,
+        };
+        let source = match source::open(&path) {
+            Ok(source) => source,
+            Err(err) => return self.data_loaded(path, Err(err.to_string())),
+        };
+
+        debug!("Loading {}", source.name());
+        let progress = tasks.clone();
+        tasks.spawn(async move {
+            let reading = path.clone();
+            let loaded = tokio::task::spawn_blocking(move || {
+                source.load(&mut |fraction| {
+                    progress.send(TaskMessage::DataProgress {
+                        path: reading.clone(),
+                        progress: fraction,
+                    })
+                })
+            })
+            .await;
+
+            let result = match loaded {
+                Ok(Ok(dataset)) => Ok(Arc::new(dataset)),
+                Ok(Err(err)) => Err(err.to_string()),
+                Err(err) => Err(format!("loading stopped: {}", err)),
+            };
+            TaskMessage::DataLoaded { path, result }
+        });
+    }
+
+    /// Write the rows shown to a file in the background, or keep them for stdout
+    fn export(&mut self, target: ExportTarget, format: ExportFormat) {
+        let export = Export::new(&self.state.data, format);
+        let rows = export.len();
+
+        let path = match target {
+            ExportTarget::Stdout => {
+                self.stdout_exports.push(export);
+                let message = format!("{} rows will be printed as {} on exit", rows, format.name());
+                return self.widget_manager.show_toast(Toast::success(message));
+            }
+            ExportTarget::File(path) => path,
+        };
+        let Some(tasks) = &self.tasks else {
+            warn!(
+                "Cannot export to {} before the event loop starts",
+                path.display()
+            );
+            return;
+        };
+
+        debug!("Exporting {} rows to {}", rows, path.display());
+        tasks.spawn(async move {
+            let writing = path.clone();
+            let written = tokio::task::spawn_blocking(move || export.write_file(&writing)).await;
+            let result = match written {
+                Ok(Ok(())) => Ok(rows),
+                Ok(Err(err)) => Err(err.to_string()),
+                Err(err) => Err(format!("export stopped: {}", err)),
+            };
+            TaskMessage::Exported { path, result }
+        });
+    }
+
+    /// Write the edited rows back to the file they were loaded from, in the background
+    fn save_data(&mut self) {
+        let LoadStatus::Loaded { path } = &self.state.load else {
+            let message = "These rows did not come from a file; export them instead.";
+            return self.alert("Cannot save", message.to_string());
+        };
+        let Some(tasks) = &self.tasks else {
+            warn!(
+                "Cannot save {} before the event loop starts",
+                path.display()
+            );
+            return;
+        };
+        let source = match source::open(path) {
+            Ok(source) => source,
+            Err(err) => return self.alert("Cannot save", err.to_string()),
+        };
+
+        let path = path.clone();
+        let dataset = self.state.data.edited_dataset();
+        debug!("Saving {} rows to {}", dataset.len(), path.display());
+        tasks.spawn(async move {
+            let saved =
+                tokio::task::spawn_blocking(move || source.save(&dataset).map(|_| dataset)).await;
+            let result = match saved {
+                Ok(Ok(dataset)) => Ok(Arc::new(dataset)),
+                Ok(Err(err)) => Err(err.to_string()),
+                Err(err) => Err(format!("saving stopped: {}", err)),
+            };
+            TaskMessage::DataSaved { path, result }
+        });
+    }
+
+    /// Take the saved rows as the ones on disk, or report why they could not be saved
+    fn data_saved(&mut self, path: PathBuf, result: Result<Arc<Dataset>, String>) {
+        let quit = std::mem::take(&mut self.state.quit_after_save);
+        // Another file may have been opened while this one was saving
+        if self.state.load != (LoadStatus::Loaded { path: path.clone() }) {
+            debug!("Dropping stale save of {}", path.display());
+            return;
+        }
+
+        match result {
+            Ok(dataset) => {
+                let rows = dataset.len();
+                self.state.data.set_saved(dataset);
+                self.state.should_quit = quit;
+                let message = format!("Saved {} rows to {}", rows, path.display());
+                self.widget_manager.show_toast(Toast::success(message));
+            }
+            Err(error) => {
+                warn!("Cannot save {}: {}", path.display(), error);
+                self.alert("Cannot save", error);
+            }
+        }
+        self.update_widgets();
+        self.dirty = true;
+    }
+
+    /// Open a dialog reporting an error, which stays until it is read
+    fn alert(&mut self, title: &str, message: String) {
+        // Saving on the way out did not work, so stay to show why
+        self.state.quit_after_save = false;
+        self.state.modals.push(Dialog::alert(title, message));
+        self.update_widgets();
+    }
+
+    /// Exports asked to go to stdout, to be printed after the terminal is restored
+    pub fn take_stdout_exports(&mut self) -> Vec<Export> {
+        std::mem::take(&mut self.stdout_exports)
+    }
+
+    /// Show a loaded dataset, or report why it could not be read
+    fn data_loaded(&mut self, path: PathBuf, result: Result<Arc<Dataset>, String>) {
+        if !self.state.load.is_loading(&path) {
+            debug!("Dropping stale load of {}", path.display());
+            return;
+        }
+
+        let status = match result {
+            Ok(dataset) => {
+                let status = format!("Loaded {} rows from {}", dataset.len(), path.display());
+                self.state.data = DataTable::new(dataset);
+                // A filter or edit being typed was for the old rows
+                self.state.filter_bar = None;
+                self.state.cell_editor = None;
+                self.state.load = LoadStatus::Loaded { path };
+                status
+            }
+            Err(error) => {
+                warn!("Cannot load {}: {}", path.display(), error);
+                let status = format!("⚠ {}", error);
+                self.state.load = LoadStatus::Failed { path, error };
+                status
+            }
+        };
+
+        self.update_widgets();
+        self.widget_manager.status_bar.set_status(status);
+        self.dirty = true;
+    }
+
+    /// Show a directory's contents in the file picker
+    fn list_directory(&mut self, dir: &Path) {
+        if let Some(file_picker) = &mut self.state.file_picker {
+            file_picker.set_listing(picker::list_dir(dir));
+        }
+    }
+
+    /// Pick up added, removed or edited theme files
+    fn reload_themes(&mut self) {
+        debug!("Theme files changed, reloading");
+        let themes_dir = self.themes_dir();
+        self.state
+            .settings
+            .set_themes(styles::theme_names(themes_dir.as_deref()));
+        let error = self.apply_theme().err();
+
+        self.update_widgets();
+        // Outside the Settings view the status bar is the only place an error shows
+        if let Some(err) = error {
+            self.widget_manager
+                .status_bar
+                .set_status(format!("⚠ {}", err));
+        }
+        self.dirty = true;
+    }
+
+    /// Translate a key press into an action and dispatch it
+    fn handle_key_event(&mut self, key: KeyEvent) {
+        if let Some(effects) = state::input(&mut self.state, KeyCombo::from(key)) {
+            self.clear_pending_keys();
+            self.run_effects(effects);
+            return;
+        }
+
+        // The focused widget sees the key before the keymap, unless a chord is under way
+        if self.pending_keys.is_empty() && self.route_to_focus(&terminal_event::Event::Key(key)) {
+            return;
+        }
+
+        self.pending_keys.push(KeyCombo::from(key));
+
+        match self
+            .keymap
+            .resolve(self.state.current_view, &self.pending_keys)
+        {
+            KeyResolution::Action(action) => {
+                self.clear_pending_keys();
+                self.dispatch(action);
+            }
+            KeyResolution::Pending => {
+                let now = self.clock.instant();
+                self.pending_since.get_or_insert(now);
+            }
+            KeyResolution::Unbound => {
+                // A broken chord may still leave a valid single key binding
+                let retry = self.pending_keys.len() > 1;
+                self.clear_pending_keys();
+                if retry {
+                    self.handle_key_event(key);
+                }
+            }
+        }
+    }
+
+    /// Act on a click, drag or wheel movement at the place it happened
+    fn handle_mouse_event(&mut self, mouse: MouseEvent) {
+        trace!("Mouse event {:?}", mouse);
+        // The palette and dialogs cover what is under them, and are closed with the keyboard
+        if self.state.palette.is_some() || !self.state.modals.is_empty() {
+            return;
+        }
+        let hit = self.widget_manager.hit_test(mouse.column, mouse.row);
+
+        match mouse.kind {
+            MouseEventKind::Down(MouseButton::Left) => match hit {
+                Some(Hit::SidebarBorder) => self.resizing_sidebar = true,
+                Some(Hit::SidebarItem(view)) => self.dispatch(Action::Navigate(view)),
+                Some(Hit::Sidebar) => self.focus(FocusId::Sidebar),
+                Some(Hit::Content) => {
+                    self.focus(FocusId::Content);
+                    self.route_to_view(mouse);
+                }
+                Some(Hit::Header | Hit::StatusBar) | None => {}
+            },
+            MouseEventKind::Drag(MouseButton::Left) if self.resizing_sidebar => {
+                let left = self.widget_manager.sidebar_area().x;
+                let width = mouse.column.saturating_sub(left).saturating_add(1);
+                let effects = state::resize_sidebar(&mut self.state, width);
+                self.run_effects(effects);
+            }
+            MouseEventKind::Up(MouseButton::Left) => self.resizing_sidebar = false,
+            MouseEventKind::ScrollUp if hit == Some(Hit::Content) => {
+                self.scroll_content(mouse, Action::ScrollUp)
+            }
+            MouseEventKind::ScrollDown if hit == Some(Hit::Content) => {
+                self.scroll_content(mouse, Action::ScrollDown)
+            }
+            _ => {}
+        }
+    }
+
+    /// Let the view use the wheel itself, e.g. to move a selection, or else scroll it
+    fn scroll_content(&mut self, mouse: MouseEvent, action: Action) {
+        if !self.route_to_view(mouse) {
+            self.dispatch(action);
+        }
+    }
+
+    /// Give a mouse event inside the content area to the current view; whether it was handled
+    fn route_to_view(&mut self, mouse: MouseEvent) -> bool {
+        let view = self.view_manager.get_view(self.state.current_view);
+        match view.handle_event(&terminal_event::Event::Mouse(mouse)) {
+            EventResult::Ignored => false,
+            EventResult::Consumed => {
+                self.dirty = true;
                 true
             }
-            KeyCode::Char('1') => {
-                self.current_view = View::Dashboard;
-                self.update_widgets();
-                false
+            EventResult::Action(action) => {
+                self.dispatch(action);
+                true
+            }
+        }
+    }
+
+    fn focus(&mut self, target: FocusId) {
+        let effects = state::focus(&mut self.state, target);
+        self.run_effects(effects);
+    }
+
+    /// Offer an event to the focused element and then its parents; whether one handled it
+    fn route_to_focus(&mut self, event: &terminal_event::Event) -> bool {
+        for target in self.state.focus.path() {
+            let result = match target {
+                FocusId::Sidebar => self.widget_manager.sidebar.handle_event(event),
+                FocusId::Content => self
+                    .view_manager
+                    .get_view(self.state.current_view)
+                    .handle_event(event),
+                // Views track their own focused child and see the event as the parent
+                FocusId::Child(_) => continue,
+            };
+
+            match result {
+                EventResult::Ignored => continue,
+                EventResult::Consumed => self.dirty = true,
+                EventResult::Action(action) => self.dispatch(action),
+            }
+            return true;
+        }
+        false
+    }
+
+    fn clear_pending_keys(&mut self) {
+        self.pending_keys.clear();
+        self.pending_since = None;
+    }
+
+    /// Run an action through the reducer and carry out its effects
+    pub fn dispatch(&mut self, action: Action) {
+        debug!("Dispatching {:?}", action);
+        let effects = state::update(&mut self.state, action);
+        self.run_effects(effects);
+    }
+
+    fn run_effects(&mut self, effects: Vec<Effect>) {
+        for effect in effects {
+            match effect {
+                Effect::Suspend => {
+                    self.suspend_requested = true;
+                    continue;
+                }
+                Effect::Render => {}
+                Effect::ApplyConfig => self.apply_config(),
+                Effect::SaveConfig => self.save_config(),
+                Effect::SaveLayout => self.save_layout(),
+                Effect::ListDirectory(dir) => self.list_directory(&dir),
+                Effect::LoadData(path) => self.load_data(path),
+                Effect::Export(target, format) => self.export(target, format),
+                Effect::SaveData => self.save_data(),
+                Effect::SaveHistory => self.save_history(),
+            }
+            self.update_widgets();
+            self.dirty = true;
+        }
+    }
+
+    /// Bring the theme and log level in line with the live configuration;
+    /// the layout is picked up by `update_widgets`
+    fn apply_config(&mut self) {
+        let _ = self.apply_theme();
+        self.state.sync_focus();
+        if self.state.config.mouse != self.mouse_capture {
+            self.mouse_capture = self.state.config.mouse;
+            self.mouse_capture_changed = true;
+        }
+        self.state
+            .style_manager
+            .set_color_depth(self.state.config.color_mode.depth());
+        log::set_max_level(self.state.config.log_level.filter());
+    }
+
+    /// Load the configured theme, keeping the current one if it cannot be loaded
+    fn apply_theme(&mut self) -> Result<(), ThemeError> {
+        let themes_dir = self.themes_dir();
+        let spec = &self.state.config.theme;
+        if let Some(watcher) = &self.theme_watcher {
+            watcher.set_active(Theme::locate(spec, themes_dir.as_deref()).ok().flatten());
+        }
+
+        match Theme::resolve(spec, themes_dir.as_deref()) {
+            Ok(theme) => {
+                self.state.style_manager.set_theme(theme);
+                self.state.settings.clear_error(SettingField::Theme);
+                Ok(())
             }
-            KeyCode::Char('2') => {
-                self.current_view = View::Data;
-                self.update_widgets();
-                false
+            Err(err) => {
+                warn!("Cannot apply theme: {}", err);
+                self.state.settings.error = Some((SettingField::Theme, err.to_string()));
+                Err(err)
             }
-            KeyCode::Char('3') => {
-                self.current_view = View::Settings;
-                self.update_widgets();
-                false
+        }
+    }
+
+    fn themes_dir(&self) -> Option<PathBuf> {
+        self.config_dir
+            .as_ref()
+            .map(|dir| dir.join(styles::THEMES_DIR))
+    }
+
+    fn save_config(&mut self) {
+        // Command-line overrides stay out of the file
+        let persisted = self.state.settings.persisted(&self.state.config);
+        let notice = match persisted.save(self.config_dir.as_deref()) {
+            Ok(path) => {
+                self.state
+                    .settings
+                    .mark_saved(&self.state.config, persisted);
+                FormNotice::Saved(path)
             }
-            KeyCode::Char('4') => {
-                self.current_view = View::Help;
-                self.update_widgets();
-                false
+            Err(err) => {
+                warn!("Cannot save settings: {}", err);
+                FormNotice::SaveFailed(err.to_string())
             }
-            KeyCode::Char('t') => {
-                self.style_manager.toggle_theme();
-                self.update_widgets();
-                false
+        };
+
+        // The form's notice is only seen in the Settings view, e.g. when saving from the palette elsewhere
+        if self.state.current_view != ViewId::Settings {
+            match &notice {
+                FormNotice::Saved(path) => {
+                    let message = format!("Saved settings to {}", path.display());
+                    self.widget_manager.show_toast(Toast::success(message));
+                }
+                FormNotice::SaveFailed(error) => self.alert("Cannot save settings", error.clone()),
+                FormNotice::Reverted => {}
             }
-            _ => false,
+        }
+        self.state.settings.notice = Some(notice);
+    }
+
+    /// Write the dashboard layout over what the config file has, so other
+    /// settings changed but not saved stay that way
+    fn save_layout(&mut self) {
+        let mut written = self.state.settings.on_disk.clone();
+        written.dashboard = self.state.config.dashboard.clone();
+        match written.save(self.config_dir.as_deref()) {
+            Ok(path) => {
+                self.state.settings.saved.dashboard = written.dashboard.clone();
+                self.state.settings.on_disk = written;
+                let message = format!("Saved layout to {}", path.display());
+                self.widget_manager.show_toast(Toast::success(message));
+            }
+            Err(err) => {
+                warn!("Cannot save layout: {}", err);
+                self.alert("Cannot save layout", err.to_string());
+            }
+        }
+    }
+
+    /// Keep the commands last run from the palette for the next session
+    fn save_history(&self) {
+        let Some(dir) = &self.config_dir else {
+            return;
+        };
+        if let Err(err) = palette::save_recent(dir, &self.state.recent_commands) {
+            warn!("Cannot save command history: {}", err);
         }
     }
 
     /// Update widgets with current state
     fn update_widgets(&mut self) {
+        debug!("Showing {} view", self.state.current_view.as_str());
+
         // Update sidebar with current view
         self.widget_manager
             .sidebar
-            .set_current_view(self.current_view.as_str().to_string());
+            .set_current_view(self.state.current_view);
+
+        // Update shortcut hints from the live keymap
+        let hints = self.keymap.hints(self.state.current_view);
+        self.widget_manager.sidebar.set_key_hints(&hints);
+        self.widget_manager.set_layout(&self.state.config.layout);
+        self.widget_manager.set_focus(self.state.focus.focused());
+        self.view_manager
+            .settings
+            .set_form(&self.state.config, &self.state.settings);
+        self.view_manager.dashboard.set_layout(
+            &self.state.config.dashboard,
+            self.state.panel,
+            self.state.maximised,
+        );
+        self.view_manager
+            .dashboard
+            .set_data(&self.state.data, &self.state.load);
+
+        self.widget_manager
+            .content
+            .set_scroll(self.state.scroll.get(&self.state.current_view).cloned());
+        let bindings = match &self.state.palette {
+            Some(_) => palette::bindings(&self.keymap, self.state.current_view),
+            None => BTreeMap::new(),
+        };
+        self.widget_manager
+            .palette
+            .set_palette(self.state.palette.as_ref(), bindings);
+        self.widget_manager.modals.set_dialogs(&self.state.modals);
+        let view = self.view_manager.get_view(self.state.current_view);
+        view.set_key_hints(&hints);
 
         // Update status bar
+        let shortcuts: Vec<String> = hints
+            .iter()
+            .filter(|hint| !matches!(hint.action, Action::Navigate(_)))
+            .map(|hint| format!("{} {}", hint.keys_label(), hint.action.description()))
+            .collect();
         let status_text = format!(
-            "View: {} | Press 'q' to quit | 1-4 for navigation | 't' to toggle theme",
-            self.current_view.as_str()
+            "View: {} - {} | {}",
+            view.get_title(),
+            view.get_description(),
+            shortcuts.join(" | ")
         );
         self.widget_manager.status_bar.set_status(status_text);
 
-        // Update content with current view
-        let view_content = self.get_current_view_content();
-        self.widget_manager.content.set_content(view_content);
-    }
-
-    /// Get content for the current view
-    fn get_current_view_content(&self) -> String {
-        match self.current_view {
-            View::Dashboard => "Dashboard content loaded...".to_string(),
-            View::Data => "Data browser content loaded...".to_string(),
-            View::Settings => "Settings content loaded...".to_string(),
-            View::Help => "Help content loaded...".to_string(),
-        }
+        let data = &self.state.data;
+        let matches = (self.state.current_view == ViewId::Data && data.filter().is_some())
+            .then(|| (data.len(), data.dataset().len()));
+        self.widget_manager.status_bar.set_matches(matches);
     }
 
     /// Render the application
-    fn render(&mut self, f: &mut ratatui::Frame) {
+    pub fn render(&mut self, f: &mut ratatui::Frame) {
         let size = f.size();
-        self.widget_manager.render_all(f, size);
-    }
-}
-
-/// Setup terminal for the application
-pub fn setup_terminal() -> Result<Terminal<CrosstermBackend<io::Stdout>>> {
-    enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-    let backend = CrosstermBackend::new(stdout);
-    let terminal = Terminal::new(backend)?;
-    Ok(terminal)
-}
+        // The Data Browser draws straight from the state rather than a copy of it
+        let data = DataContext {
+            picker: self.state.file_picker.as_ref(),
+            filter_bar: self.state.filter_bar.as_ref(),
+            export_prompt: self.state.export_prompt.as_ref(),
+            editor: self.state.cell_editor.as_ref(),
+            ..DataContext::new(&self.state.data, &self.state.load)
+        };
+        let ctx = RenderContext::new(&self.state.style_manager).with_data(data);
+        let view = self.view_manager.get_view(self.state.current_view);
+        self.widget_manager.render_all(f, size, view, &ctx);
 
-/// Restore terminal to normal state
-pub fn restore_terminal(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> Result<()> {
-    disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
-    terminal.show_cursor()?;
-    Ok(())
+        // Paging moves by however many rows fit on screen
+        self.state
+            .data
+            .set_page_size(self.view_manager.data_browser.page_size());
+        // and text scrolls within however much of it there was to draw
+        if let Some(scroll) = self.widget_manager.content.scroll() {
+            self.state
+                .scroll
+                .insert(self.state.current_view, scroll.clone());
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{
+        config::{ColorMode, Config, Overrides},
+        script::{HeadlessBackend, Script},
+        tempdir::TempDir,
+    };
+    use crossterm::event::{KeyCode, KeyModifiers};
+    use ratatui::backend::TestBackend;
+
+    fn render_to_string(app: &mut App) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(100, 40)).unwrap();
+        terminal.draw(|f| app.render(f)).unwrap();
+        terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect()
+    }
 
     #[test]
     fn test_app_creation() {
-        let app = App::new();
-        assert_eq!(app.current_view, View::Dashboard);
-        assert!(!app.should_quit);
+        let app = App::default();
+        assert_eq!(app.state.current_view, ViewId::Dashboard);
+        assert!(!app.state.should_quit);
     }
 
     #[test]
-    fn test_view_as_str() {
-        assert_eq!(View::Dashboard.as_str(), "dashboard");
-        assert_eq!(View::Data.as_str(), "data");
-        assert_eq!(View::Settings.as_str(), "settings");
-        assert_eq!(View::Help.as_str(), "help");
+    fn test_app_starts_from_given_state() {
+        let state = AppState {
+            current_view: ViewId::Help,
+            ..AppState::default()
+        };
+        let mut app = App::with_state(Keymap::default(), state);
+
+        let screen = render_to_string(&mut app);
+        assert!(screen.contains("Navigation:"));
+        assert!(!screen.contains("Sampling system metrics"));
     }
 
     #[test]
     fn test_view_equality() {
-        assert_eq!(View::Dashboard, View::Dashboard);
-        assert_ne!(View::Dashboard, View::Data);
+        assert_eq!(ViewId::Dashboard, ViewId::Dashboard);
+        assert_ne!(ViewId::Dashboard, ViewId::Data);
+    }
+
+    #[test]
+    fn test_navigation_renders_current_view() {
+        let mut app = App::default();
+        assert!(render_to_string(&mut app).contains("Sampling system metrics"));
+
+        app.dispatch(Action::Navigate(ViewId::Data));
+        assert_eq!(app.state.current_view, ViewId::Data);
+        assert!(render_to_string(&mut app).contains("Project Alpha"));
+
+        app.dispatch(Action::Navigate(ViewId::Help));
+        assert!(render_to_string(&mut app).contains("Help & Documentation"));
+    }
+
+    #[test]
+    fn test_quit_key_stops_app() {
+        let mut app = App::default();
+        app.handle_key_event(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL));
+        assert!(app.state.should_quit);
+    }
+
+    #[test]
+    fn test_termination_signals_quit() {
+        let mut app = App::default();
+        app.handle_event(Event::Signal(Signal::Terminate));
+        assert!(app.state.should_quit);
     }
 
     #[test]
-    fn test_get_current_view_content() {
-        let app = App::new();
+    fn test_suspend_is_deferred_to_run_loop() {
+        let mut app = App::default();
+        app.handle_key_event(KeyEvent::new(KeyCode::Char('z'), KeyModifiers::CONTROL));
+        assert!(app.suspend_requested);
+    }
+
+    #[test]
+    fn test_pending_chord_expires_on_tick() {
+        let mut app = App::default();
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('g')));
+        app.pending_since = Some(Instant::now() - CHORD_TIMEOUT);
+        app.handle_event(Event::Tick);
+
+        assert!(app.pending_keys.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_run_redraws_only_when_dirty() {
+        let mut app = App::default();
+        let mut terminal = Terminal::new(TestBackend::new(100, 40)).unwrap();
+        let mut events = EventLoop::without_input(Duration::from_secs(60), Duration::from_secs(60));
+
+        events.push(Event::Render);
+        events.push(Event::Key(KeyEvent::from(KeyCode::Char('4'))));
+        events.push(Event::Render);
+        events.push(Event::Key(KeyEvent::from(KeyCode::Char('q'))));
+        app.run(&mut terminal, &mut events).await.unwrap();
+
+        assert!(!app.dirty);
+        let screen: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+        assert!(screen.contains("Help & Documentation"));
+    }
+
+    #[tokio::test]
+    async fn test_scripts_run_headless_and_stop_at_a_failed_expectation() {
+        let run = |script: &str| {
+            let script: Script = script.parse().unwrap();
+            async move {
+                let mut app = App {
+                    headless: true,
+                    ..App::default()
+                };
+                let backend = HeadlessBackend::new(80, 24);
+                let mut terminal = Terminal::new(backend.clone()).unwrap();
+                let mut events = EventLoop::scripted(
+                    script,
+                    backend.clone(),
+                    Duration::from_secs(60),
+                    Duration::from_secs(60),
+                );
+                let result = app.run(&mut terminal, &mut events).await;
+                (result, backend.screen())
+            }
+        };
+
+        let (result, screen) =
+            run("press 2\nexpect text 'Data Browser' in content\nresize 100x30\npress Ctrl+Z")
+                .await;
+        result.unwrap();
+        assert_eq!(screen.lines().count(), 30);
+
+        let (result, screen) = run("press 4\nexpect text 'Sales by region' in content").await;
+        let err = result.unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<ScriptError>(),
+            Some(ScriptError::Failed { line: 2, .. })
+        ));
+        assert!(screen.contains("Help & Documentation"));
+    }
+
+    /// Handle task messages until the data file is no longer loading; how many progress reports came
+    async fn finish_loading(app: &mut App, events: &mut EventLoop, path: &Path) -> usize {
+        let mut reports = 0;
+        while app.state.load.is_loading(path) {
+            if let Some(Event::Task(message)) = events.next().await {
+                reports += matches!(message, TaskMessage::DataProgress { .. }) as usize;
+                app.handle_task_message(message);
+            }
+        }
+        reports
+    }
+
+    #[tokio::test]
+    async fn test_data_file_loads_in_background() {
+        let dir = TempDir::new();
+        let path = dir.join("rows.csv");
+        let rows: String = (0..2500).map(|i| format!("{},row {}\n", i, i)).collect();
+        std::fs::write(&path, format!("id,name\n{}", rows)).unwrap();
 
-        let dashboard_content = match View::Dashboard {
-            View::Dashboard => "Dashboard content loaded...".to_string(),
-            _ => "".to_string(),
+        let mut app = App::default();
+        let mut events = EventLoop::without_input(Duration::from_secs(60), Duration::from_secs(60));
+        app.tasks = Some(events.sender());
+        app.dispatch(Action::Navigate(ViewId::Data));
+
+        app.run_effects(vec![Effect::LoadData(path.clone())]);
+        assert!(render_to_string(&mut app).contains("Loading rows.csv"));
+        assert!(finish_loading(&mut app, &mut events, &path).await >= 2);
+        assert_eq!(app.state.data.len(), 2500);
+        assert!(render_to_string(&mut app).contains("rows.csv · Row 1 of 2500"));
+
+        // A file that cannot be read leaves the rows in place
+        let missing = dir.join("missing.csv");
+        app.run_effects(vec![Effect::LoadData(missing.clone())]);
+        finish_loading(&mut app, &mut events, &missing).await;
+        assert!(matches!(app.state.load, LoadStatus::Failed { .. }));
+        assert_eq!(app.state.data.len(), 2500);
+        assert!(render_to_string(&mut app).contains("⚠ failed to read"));
+    }
+
+    #[tokio::test]
+    async fn test_edits_are_saved_back_and_guard_quitting() {
+        let dir = TempDir::new();
+        let path = dir.join("scores.csv");
+        std::fs::write(&path, "name,score\nada,1\nbob,2\n").unwrap();
+
+        let mut app = App::default();
+        let mut events = EventLoop::without_input(Duration::from_secs(60), Duration::from_secs(60));
+        app.tasks = Some(events.sender());
+        app.dispatch(Action::Navigate(ViewId::Data));
+        app.run_effects(vec![Effect::LoadData(path.clone())]);
+        finish_loading(&mut app, &mut events, &path).await;
+
+        let press = |app: &mut App, code| {
+            app.handle_event(Event::Key(KeyEvent::new(code, KeyModifiers::NONE)));
         };
-        assert_eq!(dashboard_content, "Dashboard content loaded...");
+        press(&mut app, KeyCode::Char('l'));
+        press(&mut app, KeyCode::Enter);
+        press(&mut app, KeyCode::Char('0'));
+        press(&mut app, KeyCode::Enter);
+        assert!(render_to_string(&mut app).contains("1 rows changed"));
+
+        // Quitting asks what to do with the edits; saving them quits once they are written
+        press(&mut app, KeyCode::Char('q'));
+        assert!(!app.state.should_quit);
+        let screen = render_to_string(&mut app);
+        assert!(screen.contains("1 rows have unsaved changes."));
+        assert!(screen.contains("Save and quit"));
+
+        press(&mut app, KeyCode::Enter);
+        finish_task(&mut app, &mut events, |message| {
+            matches!(message, TaskMessage::DataSaved { .. })
+        })
+        .await;
+        assert!(render_to_string(&mut app).contains("Saved 2 rows to"));
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "name,score\nada,10\nbob,2\n"
+        );
+        assert!(!app.state.data.has_changes());
+        assert!(app.state.should_quit);
+    }
+
+    /// Handle task messages until one that `done` picks out has been handled
+    async fn finish_task(app: &mut App, events: &mut EventLoop, done: fn(&TaskMessage) -> bool) {
+        while let Some(event) = events.next().await {
+            if let Event::Task(message) = event {
+                let done = done(&message);
+                app.handle_task_message(message);
+                if done {
+                    return;
+                }
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_export_writes_in_background_and_confirms() {
+        let dir = TempDir::new();
+        let path = dir.join("active.md");
+
+        let mut app = App::default();
+        let mut events = EventLoop::without_input(Duration::from_secs(60), Duration::from_secs(60));
+        app.tasks = Some(events.sender());
+        app.dispatch(Action::Navigate(ViewId::Data));
+        app.state.data.toggle_mark();
+
+        app.run_effects(vec![Effect::Export(
+            ExportTarget::File(path.clone()),
+            ExportFormat::Markdown,
+        )]);
+        finish_task(&mut app, &mut events, |message| {
+            matches!(message, TaskMessage::Exported { .. })
+        })
+        .await;
+        let screen = render_to_string(&mut app);
+        assert!(screen.contains("Exported 1 rows to"));
+        assert!(screen.contains("active.md"));
+        assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 3);
+
+        // A file that cannot be written is reported in a dialog, until it is read
+        let missing = dir.join("missing").join("rows.csv");
+        app.run_effects(vec![Effect::Export(
+            ExportTarget::File(missing),
+            ExportFormat::Csv,
+        )]);
+        finish_task(&mut app, &mut events, |message| {
+            matches!(message, TaskMessage::Exported { .. })
+        })
+        .await;
+        let screen = render_to_string(&mut app);
+        assert!(screen.contains("Cannot export"));
+        assert!(screen.contains("failed to write"));
+        app.handle_event(Event::Key(KeyEvent::new(
+            KeyCode::Enter,
+            KeyModifiers::NONE,
+        )));
+        assert!(!render_to_string(&mut app).contains("Cannot export"));
+
+        app.run_effects(vec![Effect::Export(
+            ExportTarget::Stdout,
+            ExportFormat::Ndjson,
+        )]);
+        assert!(render_to_string(&mut app).contains("1 rows will be printed as NDJSON on exit"));
+        assert_eq!(app.take_stdout_exports().len(), 1);
+    }
+
+    #[test]
+    fn test_broken_chord_falls_back_to_single_key() {
+        let mut app = App::default();
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('g')));
+        assert_eq!(app.state.current_view, ViewId::Dashboard);
+
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('3')));
+        assert_eq!(app.state.current_view, ViewId::Settings);
+    }
+
+    #[test]
+    fn test_dashboard_panels_are_rearranged_and_saved() {
+        let config_dir = TempDir::new();
+        let mut app = App {
+            config_dir: Some(config_dir.to_path_buf()),
+            ..App::default()
+        };
+
+        // Select the network panel and move it past the disk panel
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('l')));
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('l')));
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('L')));
+        let network = &app.state.config.dashboard.panels[2];
+        assert_eq!((network.row, network.column), (1, 1));
+
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('m')));
+        let screen = render_to_string(&mut app);
+        assert!(screen.contains(" Network "));
+        assert!(!screen.contains(" Memory "));
+
+        // Only the layout is saved, not other settings changed since
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('t')));
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('s')));
+        assert!(render_to_string(&mut app).contains("Saved layout to"));
+        let saved = Config::load(Some(&config_dir)).unwrap();
+        assert_eq!(saved.dashboard, app.state.config.dashboard);
+        assert_eq!(saved.theme, Config::default().theme);
+        assert_ne!(app.state.config.theme, saved.theme);
+        assert!(app.state.settings.is_dirty(&app.state.config));
+
+        // Saving settings from elsewhere says so
+        app.dispatch(Action::Navigate(ViewId::Help));
+        app.dispatch(Action::SaveConfig);
+        assert!(render_to_string(&mut app).contains("Saved settings to"));
+        assert_eq!(Config::load(Some(&config_dir)).unwrap(), app.state.config);
+    }
+
+    #[test]
+    fn test_command_line_overrides_are_not_saved() {
+        let config_dir = TempDir::new();
+        let on_disk = Config {
+            theme: "dark".to_string(),
+            ..Config::default()
+        };
+        on_disk.save(Some(&config_dir)).unwrap();
+
+        // As started with `--theme light --no-mouse`
+        let mut state = AppState::with_config(Config::load(Some(&config_dir)).unwrap());
+        let overrides = Overrides {
+            theme: Some("light".to_string()),
+            mouse: Some(false),
+        };
+        state.settings.set_overrides(&mut state.config, overrides);
+        let mut app = App {
+            config_dir: Some(config_dir.to_path_buf()),
+            ..App::with_state(Keymap::default(), state)
+        };
+        app.dispatch(Action::Navigate(ViewId::Settings));
+        assert_eq!(app.state.config.theme, "light");
+        assert!(!render_to_string(&mut app).contains("● Unsaved changes"));
+
+        // Settings changed since are saved, the overridden ones are not
+        app.state.config.layout.compact = true;
+        app.dispatch(Action::SaveConfig);
+        let saved = Config::load(Some(&config_dir)).unwrap();
+        assert_eq!(
+            (saved.theme.as_str(), saved.mouse, saved.layout.compact),
+            ("dark", true, true)
+        );
+        assert!(!app.state.settings.is_dirty(&app.state.config));
+        app.dispatch(Action::RevertConfig);
+        assert_eq!(app.state.config.theme, "light");
+    }
+
+    #[test]
+    fn test_settings_apply_live_and_save() {
+        let config_dir = TempDir::new();
+        let mut app = App {
+            config_dir: Some(config_dir.to_path_buf()),
+            ..App::default()
+        };
+        app.state.settings.themes = vec!["light".to_string(), "dark".to_string()];
+        app.dispatch(Action::Navigate(ViewId::Settings));
+
+        // Theme is the first field; Enter opens the picker and the highlighted theme applies at once
+        app.handle_key_event(KeyEvent::from(KeyCode::Enter));
+        app.handle_key_event(KeyEvent::from(KeyCode::Down));
+        assert_eq!(app.state.style_manager.theme(), &Theme::dark());
+        app.handle_key_event(KeyEvent::from(KeyCode::Enter));
+        assert!(render_to_string(&mut app).contains("● Unsaved changes"));
+
+        // While a number is being typed, 'q' is text rather than Quit
+        app.handle_key_event(KeyEvent::from(KeyCode::Tab));
+        app.handle_key_event(KeyEvent::from(KeyCode::Tab));
+        app.handle_key_event(KeyEvent::from(KeyCode::Enter));
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('q')));
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('4')));
+        app.handle_key_event(KeyEvent::from(KeyCode::Enter));
+        assert!(!app.state.should_quit);
+        assert_eq!(app.state.config.layout.sidebar_width, 20);
+        assert!(app.state.settings.error.is_some());
+        app.handle_key_event(KeyEvent::from(KeyCode::Esc));
+        assert!(!app.state.should_quit);
+
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('s')));
+        let saved = std::fs::read_to_string(config_dir.join("config.toml")).unwrap();
+        assert!(saved.contains("theme = \"dark\""));
+        assert!(!app.state.settings.is_dirty(&app.state.config));
+    }
+
+    #[test]
+    fn test_help_is_searched_and_scrolls_to_matches() {
+        let mut app = App::default();
+        app.dispatch(Action::Navigate(ViewId::Help));
+        render_to_string(&mut app);
+        assert_eq!(app.state.scroll[&ViewId::Help].offset, 0);
+
+        for c in "/number keys".chars() {
+            app.handle_key_event(KeyEvent::from(KeyCode::Char(c)));
+        }
+        app.handle_key_event(KeyEvent::from(KeyCode::Enter));
+        let screen = render_to_string(&mut app);
+        assert!(screen.contains("/number keys  1 of 1"));
+        assert!(screen.contains("Use number keys"));
+        assert!(app.state.scroll[&ViewId::Help].offset > 0);
+
+        // Keys are bindings again once the query is kept
+        app.handle_key_event(KeyEvent::from(KeyCode::Home));
+        render_to_string(&mut app);
+        assert_eq!(app.state.scroll[&ViewId::Help].offset, 0);
+        assert_eq!(app.state.scroll[&ViewId::Help].query, "number keys");
+    }
+
+    #[test]
+    fn test_focused_sidebar_gets_keys_first() {
+        let mut app = App::default();
+        app.handle_key_event(KeyEvent::from(KeyCode::BackTab));
+        assert_eq!(app.state.focus.focused(), FocusId::Sidebar);
+
+        // Down moves the sidebar cursor rather than scrolling, and Enter opens the view under it
+        app.handle_key_event(KeyEvent::from(KeyCode::Down));
+        assert!(app.state.scroll.values().all(|scroll| scroll.offset == 0));
+        app.handle_key_event(KeyEvent::from(KeyCode::Down));
+        app.handle_key_event(KeyEvent::from(KeyCode::Enter));
+        assert_eq!(app.state.current_view, ViewId::Settings);
+        assert_eq!(app.state.focus.focused(), FocusId::Child(0));
+
+        // Keys the sidebar does not handle bubble to the keymap
+        app.handle_key_event(KeyEvent::from(KeyCode::BackTab));
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('4')));
+        assert_eq!(app.state.current_view, ViewId::Help);
+    }
+
+    #[test]
+    fn test_focus_is_restored_when_returning_to_a_view() {
+        let mut app = App::default();
+        app.dispatch(Action::Navigate(ViewId::Settings));
+        app.handle_key_event(KeyEvent::from(KeyCode::Tab));
+        app.handle_key_event(KeyEvent::from(KeyCode::Tab));
+        assert_eq!(app.state.settings.focused(), SettingField::SidebarWidth);
+
+        app.dispatch(Action::Navigate(ViewId::Help));
+        assert_eq!(app.state.focus.focused(), FocusId::Content);
+        app.dispatch(Action::Navigate(ViewId::Settings));
+        assert_eq!(app.state.focus.focused(), FocusId::Child(2));
+
+        // Tab from the last field wraps round to the sidebar
+        for _ in 2..SettingField::ALL.len() {
+            app.handle_key_event(KeyEvent::from(KeyCode::Tab));
+        }
+        assert_eq!(app.state.focus.focused(), FocusId::Sidebar);
+    }
+
+    fn mouse(kind: MouseEventKind, column: u16, row: u16) -> Event {
+        Event::Mouse(MouseEvent {
+            kind,
+            column,
+            row,
+            modifiers: KeyModifiers::NONE,
+        })
+    }
+
+    #[test]
+    fn test_mouse_navigates_scrolls_and_selects() {
+        let mut app = App::default();
+        render_to_string(&mut app);
+
+        // Sidebar items start below the header, border and sidebar heading
+        app.handle_event(mouse(MouseEventKind::Down(MouseButton::Left), 5, 4));
+        assert_eq!(app.state.focus.focused(), FocusId::Sidebar);
+        app.handle_event(mouse(MouseEventKind::Down(MouseButton::Left), 5, 9));
+        assert_eq!(app.state.current_view, ViewId::Help);
+
+        render_to_string(&mut app);
+        app.handle_event(mouse(MouseEventKind::ScrollDown, 50, 20));
+        app.handle_event(mouse(MouseEventKind::ScrollDown, 50, 20));
+        assert_eq!(app.state.scroll[&ViewId::Help].offset, 2);
+        // The wheel over the sidebar does nothing
+        app.handle_event(mouse(MouseEventKind::ScrollUp, 5, 20));
+        assert_eq!(app.state.scroll[&ViewId::Help].offset, 2);
+
+        // Clicking the second data row, below the table header, selects it
+        app.dispatch(Action::Navigate(ViewId::Data));
+        app.handle_key_event(KeyEvent::from(KeyCode::BackTab));
+        render_to_string(&mut app);
+        app.handle_event(mouse(MouseEventKind::Down(MouseButton::Left), 30, 6));
+        assert_eq!(app.state.data.cursor(), 1);
+        assert_eq!(app.state.focus.focused(), FocusId::Content);
+    }
+
+    #[test]
+    fn test_dragging_sidebar_border_resizes_it() {
+        let mut app = App::default();
+        render_to_string(&mut app);
+
+        app.handle_event(mouse(MouseEventKind::Down(MouseButton::Left), 19, 20));
+        app.handle_event(mouse(MouseEventKind::Drag(MouseButton::Left), 29, 20));
+        assert_eq!(app.state.config.layout.sidebar_width, 30);
+        app.handle_event(mouse(MouseEventKind::Drag(MouseButton::Left), 90, 20));
+        assert_eq!(app.state.config.layout.sidebar_width, 60);
+        assert!(app.state.settings.is_dirty(&app.state.config));
+
+        app.handle_event(mouse(MouseEventKind::Up(MouseButton::Left), 90, 20));
+        app.handle_event(mouse(MouseEventKind::Drag(MouseButton::Left), 40, 20));
+        assert_eq!(app.state.config.layout.sidebar_width, 60);
+    }
+
+    #[test]
+    fn test_mouse_setting_is_applied_by_run_loop() {
+        let mut app = App::default();
+        app.state.config.mouse = false;
+        app.apply_config();
+
+        assert!(!app.mouse_capture);
+        assert!(app.mouse_capture_changed);
+    }
+
+    #[test]
+    fn test_toggle_theme_recolours_every_widget() {
+        let mut app = App::default();
+        // Toggling applies the whole config, so keep the colours as written
+        app.state.config.color_mode = ColorMode::TrueColor;
+        let mut terminal = Terminal::new(TestBackend::new(100, 40)).unwrap();
+        let (light, dark) = (Theme::light(), Theme::dark());
+
+        terminal.draw(|f| app.render(f)).unwrap();
+        let before = terminal.backend().buffer().clone();
+        app.dispatch(Action::ToggleTheme);
+        terminal.draw(|f| app.render(f)).unwrap();
+        let after = terminal.backend().buffer();
+
+        // Header, sidebar, content and status bar
+        let cells = [
+            ((0, 0), true),
+            ((15, 5), true),
+            ((50, 10), false),
+            ((50, 39), false),
+        ];
+        for ((x, y), foreground) in cells {
+            let (old, new) = (before.get(x, y), after.get(x, y));
+            if foreground {
+                assert_eq!(
+                    (old.fg, new.fg),
+                    (light.foreground, dark.foreground),
+                    "cell {},{}",
+                    x,
+                    y
+                );
+            } else {
+                assert_eq!(
+                    (old.bg, new.bg),
+                    (light.background, dark.background),
+                    "cell {},{}",
+                    x,
+                    y
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_edited_theme_file_reloads() {
+        let config_dir = TempDir::new();
+        let themes_dir = config_dir.join(styles::THEMES_DIR);
+        std::fs::create_dir_all(&themes_dir).unwrap();
+        let theme = Theme {
+            primary: ratatui::style::Color::Rgb(9, 9, 9),
+            ..Theme::dark()
+        };
+        let path = themes_dir.join("custom.toml");
+        std::fs::write(&path, toml::to_string(&theme).unwrap()).unwrap();
+
+        let mut app = App {
+            config_dir: Some(config_dir.to_path_buf()),
+            ..App::default()
+        };
+        app.state.config.theme = "custom".to_string();
+        app.apply_config();
+        assert_eq!(app.state.style_manager.theme(), &theme);
+
+        let edited = Theme {
+            primary: ratatui::style::Color::Rgb(1, 2, 3),
+            ..theme
+        };
+        std::fs::write(&path, toml::to_string(&edited).unwrap()).unwrap();
+        app.handle_event(Event::Task(TaskMessage::ThemesChanged));
+        assert_eq!(app.state.style_manager.theme(), &edited);
+        assert!(app.state.settings.themes.contains(&"custom".to_string()));
+
+        // A broken edit keeps the last good theme and reports the error
+        std::fs::write(&path, "primary = 12").unwrap();
+        app.handle_event(Event::Task(TaskMessage::ThemesChanged));
+        assert_eq!(app.state.style_manager.theme(), &edited);
+        assert!(app.state.settings.error.is_some());
+
+        // Toggling leaves the custom theme for the built-in light one, which edits to the file no longer touch
+        app.dispatch(Action::ToggleTheme);
+        assert_eq!(app.state.config.theme, "light");
+        assert_eq!(app.state.style_manager.theme(), &Theme::light());
+        std::fs::write(&path, toml::to_string(&edited).unwrap()).unwrap();
+        app.handle_event(Event::Task(TaskMessage::ThemesChanged));
+        assert_eq!(app.state.style_manager.theme(), &Theme::light());
     }
 }
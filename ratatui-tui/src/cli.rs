@@ -0,0 +1,161 @@
+use std::{path::PathBuf, time::Duration};
+
+use clap::{
+    builder::{PossibleValuesParser, TypedValueParser},
+    Parser, Subcommand,
+};
+
+use crate::{event::DEFAULT_TICK_RATE, views::ViewId};
+
+/// A modern Terminal User Interface built with Rust and Ratatui
+#[derive(Debug, Parser)]
+#[command(version, about)]
+pub struct Cli {
+    /// Data file to open in the Data Browser: .csv, .json (an array of objects), .ndjson or .jsonl
+    #[arg(value_name = "FILE")]
+    pub file: Option<PathBuf>,
+
+    /// View to show on startup
+    #[arg(long, value_name = "NAME", value_parser = view_parser())]
+    pub view: Option<ViewId>,
+
+    /// Theme to start with: light, dark, a theme name or a path to a theme file
+    #[arg(long, value_name = "THEME")]
+    pub theme: Option<String>,
+
+    /// Read configuration (config.toml, keymap.toml, themes/) from this directory
+    #[arg(long, value_name = "DIR")]
+    pub config: Option<PathBuf>,
+
+    /// Append log output to this file instead of discarding it
+    #[arg(long, value_name = "PATH")]
+    pub log_file: Option<PathBuf>,
+
+    /// Leave mouse events to the terminal emulator, e.g. to allow text selection
+    #[arg(long)]
+    pub no_mouse: bool,
+
+    /// Milliseconds between periodic state updates
+    #[arg(
+        long,
+        value_name = "MS",
+        default_value_t = DEFAULT_TICK_RATE.as_millis() as u64,
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    pub tick_rate: u64,
+
+    /// Run without a terminal, playing the key, mouse, resize, wait and expect
+    /// steps in this file; exits non-zero if an expectation fails
+    #[arg(long, value_name = "FILE")]
+    pub script: Option<PathBuf>,
+
+    /// Record the session: what is drawn to this asciicast v2 file, and the
+    /// input to a script next to it (.script) that replays it with --script
+    #[arg(long, value_name = "FILE", conflicts_with = "script")]
+    pub record: Option<PathBuf>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+/// Commands that print information and exit without starting the interface
+#[derive(Debug, Subcommand, PartialEq)]
+pub enum Command {
+    /// Inspect available themes
+    #[command(subcommand)]
+    Themes(ThemesCommand),
+    /// Inspect key bindings
+    #[command(subcommand)]
+    Keymap(KeymapCommand),
+}
+
+#[derive(Debug, Subcommand, PartialEq)]
+pub enum ThemesCommand {
+    /// List built-in and installed themes
+    List,
+}
+
+#[derive(Debug, Subcommand, PartialEq)]
+pub enum KeymapCommand {
+    /// Print the effective key bindings in keymap.toml format
+    Dump,
+}
+
+impl Cli {
+    pub fn tick_rate(&self) -> Duration {
+        Duration::from_millis(self.tick_rate)
+    }
+}
+
+fn view_parser() -> impl TypedValueParser<Value = ViewId> {
+    PossibleValuesParser::new(ViewId::ALL.map(|view| view.as_str()))
+        .try_map(|name| name.parse::<ViewId>())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn test_cli_definition() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn test_defaults() {
+        let cli = Cli::try_parse_from(["ratatui-tui"]).unwrap();
+
+        assert_eq!(cli.view, None);
+        assert_eq!(cli.file, None);
+        assert!(!cli.no_mouse);
+        assert_eq!(cli.tick_rate(), DEFAULT_TICK_RATE);
+        assert_eq!(cli.script, None);
+        assert_eq!(cli.record, None);
+        assert_eq!(cli.command, None);
+    }
+
+    #[test]
+    fn test_startup_options() {
+        let cli = Cli::try_parse_from([
+            "ratatui-tui",
+            "--view",
+            "settings",
+            "--theme",
+            "dark",
+            "--no-mouse",
+            "--tick-rate",
+            "100",
+            "--script",
+            "smoke.script",
+            "sales.csv",
+        ])
+        .unwrap();
+
+        assert_eq!(cli.view, Some(ViewId::Settings));
+        assert_eq!(cli.theme.as_deref(), Some("dark"));
+        assert!(cli.no_mouse);
+        assert_eq!(cli.tick_rate(), Duration::from_millis(100));
+        assert_eq!(cli.file, Some(PathBuf::from("sales.csv")));
+        assert_eq!(cli.script, Some(PathBuf::from("smoke.script")));
+    }
+
+    #[test]
+    fn test_invalid_options_are_rejected() {
+        assert!(Cli::try_parse_from(["ratatui-tui", "--view", "nowhere"]).is_err());
+        assert!(Cli::try_parse_from(["ratatui-tui", "--tick-rate", "0"]).is_err());
+        assert!(
+            Cli::try_parse_from(["ratatui-tui", "--record", "a.cast", "--script", "b.script"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_subcommands() {
+        let cli = Cli::try_parse_from(["ratatui-tui", "themes", "list"]).unwrap();
+        assert_eq!(cli.command, Some(Command::Themes(ThemesCommand::List)));
+
+        let cli = Cli::try_parse_from(["ratatui-tui", "keymap", "dump"]).unwrap();
+        assert_eq!(cli.command, Some(Command::Keymap(KeymapCommand::Dump)));
+    }
+}
@@ -0,0 +1,95 @@
+use std::{fmt, time::Instant};
+#[cfg(test)]
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use chrono::{DateTime, Local};
+
+/// Where the application reads the time from
+pub trait Clock: fmt::Debug + Send {
+    /// Wall-clock time, as shown on the dashboard
+    fn now(&self) -> DateTime<Local>;
+
+    /// Monotonic time, for sampling intervals and timeouts
+    fn instant(&self) -> Instant;
+}
+
+/// The system's clocks
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Local> {
+        Local::now()
+    }
+
+    fn instant(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// A clock that only moves when told to, so that what is drawn does not depend
+/// on when it is drawn; clones share the time
+#[cfg(test)]
+#[derive(Debug, Clone)]
+pub struct FixedClock {
+    start: DateTime<Local>,
+    origin: Instant,
+    elapsed: Arc<Mutex<Duration>>,
+}
+
+#[cfg(test)]
+impl FixedClock {
+    pub fn new(start: DateTime<Local>) -> Self {
+        Self {
+            start,
+            origin: Instant::now(),
+            elapsed: Arc::default(),
+        }
+    }
+
+    pub fn advance(&self, by: Duration) {
+        *self
+            .elapsed
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) += by;
+    }
+
+    fn elapsed(&self) -> Duration {
+        *self
+            .elapsed
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+impl Clock for FixedClock {
+    fn now(&self) -> DateTime<Local> {
+        self.start + self.elapsed()
+    }
+
+    fn instant(&self) -> Instant {
+        self.origin + self.elapsed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_fixed_clock_moves_when_advanced() {
+        let start = Local.with_ymd_and_hms(2026, 10, 17, 9, 5, 3).unwrap();
+        let clock = FixedClock::new(start);
+        let instant = clock.instant();
+        assert_eq!(clock.now(), start);
+
+        clock.clone().advance(Duration::from_secs(2));
+        assert_eq!(clock.now().format("%H:%M:%S").to_string(), "09:05:05");
+        assert_eq!(clock.instant() - instant, Duration::from_secs(2));
+    }
+}
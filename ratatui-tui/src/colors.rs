@@ -0,0 +1,250 @@
+use std::env;
+
+use ratatui::style::Color;
+
+/// How many colours the terminal can show
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ColorDepth {
+    /// No colour at all; styles keep only their modifiers
+    Mono,
+    /// The 16 ANSI colours, whose exact shades the terminal decides
+    Ansi16,
+    /// The xterm 256-colour palette
+    Ansi256,
+    /// 24-bit RGB
+    TrueColor,
+}
+
+/// xterm's default RGB values for the 16 ANSI colours, in index order
+const ANSI_16: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::Red, (205, 0, 0)),
+    (Color::Green, (0, 205, 0)),
+    (Color::Yellow, (205, 205, 0)),
+    (Color::Blue, (0, 0, 238)),
+    (Color::Magenta, (205, 0, 205)),
+    (Color::Cyan, (0, 205, 205)),
+    (Color::Gray, (229, 229, 229)),
+    (Color::DarkGray, (127, 127, 127)),
+    (Color::LightRed, (255, 0, 0)),
+    (Color::LightGreen, (0, 255, 0)),
+    (Color::LightYellow, (255, 255, 0)),
+    (Color::LightBlue, (92, 92, 255)),
+    (Color::LightMagenta, (255, 0, 255)),
+    (Color::LightCyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+/// Channel values of the 6x6x6 colour cube at indexes 16-231
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+impl ColorDepth {
+    /// Detect the depth of the terminal from the environment
+    pub fn detect() -> Self {
+        Self::from_env(|name| env::var(name).ok())
+    }
+
+    /// Detect the depth from environment variables looked up with `var`
+    ///
+    /// `NO_COLOR` wins over everything, then `COLORTERM` announcing 24-bit
+    /// support, then the capabilities implied by `TERM`.
+    pub fn from_env(var: impl Fn(&str) -> Option<String>) -> Self {
+        // https://no-color.org: any non-empty value disables colour
+        if var("NO_COLOR").is_some_and(|value| !value.is_empty()) {
+            return ColorDepth::Mono;
+        }
+
+        let colorterm = var("COLORTERM").unwrap_or_default().to_ascii_lowercase();
+        if colorterm == "truecolor" || colorterm == "24bit" {
+            return ColorDepth::TrueColor;
+        }
+
+        let term = var("TERM").unwrap_or_default().to_ascii_lowercase();
+        if term == "dumb" {
+            ColorDepth::Mono
+        } else if term.ends_with("-direct") || term.contains("truecolor") {
+            ColorDepth::TrueColor
+        } else if term.contains("256color") {
+            ColorDepth::Ansi256
+        } else {
+            ColorDepth::Ansi16
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ColorDepth::Mono => "mono",
+            ColorDepth::Ansi16 => "16",
+            ColorDepth::Ansi256 => "256",
+            ColorDepth::TrueColor => "truecolor",
+        }
+    }
+
+    /// The closest colour this depth can show; `Color::Reset` when it shows none
+    pub fn quantize(&self, color: Color) -> Color {
+        if color == Color::Reset {
+            return color;
+        }
+
+        match self {
+            ColorDepth::TrueColor => color,
+            ColorDepth::Ansi256 => match color {
+                Color::Rgb(r, g, b) => Color::Indexed(nearest_256((r, g, b))),
+                color => color,
+            },
+            ColorDepth::Ansi16 => match color {
+                Color::Rgb(r, g, b) => nearest_16((r, g, b)),
+                Color::Indexed(index) if index < 16 => ANSI_16[index as usize].0,
+                Color::Indexed(index) => nearest_16(indexed_rgb(index)),
+                color => color,
+            },
+            ColorDepth::Mono => Color::Reset,
+        }
+    }
+}
+
+/// The closest of the 16 ANSI colours
+fn nearest_16(rgb: (u8, u8, u8)) -> Color {
+    ANSI_16
+        .iter()
+        .min_by_key(|(_, candidate)| distance(rgb, *candidate))
+        .map(|(color, _)| *color)
+        .unwrap_or(Color::Reset)
+}
+
+/// The index of the closest colour in the cube or grey ramp of the 256-colour palette
+///
+/// Indexes 0-15 are skipped because terminals let users redefine them.
+fn nearest_256(rgb: (u8, u8, u8)) -> u8 {
+    let level = |value: u8| {
+        (0..CUBE_LEVELS.len())
+            .min_by_key(|&i| (CUBE_LEVELS[i] as i32 - value as i32).abs())
+            .unwrap_or(0) as u8
+    };
+    let (r, g, b) = (level(rgb.0), level(rgb.1), level(rgb.2));
+    let cube = 16 + 36 * r + 6 * g + b;
+
+    // Grey ramp 232-255 runs from 8 to 238 in steps of 10
+    let average = (rgb.0 as u32 + rgb.1 as u32 + rgb.2 as u32) / 3;
+    let grey = 232 + (average.saturating_sub(3) / 10).min(23) as u8;
+
+    if distance(rgb, indexed_rgb(grey)) < distance(rgb, indexed_rgb(cube)) {
+        grey
+    } else {
+        cube
+    }
+}
+
+/// RGB value of an entry in the xterm 256-colour palette
+fn indexed_rgb(index: u8) -> (u8, u8, u8) {
+    match index {
+        0..=15 => ANSI_16[index as usize].1,
+        16..=231 => {
+            let index = index - 16;
+            (
+                CUBE_LEVELS[(index / 36) as usize],
+                CUBE_LEVELS[(index / 6 % 6) as usize],
+                CUBE_LEVELS[(index % 6) as usize],
+            )
+        }
+        232..=255 => {
+            let value = 8 + 10 * (index - 232);
+            (value, value, value)
+        }
+    }
+}
+
+fn distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+    let channel = |x: u8, y: u8| (x as i32 - y as i32).pow(2) as u32;
+    channel(a.0, b.0) + channel(a.1, b.1) + channel(a.2, b.2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detect(vars: &[(&str, &str)]) -> ColorDepth {
+        ColorDepth::from_env(|name| {
+            vars.iter()
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| value.to_string())
+        })
+    }
+
+    #[test]
+    fn test_detect_from_environment() {
+        assert_eq!(
+            detect(&[("COLORTERM", "truecolor"), ("TERM", "xterm")]),
+            ColorDepth::TrueColor
+        );
+        assert_eq!(detect(&[("COLORTERM", "24bit")]), ColorDepth::TrueColor);
+        assert_eq!(detect(&[("TERM", "xterm-direct")]), ColorDepth::TrueColor);
+        assert_eq!(detect(&[("TERM", "screen-256color")]), ColorDepth::Ansi256);
+        assert_eq!(detect(&[("TERM", "linux")]), ColorDepth::Ansi16);
+        assert_eq!(detect(&[]), ColorDepth::Ansi16);
+        assert_eq!(detect(&[("TERM", "dumb")]), ColorDepth::Mono);
+    }
+
+    #[test]
+    fn test_no_color_overrides_terminal() {
+        assert_eq!(
+            detect(&[("NO_COLOR", "1"), ("COLORTERM", "truecolor")]),
+            ColorDepth::Mono
+        );
+        // An empty NO_COLOR does not count
+        assert_eq!(
+            detect(&[("NO_COLOR", ""), ("TERM", "xterm-256color")]),
+            ColorDepth::Ansi256
+        );
+    }
+
+    #[test]
+    fn test_quantize_to_256() {
+        let depth = ColorDepth::Ansi256;
+
+        // Exact cube and grey ramp entries map to themselves
+        assert_eq!(depth.quantize(Color::Rgb(255, 0, 0)), Color::Indexed(196));
+        assert_eq!(depth.quantize(Color::Rgb(95, 135, 175)), Color::Indexed(67));
+        assert_eq!(
+            depth.quantize(Color::Rgb(128, 128, 128)),
+            Color::Indexed(244)
+        );
+        // Near-greys prefer the finer grey ramp over the cube
+        assert_eq!(depth.quantize(Color::Rgb(30, 30, 30)), Color::Indexed(234));
+        assert_eq!(depth.quantize(Color::Rgb(0, 122, 204)), Color::Indexed(32));
+        // Colours the palette already has are left alone
+        assert_eq!(depth.quantize(Color::Cyan), Color::Cyan);
+        assert_eq!(depth.quantize(Color::Indexed(42)), Color::Indexed(42));
+    }
+
+    #[test]
+    fn test_quantize_to_16() {
+        let depth = ColorDepth::Ansi16;
+
+        assert_eq!(depth.quantize(Color::Rgb(255, 255, 255)), Color::White);
+        assert_eq!(depth.quantize(Color::Rgb(30, 30, 30)), Color::Black);
+        assert_eq!(depth.quantize(Color::Rgb(220, 53, 69)), Color::Red);
+        assert_eq!(depth.quantize(Color::Rgb(40, 167, 69)), Color::Green);
+        assert_eq!(depth.quantize(Color::Rgb(108, 117, 125)), Color::DarkGray);
+        assert_eq!(depth.quantize(Color::Indexed(9)), Color::LightRed);
+        assert_eq!(depth.quantize(Color::Indexed(196)), Color::LightRed);
+        assert_eq!(depth.quantize(Color::Reset), Color::Reset);
+    }
+
+    #[test]
+    fn test_every_palette_entry_round_trips() {
+        for index in 16..=255 {
+            assert_eq!(nearest_256(indexed_rgb(index)), index, "index {}", index);
+        }
+    }
+
+    #[test]
+    fn test_mono_and_truecolor() {
+        assert_eq!(ColorDepth::Mono.quantize(Color::Rgb(1, 2, 3)), Color::Reset);
+        assert_eq!(ColorDepth::Mono.quantize(Color::Blue), Color::Reset);
+        assert_eq!(
+            ColorDepth::TrueColor.quantize(Color::Rgb(1, 2, 3)),
+            Color::Rgb(1, 2, 3)
+        );
+    }
+}
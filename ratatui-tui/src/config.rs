@@ -0,0 +1,366 @@
+use std::{
+    fs, io,
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{colors::ColorDepth, dashboard::DashboardConfig};
+
+const CONFIG_FILE: &str = "config.toml";
+
+/// Sidebar widths the layout can accommodate
+pub const SIDEBAR_WIDTH_RANGE: RangeInclusive<u16> = 12..=60;
+
+/// Errors raised while loading or saving the configuration
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("failed to read config {path}: {source}")]
+    Read { path: PathBuf, source: io::Error },
+    #[error("failed to write config {path}: {source}")]
+    Write { path: PathBuf, source: io::Error },
+    #[error("invalid config file: {0}")]
+    Parse(#[from] toml::de::Error),
+    #[error("invalid config value for {field}: {message}")]
+    Invalid {
+        field: &'static str,
+        message: String,
+    },
+    #[error("no config directory; set HOME, XDG_CONFIG_HOME or pass --config")]
+    NoConfigDir,
+}
+
+/// User preferences persisted as `config.toml` in the config directory
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Built-in theme name, installed theme name or path to a theme file
+    pub theme: String,
+    pub color_mode: ColorMode,
+    /// Report mouse events to the application; off leaves text selection to the terminal
+    pub mouse: bool,
+    pub log_level: LogLevel,
+    pub layout: LayoutConfig,
+    /// Panels on the dashboard and where they sit; saved when rearranged
+    pub dashboard: DashboardConfig,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            theme: "light".to_string(),
+            color_mode: ColorMode::Auto,
+            mouse: true,
+            log_level: LogLevel::Info,
+            layout: LayoutConfig::default(),
+            dashboard: DashboardConfig::default(),
+        }
+    }
+}
+
+/// Which parts of the screen are shown, and how large
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LayoutConfig {
+    pub sidebar_width: u16,
+    pub show_sidebar: bool,
+    pub show_status_bar: bool,
+    /// Hide the header to leave more room for content
+    pub compact: bool,
+}
+
+impl Default for LayoutConfig {
+    fn default() -> Self {
+        Self {
+            sidebar_width: 20,
+            show_sidebar: true,
+            show_status_bar: true,
+            compact: false,
+        }
+    }
+}
+
+/// How many colours to use: detected from the terminal, or forced to a depth
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ColorMode {
+    #[serde(rename = "auto")]
+    Auto,
+    #[serde(rename = "truecolor")]
+    TrueColor,
+    #[serde(rename = "256")]
+    Ansi256,
+    #[serde(rename = "16")]
+    Ansi16,
+    #[serde(rename = "mono")]
+    Mono,
+}
+
+impl ColorMode {
+    pub const ALL: [ColorMode; 5] = [
+        ColorMode::Auto,
+        ColorMode::TrueColor,
+        ColorMode::Ansi256,
+        ColorMode::Ansi16,
+        ColorMode::Mono,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ColorMode::Auto => "auto",
+            ColorMode::TrueColor => "truecolor",
+            ColorMode::Ansi256 => "256",
+            ColorMode::Ansi16 => "16",
+            ColorMode::Mono => "mono",
+        }
+    }
+
+    /// The depth to render with, detecting it from the environment in auto mode
+    pub fn depth(&self) -> ColorDepth {
+        match self {
+            ColorMode::Auto => ColorDepth::detect(),
+            ColorMode::TrueColor => ColorDepth::TrueColor,
+            ColorMode::Ansi256 => ColorDepth::Ansi256,
+            ColorMode::Ansi16 => ColorDepth::Ansi16,
+            ColorMode::Mono => ColorDepth::Mono,
+        }
+    }
+}
+
+/// How much is written to the log
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Off,
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl LogLevel {
+    pub const ALL: [LogLevel; 6] = [
+        LogLevel::Off,
+        LogLevel::Error,
+        LogLevel::Warn,
+        LogLevel::Info,
+        LogLevel::Debug,
+        LogLevel::Trace,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LogLevel::Off => "off",
+            LogLevel::Error => "error",
+            LogLevel::Warn => "warn",
+            LogLevel::Info => "info",
+            LogLevel::Debug => "debug",
+            LogLevel::Trace => "trace",
+        }
+    }
+
+    pub fn filter(&self) -> log::LevelFilter {
+        match self {
+            LogLevel::Off => log::LevelFilter::Off,
+            LogLevel::Error => log::LevelFilter::Error,
+            LogLevel::Warn => log::LevelFilter::Warn,
+            LogLevel::Info => log::LevelFilter::Info,
+            LogLevel::Debug => log::LevelFilter::Debug,
+            LogLevel::Trace => log::LevelFilter::Trace,
+        }
+    }
+}
+
+impl Config {
+    /// Load the config file from `config_dir`, or the defaults if there is none
+    pub fn load(config_dir: Option<&Path>) -> Result<Self, ConfigError> {
+        match config_dir.map(|dir| dir.join(CONFIG_FILE)) {
+            Some(path) if path.exists() => {
+                let source = fs::read_to_string(&path)
+                    .map_err(|source| ConfigError::Read { path, source })?;
+                Self::from_toml(&source)
+            }
+            _ => Ok(Self::default()),
+        }
+    }
+
+    pub fn from_toml(source: &str) -> Result<Self, ConfigError> {
+        let config: Config = toml::from_str(source)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn to_toml(&self) -> String {
+        toml::to_string_pretty(self).expect("config serializes to TOML")
+    }
+
+    /// Write the config file into `config_dir`, creating the directory if needed
+    pub fn save(&self, config_dir: Option<&Path>) -> Result<PathBuf, ConfigError> {
+        let dir = config_dir.ok_or(ConfigError::NoConfigDir)?;
+        let path = dir.join(CONFIG_FILE);
+
+        fs::create_dir_all(dir)
+            .and_then(|_| fs::write(&path, self.to_toml()))
+            .map_err(|source| ConfigError::Write {
+                path: path.clone(),
+                source,
+            })?;
+        Ok(path)
+    }
+
+    /// Reject values the application cannot use
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        validate_sidebar_width(self.layout.sidebar_width).map_err(|message| {
+            ConfigError::Invalid {
+                field: "layout.sidebar_width",
+                message,
+            }
+        })?;
+        self.dashboard
+            .validate()
+            .map_err(|message| ConfigError::Invalid {
+                field: "dashboard",
+                message,
+            })
+    }
+}
+
+/// Settings given on the command line, in force for the session but never saved
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Overrides {
+    pub theme: Option<String>,
+    pub mouse: Option<bool>,
+}
+
+impl Overrides {
+    /// `config` with the overrides in force
+    pub fn apply(&self, mut config: Config) -> Config {
+        if let Some(theme) = &self.theme {
+            config.theme = theme.clone();
+        }
+        if let Some(mouse) = self.mouse {
+            config.mouse = mouse;
+        }
+        config
+    }
+
+    /// What to write to the file for the live `config`: settings still at their
+    /// overridden value keep what the file has, those changed since are written
+    pub fn persisted(&self, config: &Config, on_disk: &Config) -> Config {
+        let mut persisted = config.clone();
+        if self.theme.as_ref() == Some(&config.theme) {
+            persisted.theme = on_disk.theme.clone();
+        }
+        if self.mouse == Some(config.mouse) {
+            persisted.mouse = on_disk.mouse;
+        }
+        persisted
+    }
+}
+
+pub fn validate_sidebar_width(width: u16) -> Result<(), String> {
+    if SIDEBAR_WIDTH_RANGE.contains(&width) {
+        Ok(())
+    } else {
+        Err(format!(
+            "must be between {} and {}",
+            SIDEBAR_WIDTH_RANGE.start(),
+            SIDEBAR_WIDTH_RANGE.end()
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_partial_files_use_defaults() {
+        let config = Config::from_toml("theme = \"dark\"\n[layout]\ncompact = true\n").unwrap();
+
+        assert_eq!(config.theme, "dark");
+        assert!(config.layout.compact);
+        assert_eq!(
+            config.layout.sidebar_width,
+            LayoutConfig::default().sidebar_width
+        );
+        assert_eq!(config.log_level, LogLevel::Info);
+        assert_eq!(config.color_mode, ColorMode::Auto);
+        assert!(config.mouse);
+
+        let config = Config::from_toml("color_mode = \"16\"").unwrap();
+        assert_eq!(config.color_mode.depth(), ColorDepth::Ansi16);
+    }
+
+    #[test]
+    fn test_overrides_are_kept_out_of_the_file() {
+        let on_disk = Config::default();
+        let overrides = Overrides {
+            theme: Some("dark".to_string()),
+            mouse: None,
+        };
+        let mut config = overrides.apply(on_disk.clone());
+        assert_eq!(config.theme, "dark");
+        assert_eq!(overrides.persisted(&config, &on_disk), on_disk);
+
+        // A setting changed after starting is saved, even one that was overridden
+        config.theme = "solarized".to_string();
+        config.mouse = false;
+        let persisted = overrides.persisted(&config, &on_disk);
+        assert_eq!(
+            (persisted.theme.as_str(), persisted.mouse),
+            ("solarized", false)
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut config = Config {
+            color_mode: ColorMode::Ansi256,
+            log_level: LogLevel::Debug,
+            ..Config::default()
+        };
+        config.layout.show_sidebar = false;
+        config.dashboard.panels[0].title = Some("Processors".to_string());
+        config.dashboard.panels.truncate(3);
+
+        assert_eq!(Config::from_toml(&config.to_toml()).unwrap(), config);
+    }
+
+    #[test]
+    fn test_invalid_files_are_rejected() {
+        assert!(matches!(
+            Config::from_toml("colour = \"red\""),
+            Err(ConfigError::Parse(_))
+        ));
+        assert!(matches!(
+            Config::from_toml("log_level = \"loud\""),
+            Err(ConfigError::Parse(_))
+        ));
+        assert!(matches!(
+            Config::from_toml("[layout]\nsidebar_width = 2"),
+            Err(ConfigError::Invalid { .. })
+        ));
+        assert!(matches!(
+            Config::from_toml("[dashboard]\nrows = 1\n[[dashboard.panels]]\nkind = \"clock\"\nrow = 1\ncolumn = 0\n"),
+            Err(ConfigError::Invalid { field: "dashboard", .. })
+        ));
+    }
+
+    #[test]
+    fn test_dashboard_panels_are_declared_in_the_file() {
+        let source = "[dashboard]\ncolumns = 2\nrows = 1\n\n\
+            [[dashboard.panels]]\nkind = \"note\"\nrow = 0\ncolumn = 0\ntext = \"On call: Sam\"\n\n\
+            [[dashboard.panels]]\nkind = \"clock\"\nrow = 0\ncolumn = 1\nmin_width = 30\n";
+        let config = Config::from_toml(source).unwrap();
+
+        let panels = &config.dashboard.panels;
+        assert_eq!(panels.len(), 2);
+        assert_eq!(panels[0].text.as_deref(), Some("On call: Sam"));
+        assert_eq!(panels[1].column_span, 1);
+        assert_eq!(panels[1].min_size(), (30, 4));
+        assert_eq!(Config::from_toml(&config.to_toml()).unwrap(), config);
+    }
+}
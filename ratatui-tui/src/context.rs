@@ -0,0 +1,59 @@
+use crate::{
+    edit::CellEditor, export::ExportPrompt, filter::FilterBar, picker::FilePicker,
+    source::LoadStatus, styles::StyleManager, table::DataTable,
+};
+
+/// Shared state that every widget and view reads while a frame is drawn
+///
+/// Built from the application state for each frame, so a theme change is seen
+/// by the whole screen at once rather than by whichever widget holds a copy.
+#[derive(Debug, Clone, Copy)]
+pub struct RenderContext<'a> {
+    pub styles: &'a StyleManager,
+    /// The Data Browser's rows, when there are any to draw
+    pub data: Option<DataContext<'a>>,
+}
+
+impl<'a> RenderContext<'a> {
+    pub fn new(styles: &'a StyleManager) -> Self {
+        Self { styles, data: None }
+    }
+
+    pub fn with_data(mut self, data: DataContext<'a>) -> Self {
+        self.data = Some(data);
+        self
+    }
+}
+
+/// The open table and whatever is open over it, borrowed from the application
+/// state so a large dataset is never copied just to be drawn
+#[derive(Debug, Clone, Copy)]
+pub struct DataContext<'a> {
+    pub table: &'a DataTable,
+    pub load: &'a LoadStatus,
+    pub picker: Option<&'a FilePicker>,
+    pub filter_bar: Option<&'a FilterBar>,
+    pub export_prompt: Option<&'a ExportPrompt>,
+    pub editor: Option<&'a CellEditor>,
+}
+
+impl<'a> DataContext<'a> {
+    /// The table and where its rows come from, with nothing open over it
+    pub fn new(table: &'a DataTable, load: &'a LoadStatus) -> Self {
+        Self {
+            table,
+            load,
+            picker: None,
+            filter_bar: None,
+            export_prompt: None,
+            editor: None,
+        }
+    }
+
+    /// The editor's text, if it is open on a cell
+    pub fn editing(&self, index: usize, column: usize) -> Option<&'a str> {
+        self.editor
+            .filter(|editor| editor.row == index && editor.column == column)
+            .map(|editor| editor.text.as_str())
+    }
+}
@@ -0,0 +1,576 @@
+use std::{fmt, ops::RangeInclusive, str::FromStr};
+
+use ratatui::layout::Rect;
+use serde::{Deserialize, Serialize};
+
+/// Rows and columns the dashboard grid can be divided into
+pub const GRID_RANGE: RangeInclusive<u16> = 1..=12;
+
+/// What a dashboard panel shows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PanelKind {
+    /// CPU use now and over the last couple of minutes
+    Cpu,
+    Memory,
+    /// Load averages against the number of CPUs
+    Load,
+    Network,
+    Disk,
+    /// The rows open in the Data Browser
+    Data,
+    /// The latest log lines
+    Log,
+    Clock,
+    /// Text from the config file
+    Note,
+}
+
+impl PanelKind {
+    pub fn title(&self) -> &'static str {
+        match self {
+            PanelKind::Cpu => "CPU",
+            PanelKind::Memory => "Memory",
+            PanelKind::Load => "Load",
+            PanelKind::Network => "Network",
+            PanelKind::Disk => "Disk",
+            PanelKind::Data => "Data",
+            PanelKind::Log => "Log",
+            PanelKind::Clock => "Clock",
+            PanelKind::Note => "Note",
+        }
+    }
+
+    /// Smallest width and height, borders included, the panel can be drawn in
+    pub fn min_size(&self) -> (u16, u16) {
+        match self {
+            PanelKind::Cpu => (16, 5),
+            PanelKind::Memory => (16, 4),
+            PanelKind::Load => (18, 6),
+            PanelKind::Network => (16, 6),
+            PanelKind::Disk => (16, 4),
+            PanelKind::Data => (20, 6),
+            PanelKind::Log => (20, 3),
+            PanelKind::Clock => (12, 4),
+            PanelKind::Note => (8, 3),
+        }
+    }
+}
+
+/// A panel and the grid cells it covers
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PanelConfig {
+    pub kind: PanelKind,
+    /// Top row and left column, counted from 0
+    pub row: u16,
+    pub column: u16,
+    #[serde(default = "one")]
+    pub row_span: u16,
+    #[serde(default = "one")]
+    pub column_span: u16,
+    /// Override the kind's smallest size; a panel given less shows what it needs instead
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_width: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_height: Option<u16>,
+    /// Shown in the border instead of the kind's title
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// What a note panel says
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+}
+
+fn one() -> u16 {
+    1
+}
+
+impl PanelConfig {
+    pub fn new(kind: PanelKind, row: u16, column: u16, row_span: u16, column_span: u16) -> Self {
+        Self {
+            kind,
+            row,
+            column,
+            row_span,
+            column_span,
+            min_width: None,
+            min_height: None,
+            title: None,
+            text: None,
+        }
+    }
+
+    pub fn title(&self) -> &str {
+        self.title.as_deref().unwrap_or(self.kind.title())
+    }
+
+    pub fn min_size(&self) -> (u16, u16) {
+        let (width, height) = self.kind.min_size();
+        (
+            self.min_width.unwrap_or(width),
+            self.min_height.unwrap_or(height),
+        )
+    }
+
+    fn overlaps(&self, other: &PanelConfig) -> bool {
+        self.row < other.row + other.row_span
+            && other.row < self.row + self.row_span
+            && self.column < other.column + other.column_span
+            && other.column < self.column + self.column_span
+    }
+}
+
+/// One of the four directions a panel is moved or resized in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Side {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+impl Side {
+    pub const ALL: [Side; 4] = [Side::Left, Side::Right, Side::Up, Side::Down];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Side::Left => "left",
+            Side::Right => "right",
+            Side::Up => "up",
+            Side::Down => "down",
+        }
+    }
+}
+
+impl fmt::Display for Side {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Side {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "left" => Ok(Side::Left),
+            "right" => Ok(Side::Right),
+            "up" => Ok(Side::Up),
+            "down" => Ok(Side::Down),
+            _ => Err(format!("unknown side '{}'", s)),
+        }
+    }
+}
+
+/// The dashboard's grid and the panels placed on it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DashboardConfig {
+    pub rows: u16,
+    pub columns: u16,
+    pub panels: Vec<PanelConfig>,
+}
+
+impl Default for DashboardConfig {
+    fn default() -> Self {
+        Self {
+            rows: 3,
+            columns: 4,
+            panels: vec![
+                PanelConfig::new(PanelKind::Cpu, 0, 0, 1, 2),
+                PanelConfig::new(PanelKind::Memory, 0, 2, 1, 2),
+                PanelConfig::new(PanelKind::Network, 1, 0, 1, 2),
+                PanelConfig::new(PanelKind::Disk, 1, 2, 1, 1),
+                PanelConfig::new(PanelKind::Load, 1, 3, 1, 1),
+                PanelConfig::new(PanelKind::Log, 2, 0, 1, 2),
+                PanelConfig::new(PanelKind::Data, 2, 2, 1, 1),
+                PanelConfig::new(PanelKind::Clock, 2, 3, 1, 1),
+            ],
+        }
+    }
+}
+
+impl DashboardConfig {
+    /// Check the grid's size and that every panel sits inside it without overlapping another
+    pub fn validate(&self) -> Result<(), String> {
+        for (name, size) in [("rows", self.rows), ("columns", self.columns)] {
+            if !GRID_RANGE.contains(&size) {
+                return Err(format!(
+                    "{} must be between {} and {}",
+                    name,
+                    GRID_RANGE.start(),
+                    GRID_RANGE.end()
+                ));
+            }
+        }
+
+        for (index, panel) in self.panels.iter().enumerate() {
+            if !self.fits(panel) {
+                return Err(format!(
+                    "panel {} ({}) does not fit the {}x{} grid",
+                    index + 1,
+                    panel.title(),
+                    self.rows,
+                    self.columns
+                ));
+            }
+            if let Some(other) = self.panels[..index]
+                .iter()
+                .position(|other| other.overlaps(panel))
+            {
+                return Err(format!(
+                    "panels {} ({}) and {} ({}) overlap",
+                    other + 1,
+                    self.panels[other].title(),
+                    index + 1,
+                    panel.title()
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn fits(&self, panel: &PanelConfig) -> bool {
+        panel.row_span > 0
+            && panel.column_span > 0
+            && panel.row.saturating_add(panel.row_span) <= self.rows
+            && panel.column.saturating_add(panel.column_span) <= self.columns
+    }
+
+    /// Whether `panels` fit the grid with none overlapping
+    fn allows(&self, panels: &[PanelConfig]) -> bool {
+        panels.iter().enumerate().all(|(index, panel)| {
+            self.fits(panel) && panels[..index].iter().all(|other| !other.overlaps(panel))
+        })
+    }
+
+    /// The panel after (or before) `index`, reading the grid left to right and top to bottom
+    pub fn next_panel(&self, index: usize, forward: bool) -> usize {
+        let mut order: Vec<usize> = (0..self.panels.len()).collect();
+        order.sort_by_key(|&index| (self.panels[index].row, self.panels[index].column));
+
+        let Some(position) = order.iter().position(|&other| other == index) else {
+            return 0;
+        };
+        let next = if forward {
+            (position + 1) % order.len()
+        } else {
+            (position + order.len() - 1) % order.len()
+        };
+        order[next]
+    }
+
+    /// Move a panel one cell towards `side`, trading places with the panel in the way
+    pub fn move_panel(&mut self, index: usize, side: Side) -> bool {
+        let Some(panel) = self.panels.get(index) else {
+            return false;
+        };
+        let mut moved = panel.clone();
+        match side {
+            Side::Left if moved.column > 0 => moved.column -= 1,
+            Side::Up if moved.row > 0 => moved.row -= 1,
+            Side::Right => moved.column += 1,
+            Side::Down => moved.row += 1,
+            _ => return false,
+        }
+
+        let mut panels = self.panels.clone();
+        panels[index] = moved.clone();
+        let blocking: Vec<usize> = (0..panels.len())
+            .filter(|&other| other != index && panels[other].overlaps(&moved))
+            .collect();
+
+        // A single neighbour swaps sides with the panel, each keeping its size
+        if let [other] = blocking[..] {
+            let (panel, neighbour) = (&self.panels[index], &self.panels[other]);
+            let (mut panel, mut neighbour) = (panel.clone(), neighbour.clone());
+            match side {
+                Side::Left => {
+                    panel.column = neighbour.column;
+                    neighbour.column = panel.column + panel.column_span;
+                }
+                Side::Right => {
+                    neighbour.column = panel.column;
+                    panel.column = neighbour.column + neighbour.column_span;
+                }
+                Side::Up => {
+                    panel.row = neighbour.row;
+                    neighbour.row = panel.row + panel.row_span;
+                }
+                Side::Down => {
+                    neighbour.row = panel.row;
+                    panel.row = neighbour.row + neighbour.row_span;
+                }
+            }
+            panels[index] = panel;
+            panels[other] = neighbour;
+        }
+
+        if blocking.len() > 1 || !self.allows(&panels) {
+            return false;
+        }
+        self.panels = panels;
+        true
+    }
+
+    /// Grow or shrink a panel by a cell: right and down make it wider and taller,
+    /// left and up narrower and shorter
+    pub fn resize_panel(&mut self, index: usize, side: Side) -> bool {
+        let Some(panel) = self.panels.get(index) else {
+            return false;
+        };
+        let mut resized = panel.clone();
+        match side {
+            Side::Right => resized.column_span += 1,
+            Side::Down => resized.row_span += 1,
+            Side::Left if resized.column_span > 1 => resized.column_span -= 1,
+            Side::Up if resized.row_span > 1 => resized.row_span -= 1,
+            _ => return false,
+        }
+
+        let mut panels = self.panels.clone();
+        panels[index] = resized;
+        if !self.allows(&panels) {
+            return false;
+        }
+        self.panels = panels;
+        true
+    }
+
+    /// Where each panel is drawn in `area`, in config order
+    ///
+    /// Rows and columns share the space evenly, except that each is made large enough
+    /// for the panels over it when there is room.
+    pub fn areas(&self, area: Rect) -> Vec<Rect> {
+        let widths = tracks(
+            area.width,
+            self.columns,
+            self.panels
+                .iter()
+                .map(|panel| (panel.column, panel.column_span, panel.min_size().0)),
+        );
+        let heights = tracks(
+            area.height,
+            self.rows,
+            self.panels
+                .iter()
+                .map(|panel| (panel.row, panel.row_span, panel.min_size().1)),
+        );
+        let xs = offsets(area.x, &widths);
+        let ys = offsets(area.y, &heights);
+
+        self.panels
+            .iter()
+            .map(|panel| {
+                let (left, right) = span(&xs, panel.column, panel.column_span);
+                let (top, bottom) = span(&ys, panel.row, panel.row_span);
+                Rect::new(left, top, right - left, bottom - top)
+            })
+            .collect()
+    }
+}
+
+/// Sizes of `count` rows or columns sharing `total` cells, given each panel's
+/// first track, span and minimum size
+fn tracks(total: u16, count: u16, panels: impl Iterator<Item = (u16, u16, u16)>) -> Vec<u16> {
+    let count = count as usize;
+    let mut minimums = vec![0u16; count];
+    for (start, span, min) in panels {
+        let share = min.div_ceil(span.max(1));
+        for track in minimums.iter_mut().skip(start as usize).take(span as usize) {
+            *track = (*track).max(share);
+        }
+    }
+
+    // Tracks needing more than an even share keep their minimum; the rest split what is left
+    let mut fixed = vec![false; count];
+    let (mut level, mut spare) = (0, 0);
+    loop {
+        let taken: u16 = (0..count).filter(|&i| fixed[i]).map(|i| minimums[i]).sum();
+        let free = fixed.iter().filter(|fixed| !**fixed).count() as u16;
+        if free == 0 {
+            break;
+        }
+        let left = total.saturating_sub(taken);
+        (level, spare) = (left / free, left % free);
+
+        let mut changed = false;
+        for i in 0..count {
+            if !fixed[i] && minimums[i] > level {
+                fixed[i] = true;
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+
+    let mut sizes: Vec<u16> = (0..count)
+        .map(|i| {
+            if fixed[i] {
+                minimums[i]
+            } else if spare > 0 {
+                spare -= 1;
+                level + 1
+            } else {
+                level
+            }
+        })
+        .collect();
+
+    // Without room for every minimum, the last tracks give way
+    let mut excess = sizes.iter().sum::<u16>().saturating_sub(total);
+    for size in sizes.iter_mut().rev() {
+        let cut = excess.min(*size);
+        *size -= cut;
+        excess -= cut;
+    }
+    sizes
+}
+
+/// Where each track starts, followed by where the last one ends
+fn offsets(start: u16, sizes: &[u16]) -> Vec<u16> {
+    let mut offsets = vec![start];
+    for size in sizes {
+        offsets.push(offsets[offsets.len() - 1] + size);
+    }
+    offsets
+}
+
+fn span(offsets: &[u16], start: u16, span: u16) -> (u16, u16) {
+    let last = offsets.len() - 1;
+    let start = (start as usize).min(last);
+    (offsets[start], offsets[(start + span as usize).min(last)])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn positions(config: &DashboardConfig) -> Vec<(PanelKind, u16, u16)> {
+        config
+            .panels
+            .iter()
+            .map(|panel| (panel.kind, panel.row, panel.column))
+            .collect()
+    }
+
+    #[test]
+    fn test_default_layout_is_valid() {
+        assert_eq!(DashboardConfig::default().validate(), Ok(()));
+
+        let mut config = DashboardConfig::default();
+        config
+            .panels
+            .push(PanelConfig::new(PanelKind::Note, 2, 3, 1, 1));
+        assert_eq!(
+            config.validate(),
+            Err("panels 8 (Clock) and 9 (Note) overlap".to_string())
+        );
+
+        config.panels.pop();
+        config.panels[0].column_span = 5;
+        assert_eq!(
+            config.validate(),
+            Err("panel 1 (CPU) does not fit the 3x4 grid".to_string())
+        );
+
+        config.columns = 0;
+        assert_eq!(
+            config.validate(),
+            Err("columns must be between 1 and 12".to_string())
+        );
+    }
+
+    #[test]
+    fn test_panels_split_the_area_around_their_minimum_sizes() {
+        let mut load = PanelConfig::new(PanelKind::Load, 0, 0, 2, 1);
+        load.min_width = Some(26);
+        let config = DashboardConfig {
+            rows: 2,
+            columns: 3,
+            panels: vec![
+                load,
+                PanelConfig::new(PanelKind::Note, 0, 1, 1, 2),
+                PanelConfig::new(PanelKind::Clock, 1, 1, 1, 1),
+            ],
+        };
+
+        // Plenty of room: columns are even
+        let areas = config.areas(Rect::new(0, 1, 90, 20));
+        assert_eq!(areas[0], Rect::new(0, 1, 30, 20));
+        assert_eq!(areas[1], Rect::new(30, 1, 60, 10));
+        assert_eq!(areas[2], Rect::new(30, 11, 30, 10));
+
+        // Load needs 26 columns, so the others share what is left
+        let areas = config.areas(Rect::new(0, 0, 60, 20));
+        assert_eq!(areas[0].width, 26);
+        assert_eq!(areas[1], Rect::new(26, 0, 34, 10));
+        assert_eq!(areas[2].width, 17);
+
+        // Too narrow for every minimum: the last columns give way
+        let areas = config.areas(Rect::new(0, 0, 30, 20));
+        assert_eq!(areas[0].width, 26);
+        assert_eq!(areas[1].width, 4);
+        assert_eq!(areas[2].width, 4);
+    }
+
+    #[test]
+    fn test_panels_move_by_trading_places() {
+        let mut config = DashboardConfig::default();
+
+        // Network (two columns) and Disk trade places
+        assert!(config.move_panel(2, Side::Right));
+        assert_eq!(config.panels[2].column, 1);
+        assert_eq!(config.panels[3].column, 0);
+
+        // Two panels are below Memory, and nothing is above CPU
+        assert!(!config.move_panel(1, Side::Down));
+        assert!(!config.move_panel(0, Side::Up));
+
+        // The data summary trades places with the two-column log tail
+        assert!(config.move_panel(6, Side::Left));
+        assert_eq!(
+            positions(&config)[5..],
+            [
+                (PanelKind::Log, 2, 1),
+                (PanelKind::Data, 2, 0),
+                (PanelKind::Clock, 2, 3)
+            ]
+        );
+        assert_eq!(config.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_panels_resize_into_free_cells() {
+        let mut config = DashboardConfig::default();
+        assert!(!config.resize_panel(0, Side::Down));
+        assert!(!config.resize_panel(3, Side::Left));
+
+        assert!(config.resize_panel(0, Side::Left));
+        assert_eq!(config.panels[0].column_span, 1);
+        assert!(config.resize_panel(1, Side::Left));
+        assert!(config.move_panel(1, Side::Left));
+        assert_eq!(
+            (config.panels[1].column, config.panels[1].column_span),
+            (1, 1)
+        );
+        assert!(config.resize_panel(1, Side::Right));
+        assert!(config.resize_panel(1, Side::Right));
+        assert_eq!(config.panels[1].column_span, 3);
+        assert_eq!(config.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_panels_are_visited_in_reading_order() {
+        let mut config = DashboardConfig::default();
+        config.panels.swap(0, 7);
+
+        assert_eq!(config.next_panel(7, true), 1);
+        assert_eq!(config.next_panel(0, true), 7);
+        assert_eq!(config.next_panel(7, false), 0);
+        assert_eq!("down".parse::<Side>(), Ok(Side::Down));
+    }
+}
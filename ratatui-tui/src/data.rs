@@ -0,0 +1,383 @@
+use std::{borrow::Cow, cmp::Ordering, fmt};
+
+use chrono::NaiveDate;
+use serde_json::Value as JsonValue;
+use thiserror::Error;
+
+/// Date formats recognised in text, tried in order
+const DATE_FORMATS: [&str; 3] = ["%Y-%m-%d", "%Y/%m/%d", "%d.%m.%Y"];
+
+/// The type shared by every value in a column
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnKind {
+    Number,
+    Date,
+    Bool,
+    Text,
+}
+
+impl ColumnKind {
+    /// The narrowest kind every non-empty cell fits: number, then bool, then
+    /// date, falling back to text; a column of empty cells is text
+    pub fn infer<'a>(cells: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut candidates = vec![ColumnKind::Number, ColumnKind::Bool, ColumnKind::Date];
+        let mut any = false;
+
+        for cell in cells.into_iter().filter(|cell| !cell.trim().is_empty()) {
+            any = true;
+            candidates.retain(|kind| Value::parse(cell, *kind).is_some());
+            if candidates.is_empty() {
+                return ColumnKind::Text;
+            }
+        }
+
+        match candidates.first() {
+            Some(kind) if any => *kind,
+            _ => ColumnKind::Text,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ColumnKind::Number => "number",
+            ColumnKind::Date => "date",
+            ColumnKind::Bool => "bool",
+            ColumnKind::Text => "text",
+        }
+    }
+}
+
+/// Text that does not fit the kind of the column it was meant for
+#[derive(Debug, Clone, PartialEq, Error)]
+#[error("{column} is a {kind} column; '{text}' is not a {kind}")]
+pub struct InvalidValue {
+    pub column: String,
+    pub kind: &'static str,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Column {
+    pub name: String,
+    pub kind: ColumnKind,
+}
+
+impl Column {
+    pub fn new(name: &str, kind: ColumnKind) -> Self {
+        Self {
+            name: name.to_string(),
+            kind,
+        }
+    }
+
+    /// A cell as a file held it; one that does not fit this column's kind is
+    /// kept as text rather than dropped
+    fn read(&self, cell: Option<&JsonValue>) -> Value {
+        match cell.and_then(cell_text) {
+            Some(text) => {
+                Value::parse(&text, self.kind).unwrap_or_else(|| Value::Text(text.into_owned()))
+            }
+            None => Value::Null,
+        }
+    }
+
+    /// Read typed text as a value of this column's kind
+    pub fn parse(&self, text: &str) -> Result<Value, InvalidValue> {
+        Value::parse(text, self.kind).ok_or_else(|| InvalidValue {
+            column: self.name.clone(),
+            kind: self.kind.as_str(),
+            text: text.trim().to_string(),
+        })
+    }
+}
+
+/// A single cell
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    Date(NaiveDate),
+    Text(String),
+}
+
+impl Value {
+    /// Read text as a value of the given kind; `None` if it is not one.
+    /// Blank text is `Null` whatever the kind, and text is kept as written.
+    pub fn parse(raw: &str, kind: ColumnKind) -> Option<Value> {
+        let text = raw.trim();
+        if text.is_empty() {
+            return Some(Value::Null);
+        }
+
+        match kind {
+            ColumnKind::Number => text
+                .parse::<f64>()
+                .ok()
+                .filter(|value| value.is_finite())
+                .map(Value::Number),
+            ColumnKind::Bool => match text.to_ascii_lowercase().as_str() {
+                "true" => Some(Value::Bool(true)),
+                "false" => Some(Value::Bool(false)),
+                _ => None,
+            },
+            ColumnKind::Date => DATE_FORMATS
+                .iter()
+                .find_map(|format| NaiveDate::parse_from_str(text, format).ok())
+                .map(Value::Date),
+            ColumnKind::Text => Some(Value::Text(raw.to_string())),
+        }
+    }
+
+    /// Total order used for sorting: empty cells first, then by type, then by value
+    pub fn compare(&self, other: &Value) -> Ordering {
+        match (self, other) {
+            (Value::Bool(a), Value::Bool(b)) => a.cmp(b),
+            (Value::Number(a), Value::Number(b)) => a.total_cmp(b),
+            (Value::Date(a), Value::Date(b)) => a.cmp(b),
+            (Value::Text(a), Value::Text(b)) => a.to_lowercase().cmp(&b.to_lowercase()),
+            (a, b) => a.rank().cmp(&b.rank()),
+        }
+    }
+
+    /// The value as JSON, keeping numbers, booleans and nulls as such
+    pub fn to_json(&self) -> JsonValue {
+        match self {
+            Value::Null => JsonValue::Null,
+            Value::Bool(value) => JsonValue::Bool(*value),
+            // Whole numbers as integers, as they were most likely written
+            Value::Number(value) if value.fract() == 0.0 && value.abs() < 1e15 => {
+                JsonValue::from(*value as i64)
+            }
+            Value::Number(value) => JsonValue::from(*value),
+            value @ (Value::Date(_) | Value::Text(_)) => JsonValue::String(value.to_string()),
+        }
+    }
+
+    fn rank(&self) -> u8 {
+        match self {
+            Value::Null => 0,
+            Value::Bool(_) => 1,
+            Value::Number(_) => 2,
+            Value::Date(_) => 3,
+            Value::Text(_) => 4,
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Null => Ok(()),
+            Value::Bool(value) => write!(f, "{}", value),
+            // Whole numbers without a trailing ".0"
+            Value::Number(value) if value.fract() == 0.0 && value.abs() < 1e15 => {
+                write!(f, "{}", *value as i64)
+            }
+            Value::Number(value) => write!(f, "{}", value),
+            Value::Date(date) => write!(f, "{}", date.format("%Y-%m-%d")),
+            Value::Text(text) => write!(f, "{}", text),
+        }
+    }
+}
+
+/// Rows of typed values under a fixed set of columns
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dataset {
+    pub columns: Vec<Column>,
+    pub rows: Vec<Vec<Value>>,
+    /// Each cell as the file it was read from held it, so cells that are never
+    /// edited are written back unchanged; `None` where a row left a cell out,
+    /// and no rows at all when the data did not come from a file
+    pub originals: Vec<Vec<Option<JsonValue>>>,
+}
+
+impl Dataset {
+    /// Type the cells of a table read from a file, one column at a time
+    ///
+    /// Cells are given as the file held them: JSON values as written, CSV
+    /// fields as strings, and `None` where a row left a cell out.
+    pub fn infer(names: Vec<String>, originals: Vec<Vec<Option<JsonValue>>>) -> Self {
+        let columns: Vec<Column> = names
+            .iter()
+            .enumerate()
+            .map(|(index, name)| {
+                let cells: Vec<Cow<'_, str>> = originals
+                    .iter()
+                    .filter_map(|row| row.get(index)?.as_ref().and_then(cell_text))
+                    .collect();
+                Column::new(
+                    name,
+                    ColumnKind::infer(cells.iter().map(|cell| cell.as_ref())),
+                )
+            })
+            .collect();
+
+        let rows = originals
+            .iter()
+            .map(|row| {
+                columns
+                    .iter()
+                    .enumerate()
+                    .map(|(index, column)| column.read(row.get(index).and_then(Option::as_ref)))
+                    .collect()
+            })
+            .collect();
+
+        Self {
+            columns,
+            rows,
+            originals,
+        }
+    }
+
+    /// The projects shown before any data is loaded
+    pub fn sample() -> Self {
+        let projects = [
+            ("001", "Project Alpha", "Active", 1234.0, (2024, 1, 15)),
+            ("002", "Project Beta", "Pending", 5678.0, (2024, 3, 2)),
+            ("003", "Project Gamma", "Completed", 9012.0, (2023, 11, 20)),
+            ("004", "Project Delta", "Active", 3456.0, (2024, 5, 8)),
+            ("005", "Project Epsilon", "Cancelled", 0.0, (2023, 8, 30)),
+        ];
+
+        let rows = projects
+            .into_iter()
+            .map(|(id, name, status, value, (year, month, day))| {
+                vec![
+                    Value::Text(id.to_string()),
+                    Value::Text(name.to_string()),
+                    Value::Text(status.to_string()),
+                    Value::Number(value),
+                    NaiveDate::from_ymd_opt(year, month, day).map_or(Value::Null, Value::Date),
+                ]
+            })
+            .collect();
+
+        Self {
+            columns: vec![
+                Column::new("ID", ColumnKind::Text),
+                Column::new("Name", ColumnKind::Text),
+                Column::new("Status", ColumnKind::Text),
+                Column::new("Value", ColumnKind::Number),
+                Column::new("Started", ColumnKind::Date),
+            ],
+            rows,
+            originals: Vec::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// The value in a cell, or `Null` past the end of a short row
+    pub fn value(&self, row: usize, column: usize) -> &Value {
+        self.rows[row].get(column).unwrap_or(&Value::Null)
+    }
+
+    /// A cell as its file held it, as long as its value is still the one read from there
+    pub fn original(&self, row: usize, column: usize) -> Option<&JsonValue> {
+        let original = self.originals.get(row)?.get(column)?.as_ref()?;
+        (self.columns[column].read(Some(original)) == *self.value(row, column)).then_some(original)
+    }
+
+    /// Whether the file the rows were read from left this cell out
+    pub fn is_missing(&self, row: usize, column: usize) -> bool {
+        self.originals
+            .get(row)
+            .is_some_and(|cells| cells.get(column).is_none_or(Option::is_none))
+    }
+}
+
+/// A cell's text as read from a file; nested arrays and objects are kept as JSON
+fn cell_text(cell: &JsonValue) -> Option<Cow<'_, str>> {
+    match cell {
+        JsonValue::Null => None,
+        JsonValue::String(text) => Some(Cow::Borrowed(text)),
+        value => Some(Cow::Owned(value.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_values_sort_by_type() {
+        assert_eq!(
+            Value::Number(2.0).compare(&Value::Number(10.0)),
+            Ordering::Less
+        );
+        assert_eq!(
+            Value::Text("beta".to_string()).compare(&Value::Text("Alpha".to_string())),
+            Ordering::Greater
+        );
+        assert_eq!(Value::Null.compare(&Value::Number(-5.0)), Ordering::Less);
+    }
+
+    #[test]
+    fn test_value_display() {
+        assert_eq!(Value::Number(1234.0).to_string(), "1234");
+        assert_eq!(Value::Number(2.5).to_string(), "2.5");
+        assert_eq!(Value::Null.to_string(), "");
+        let date = NaiveDate::from_ymd_opt(2024, 1, 15).unwrap();
+        assert_eq!(Value::Date(date).to_string(), "2024-01-15");
+    }
+
+    #[test]
+    fn test_column_kinds_are_inferred() {
+        assert_eq!(
+            ColumnKind::infer(["1", " 2.5", "", "-3e2"]),
+            ColumnKind::Number
+        );
+        assert_eq!(ColumnKind::infer(["true", "FALSE"]), ColumnKind::Bool);
+        assert_eq!(
+            ColumnKind::infer(["2024-01-15", "2023/12/01"]),
+            ColumnKind::Date
+        );
+        assert_eq!(ColumnKind::infer(["1", "two"]), ColumnKind::Text);
+        assert_eq!(ColumnKind::infer(["2024-01-15", "true"]), ColumnKind::Text);
+        assert_eq!(ColumnKind::infer(["", " "]), ColumnKind::Text);
+        assert_eq!(ColumnKind::infer(["NaN"]), ColumnKind::Text);
+
+        // Text is kept as written; other kinds ignore the spaces around them
+        assert_eq!(
+            Value::parse(" two ", ColumnKind::Text),
+            Some(Value::Text(" two ".to_string()))
+        );
+        assert_eq!(
+            Value::parse(" 2 ", ColumnKind::Number),
+            Some(Value::Number(2.0))
+        );
+    }
+
+    #[test]
+    fn test_dataset_is_typed_by_column() {
+        let cell = |text: &str| Some(JsonValue::from(text));
+        let dataset = Dataset::infer(
+            vec!["id".to_string(), "active".to_string(), "note".to_string()],
+            vec![
+                vec![cell("7"), cell("true"), cell("x")],
+                vec![cell("8"), None, cell("")],
+                vec![cell("9"), cell("false")],
+            ],
+        );
+
+        let kinds: Vec<ColumnKind> = dataset.columns.iter().map(|column| column.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![ColumnKind::Number, ColumnKind::Bool, ColumnKind::Text]
+        );
+        assert_eq!(
+            dataset.rows[0],
+            vec![
+                Value::Number(7.0),
+                Value::Bool(true),
+                Value::Text("x".to_string())
+            ]
+        );
+        assert_eq!(dataset.rows[1][1], Value::Null);
+        assert_eq!(dataset.rows[2][2], Value::Null);
+    }
+}
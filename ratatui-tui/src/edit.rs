@@ -0,0 +1,101 @@
+use crossterm::event::{KeyCode, KeyModifiers};
+
+use crate::{
+    data::{Column, InvalidValue, Value},
+    keymap::KeyCombo,
+};
+
+/// What a key press in the cell editor asks for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EditOutcome {
+    Edited,
+    Unchanged,
+    /// Check the text against the column and keep it
+    Commit,
+    Cancel,
+}
+
+/// Text being typed into a cell of the Data Browser
+#[derive(Debug, Clone, PartialEq)]
+pub struct CellEditor {
+    /// Dataset index of the row being edited
+    pub row: usize,
+    pub column: usize,
+    pub text: String,
+    /// Why the text was not accepted, until it is edited again
+    pub error: Option<String>,
+}
+
+impl CellEditor {
+    /// An editor starting from the cell's current value; empty cells start empty
+    pub fn new(row: usize, column: usize, value: &Value) -> Self {
+        Self {
+            row,
+            column,
+            text: value.to_string(),
+            error: None,
+        }
+    }
+
+    /// Type, commit or cancel; `None` for keys the editor leaves to the keymap
+    pub fn input(&mut self, key: KeyCombo) -> Option<EditOutcome> {
+        if key.modifiers.contains(KeyModifiers::CONTROL) {
+            return None;
+        }
+
+        let outcome = match key.code {
+            KeyCode::Char(c) => {
+                self.text.push(c);
+                self.error = None;
+                EditOutcome::Edited
+            }
+            KeyCode::Backspace if !self.text.is_empty() => {
+                self.text.pop();
+                self.error = None;
+                EditOutcome::Edited
+            }
+            KeyCode::Enter => EditOutcome::Commit,
+            KeyCode::Esc => EditOutcome::Cancel,
+            _ => EditOutcome::Unchanged,
+        };
+
+        Some(outcome)
+    }
+
+    /// The text as a value of the column's kind; blank text empties the cell
+    pub fn value(&self, column: &Column) -> Result<Value, InvalidValue> {
+        column.parse(&self.text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::ColumnKind;
+
+    #[test]
+    fn test_text_is_checked_against_the_column() {
+        let column = Column::new("Value", ColumnKind::Number);
+        let mut editor = CellEditor::new(0, 3, &Value::Number(1234.0));
+        assert_eq!(editor.text, "1234");
+
+        let key = |code| KeyCombo::new(code, KeyModifiers::NONE);
+        editor.input(key(KeyCode::Char('x')));
+        let err = editor.value(&column).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Value is a number column; '1234x' is not a number"
+        );
+
+        editor.input(key(KeyCode::Backspace));
+        assert_eq!(editor.input(key(KeyCode::Enter)), Some(EditOutcome::Commit));
+        assert_eq!(editor.value(&column), Ok(Value::Number(1234.0)));
+
+        editor.text.clear();
+        assert_eq!(editor.value(&column), Ok(Value::Null));
+        assert_eq!(
+            editor.input(KeyCombo::new(KeyCode::Char('s'), KeyModifiers::CONTROL)),
+            None
+        );
+    }
+}
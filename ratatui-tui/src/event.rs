@@ -0,0 +1,337 @@
+use std::{future::Future, path::PathBuf, sync::Arc, time::Duration};
+
+use crossterm::event::{
+    Event as TerminalEvent, EventStream, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent,
+    MouseEventKind,
+};
+use futures::StreamExt;
+use log::warn;
+use tokio::{
+    sync::mpsc::{self, UnboundedReceiver, UnboundedSender},
+    task::JoinHandle,
+    time::{self, MissedTickBehavior},
+};
+
+use crate::{
+    data::Dataset,
+    script::{HeadlessBackend, Script, ScriptEvent, Step},
+};
+
+/// How often `Event::Tick` fires by default
+pub const DEFAULT_TICK_RATE: Duration = Duration::from_millis(250);
+
+/// How often `Event::Render` fires by default (60 frames per second)
+pub const DEFAULT_FRAME_RATE: Duration = Duration::from_micros(16_667);
+
+/// Events consumed by the application's main loop
+#[derive(Debug, Clone)]
+pub enum Event {
+    Key(KeyEvent),
+    Mouse(MouseEvent),
+    Resize(u16, u16),
+    /// Periodic state update
+    Tick,
+    /// Time to redraw, if anything changed
+    Render,
+    /// Message posted by a background task
+    Task(TaskMessage),
+    /// The process received a signal
+    Signal(Signal),
+    /// The terminal input stream failed
+    Error(String),
+    /// A check or the end of a script, when running one
+    Script(ScriptEvent),
+}
+
+/// Process signals the application reacts to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Signal {
+    /// SIGINT
+    Interrupt,
+    /// SIGTERM
+    Terminate,
+    /// SIGTSTP
+    Suspend,
+    /// SIGCONT
+    Resume,
+}
+
+/// Results delivered by background tasks
+#[derive(Debug, Clone, PartialEq)]
+pub enum TaskMessage {
+    /// A theme file was added, removed or edited
+    ThemesChanged,
+    /// Fraction of a data file read so far
+    DataProgress { path: PathBuf, progress: f64 },
+    /// A data file finished loading, or failed to
+    DataLoaded {
+        path: PathBuf,
+        result: Result<Arc<Dataset>, String>,
+    },
+    /// Rows were written to a file, or could not be
+    Exported {
+        path: PathBuf,
+        result: Result<usize, String>,
+    },
+    /// Edited rows were written back to their file, or could not be;
+    /// carries the rows as saved
+    DataSaved {
+        path: PathBuf,
+        result: Result<Arc<Dataset>, String>,
+    },
+}
+
+/// Handle for background tasks to post messages to the event loop
+#[derive(Debug, Clone)]
+pub struct TaskSender {
+    tx: UnboundedSender<Event>,
+}
+
+impl TaskSender {
+    /// Post a message; silently dropped if the event loop has shut down
+    pub fn send(&self, message: TaskMessage) {
+        let _ = self.tx.send(Event::Task(message));
+    }
+
+    /// Run a future on the runtime and deliver its result to the event loop
+    pub fn spawn<F>(&self, task: F)
+    where
+        F: Future<Output = TaskMessage> + Send + 'static,
+    {
+        let sender = self.clone();
+        tokio::spawn(async move { sender.send(task.await) });
+    }
+}
+
+/// Multiplexes terminal input, timers and background task messages
+#[derive(Debug)]
+pub struct EventLoop {
+    tx: UnboundedSender<Event>,
+    rx: UnboundedReceiver<Event>,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl EventLoop {
+    /// Start reading terminal input and signals, and firing ticks and frames at the given rates
+    pub fn new(tick_rate: Duration, frame_rate: Duration) -> Self {
+        Self::start(tick_rate, frame_rate, true)
+    }
+
+    /// Start the timers only, for driving the app without a terminal
+    pub fn without_input(tick_rate: Duration, frame_rate: Duration) -> Self {
+        Self::start(tick_rate, frame_rate, false)
+    }
+
+    /// Start the timers and play a script's steps as input, resizing `backend`
+    /// when the script does
+    pub fn scripted(
+        script: Script,
+        backend: HeadlessBackend,
+        tick_rate: Duration,
+        frame_rate: Duration,
+    ) -> Self {
+        let mut events = Self::without_input(tick_rate, frame_rate);
+        events
+            .tasks
+            .push(tokio::spawn(Self::play(events.tx.clone(), script, backend)));
+        events
+    }
+
+    fn start(tick_rate: Duration, frame_rate: Duration, read_input: bool) -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let mut tasks = vec![tokio::spawn(Self::pump(
+            tx.clone(),
+            tick_rate,
+            frame_rate,
+            read_input,
+        ))];
+
+        #[cfg(unix)]
+        if read_input {
+            tasks.push(tokio::spawn(Self::forward_signals(tx.clone())));
+        }
+
+        Self { tx, rx, tasks }
+    }
+
+    async fn pump(
+        tx: UnboundedSender<Event>,
+        tick_rate: Duration,
+        frame_rate: Duration,
+        read_input: bool,
+    ) {
+        let mut reader = read_input.then(EventStream::new);
+        let mut tick = time::interval(tick_rate);
+        let mut render = time::interval(frame_rate);
+        tick.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        render.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+        loop {
+            let input = async {
+                match reader.as_mut() {
+                    Some(reader) => reader.next().await,
+                    None => std::future::pending().await,
+                }
+            };
+
+            let event = tokio::select! {
+                _ = tx.closed() => break,
+                Some(result) = input => match result {
+                    Ok(TerminalEvent::Key(key)) => Event::Key(key),
+                    Ok(TerminalEvent::Mouse(mouse)) => Event::Mouse(mouse),
+                    Ok(TerminalEvent::Resize(width, height)) => Event::Resize(width, height),
+                    Ok(_) => continue,
+                    Err(err) => Event::Error(err.to_string()),
+                },
+                _ = tick.tick() => Event::Tick,
+                _ = render.tick() => Event::Render,
+            };
+
+            if tx.send(event).is_err() {
+                break;
+            }
+        }
+    }
+
+    async fn play(tx: UnboundedSender<Event>, script: Script, backend: HeadlessBackend) {
+        let mouse = |kind, column, row| {
+            Event::Mouse(MouseEvent {
+                kind,
+                column,
+                row,
+                modifiers: KeyModifiers::NONE,
+            })
+        };
+
+        for (line, step) in script.steps {
+            let events = match step {
+                Step::Press(keys) => keys
+                    .into_iter()
+                    .map(|key| Event::Key(KeyEvent::new(key.code, key.modifiers)))
+                    .collect(),
+                Step::Type(text) => text
+                    .chars()
+                    .map(|c| Event::Key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE)))
+                    .collect(),
+                Step::Click { column, row } => vec![
+                    mouse(MouseEventKind::Down(MouseButton::Left), column, row),
+                    mouse(MouseEventKind::Up(MouseButton::Left), column, row),
+                ],
+                Step::Mouse { kind, column, row } => vec![mouse(kind, column, row)],
+                Step::Scroll { down, column, row } => {
+                    let kind = if down {
+                        MouseEventKind::ScrollDown
+                    } else {
+                        MouseEventKind::ScrollUp
+                    };
+                    vec![mouse(kind, column, row)]
+                }
+                Step::Resize { width, height } => {
+                    backend.resize(width, height);
+                    vec![Event::Resize(width, height)]
+                }
+                Step::Wait(duration) => {
+                    time::sleep(duration).await;
+                    Vec::new()
+                }
+                Step::Expect(expect) => vec![Event::Script(ScriptEvent::Expect { line, expect })],
+            };
+            // Draw after each step, so clicks land on what the steps before them showed
+            for event in events.into_iter().chain([Event::Render]) {
+                if tx.send(event).is_err() {
+                    return;
+                }
+            }
+        }
+        let _ = tx.send(Event::Script(ScriptEvent::End));
+    }
+
+    #[cfg(unix)]
+    async fn forward_signals(tx: UnboundedSender<Event>) {
+        use signal_hook::consts::{SIGCONT, SIGTSTP};
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let streams = (
+            signal(SignalKind::interrupt()),
+            signal(SignalKind::terminate()),
+            signal(SignalKind::from_raw(SIGTSTP)),
+            signal(SignalKind::from_raw(SIGCONT)),
+        );
+        let (Ok(mut interrupt), Ok(mut terminate), Ok(mut suspend), Ok(mut resume)) = streams
+        else {
+            warn!("Failed to install signal handlers");
+            return;
+        };
+
+        loop {
+            let signal = tokio::select! {
+                _ = tx.closed() => break,
+                _ = interrupt.recv() => Signal::Interrupt,
+                _ = terminate.recv() => Signal::Terminate,
+                _ = suspend.recv() => Signal::Suspend,
+                _ = resume.recv() => Signal::Resume,
+            };
+
+            if tx.send(Event::Signal(signal)).is_err() {
+                break;
+            }
+        }
+    }
+
+    /// Handle for background tasks to post messages through
+    pub fn sender(&self) -> TaskSender {
+        TaskSender {
+            tx: self.tx.clone(),
+        }
+    }
+
+    /// Push an event into the loop as if it had come from the terminal
+    #[cfg(test)]
+    pub fn push(&self, event: Event) {
+        let _ = self.tx.send(event);
+    }
+
+    /// Wait for the next event
+    pub async fn next(&mut self) -> Option<Event> {
+        self.rx.recv().await
+    }
+}
+
+impl Drop for EventLoop {
+    fn drop(&mut self) {
+        for task in &self.tasks {
+            task.abort();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_ticks_fire() {
+        let mut events =
+            EventLoop::without_input(Duration::from_millis(5), Duration::from_secs(60));
+        let mut ticks = 0;
+
+        while ticks < 3 {
+            if let Some(Event::Tick) = events.next().await {
+                ticks += 1;
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_background_task_messages_are_delivered() {
+        let mut events = EventLoop::without_input(Duration::from_secs(60), Duration::from_secs(60));
+        events.sender().spawn(async { TaskMessage::ThemesChanged });
+
+        loop {
+            if let Some(Event::Task(message)) = events.next().await {
+                assert_eq!(message, TaskMessage::ThemesChanged);
+                break;
+            }
+        }
+    }
+}
@@ -0,0 +1,406 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use crossterm::event::{KeyCode, KeyModifiers};
+use serde_json::{Map, Value as JsonValue};
+use thiserror::Error;
+
+use crate::{data::ColumnKind, keymap::KeyCombo, table::DataTable};
+
+/// File formats rows can be exported to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+    Ndjson,
+    Markdown,
+}
+
+impl ExportFormat {
+    /// All formats, in the order Tab cycles through them
+    pub const ALL: [ExportFormat; 4] = [
+        ExportFormat::Csv,
+        ExportFormat::Json,
+        ExportFormat::Ndjson,
+        ExportFormat::Markdown,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "CSV",
+            ExportFormat::Json => "JSON",
+            ExportFormat::Ndjson => "NDJSON",
+            ExportFormat::Markdown => "Markdown",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+            ExportFormat::Ndjson => "ndjson",
+            ExportFormat::Markdown => "md",
+        }
+    }
+
+    /// The format a file name asks for, by its extension
+    pub fn from_path(path: &Path) -> Option<ExportFormat> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "csv" => Some(ExportFormat::Csv),
+            "json" => Some(ExportFormat::Json),
+            "ndjson" | "jsonl" => Some(ExportFormat::Ndjson),
+            "md" | "markdown" => Some(ExportFormat::Markdown),
+            _ => None,
+        }
+    }
+
+    fn next(&self) -> ExportFormat {
+        let position = Self::ALL
+            .iter()
+            .position(|format| format == self)
+            .unwrap_or(0);
+        Self::ALL[(position + 1) % Self::ALL.len()]
+    }
+}
+
+/// Where exported rows go
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExportTarget {
+    File(PathBuf),
+    /// Printed once the terminal has been restored on exit
+    Stdout,
+}
+
+/// Raised when an export cannot be written
+#[derive(Debug, Error)]
+#[error("failed to write {path}: {source}")]
+pub struct ExportError {
+    pub path: PathBuf,
+    pub source: io::Error,
+}
+
+/// Rows of a table as shown, ready to be written out
+///
+/// Holds a copy of the table, sharing its dataset, and the indexes of the
+/// rows and columns to write, so it is cheap to take and can be written from
+/// another thread. Unsaved edits are written as they are shown.
+#[derive(Debug, Clone)]
+pub struct Export {
+    table: DataTable,
+    rows: Vec<usize>,
+    columns: Vec<usize>,
+    pub format: ExportFormat,
+}
+
+impl Export {
+    /// The selected rows of a table, or every row it shows if none are
+    /// selected, in the order shown and with only the columns shown
+    pub fn new(table: &DataTable, format: ExportFormat) -> Self {
+        Self {
+            table: table.clone(),
+            rows: table.selection(),
+            columns: table.visible_columns(),
+            format,
+        }
+    }
+
+    /// Number of rows written
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn write(&self, out: &mut dyn Write) -> io::Result<()> {
+        match self.format {
+            ExportFormat::Csv => self.write_csv(out),
+            ExportFormat::Json => {
+                let records: Vec<JsonValue> =
+                    self.rows.iter().map(|row| self.record(*row)).collect();
+                serde_json::to_writer_pretty(&mut *out, &records)?;
+                writeln!(out)
+            }
+            ExportFormat::Ndjson => {
+                for row in &self.rows {
+                    serde_json::to_writer(&mut *out, &self.record(*row))?;
+                    writeln!(out)?;
+                }
+                Ok(())
+            }
+            ExportFormat::Markdown => self.write_markdown(out),
+        }
+    }
+
+    /// Write to a file, replacing it if it exists
+    pub fn write_file(&self, path: &Path) -> Result<(), ExportError> {
+        let error = |source| ExportError {
+            path: path.to_path_buf(),
+            source,
+        };
+        let mut out = BufWriter::new(File::create(path).map_err(error)?);
+        self.write(&mut out)
+            .and_then(|_| out.flush())
+            .map_err(error)
+    }
+
+    fn write_csv(&self, out: &mut dyn Write) -> io::Result<()> {
+        let mut writer = csv::Writer::from_writer(out);
+        let dataset = self.table.dataset();
+        writer.write_record(
+            self.columns
+                .iter()
+                .map(|column| &dataset.columns[*column].name),
+        )?;
+        for row in &self.rows {
+            writer.write_record(
+                self.columns
+                    .iter()
+                    .map(|column| self.table.value(*row, *column).to_string()),
+            )?;
+        }
+        writer.flush()
+    }
+
+    /// A row as a JSON object, keeping numbers, booleans and nulls as such
+    fn record(&self, row: usize) -> JsonValue {
+        let mut object = Map::new();
+        for column in &self.columns {
+            let name = self.table.dataset().columns[*column].name.clone();
+            object.insert(name, self.table.value(row, *column).to_json());
+        }
+        JsonValue::Object(object)
+    }
+
+    /// A GitHub-flavoured table, with number columns aligned right
+    fn write_markdown(&self, out: &mut dyn Write) -> io::Result<()> {
+        let line = |cells: Vec<String>| format!("| {} |", cells.join(" | "));
+        let dataset = self.table.dataset();
+        let header = self
+            .columns
+            .iter()
+            .map(|column| markdown_cell(&dataset.columns[*column].name))
+            .collect();
+        let rule = self
+            .columns
+            .iter()
+            .map(|column| match dataset.columns[*column].kind {
+                ColumnKind::Number => "---:".to_string(),
+                _ => "---".to_string(),
+            })
+            .collect();
+
+        writeln!(out, "{}", line(header))?;
+        writeln!(out, "{}", line(rule))?;
+        for row in &self.rows {
+            let cells = self
+                .columns
+                .iter()
+                .map(|column| markdown_cell(&self.table.value(*row, *column).to_string()))
+                .collect();
+            writeln!(out, "{}", line(cells))?;
+        }
+        Ok(())
+    }
+}
+
+/// Text that cannot break out of its table cell
+fn markdown_cell(text: &str) -> String {
+    text.replace('|', "\\|").replace(['\r', '\n'], " ")
+}
+
+/// What a key press in the export prompt asks for
+#[derive(Debug, Clone, PartialEq)]
+pub enum PromptOutcome {
+    Edited,
+    Unchanged,
+    Submit(ExportTarget),
+    Cancel,
+}
+
+/// The prompt for where to export rows and in which format
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExportPrompt {
+    /// A file name, or `-` for stdout
+    pub text: String,
+    pub format: ExportFormat,
+}
+
+impl ExportPrompt {
+    /// A prompt suggesting a file named after the data shown, e.g. `sales-export.csv`
+    pub fn new(name: Option<&str>) -> Self {
+        let stem = name
+            .map(|name| {
+                Path::new(name)
+                    .file_stem()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .filter(|stem| !stem.is_empty())
+            .map_or_else(|| "export".to_string(), |stem| format!("{}-export", stem));
+        let format = ExportFormat::Csv;
+        Self {
+            text: format!("{}.{}", stem, format.extension()),
+            format,
+        }
+    }
+
+    /// Type a file name, cycle formats with Tab, or submit; `None` for keys
+    /// the prompt leaves to the keymap
+    pub fn input(&mut self, key: KeyCombo) -> Option<PromptOutcome> {
+        if key.modifiers.contains(KeyModifiers::CONTROL) {
+            return None;
+        }
+
+        let outcome = match key.code {
+            KeyCode::Char(c) => {
+                self.text.push(c);
+                self.follow_extension();
+                PromptOutcome::Edited
+            }
+            KeyCode::Backspace if !self.text.is_empty() => {
+                self.text.pop();
+                self.follow_extension();
+                PromptOutcome::Edited
+            }
+            KeyCode::Tab => {
+                self.format = self.format.next();
+                if self.text != "-" {
+                    let mut path = PathBuf::from(&self.text);
+                    path.set_extension(self.format.extension());
+                    self.text = path.to_string_lossy().into_owned();
+                }
+                PromptOutcome::Edited
+            }
+            KeyCode::Enter => match self.text.trim() {
+                "" => PromptOutcome::Unchanged,
+                "-" => PromptOutcome::Submit(ExportTarget::Stdout),
+                text => PromptOutcome::Submit(ExportTarget::File(PathBuf::from(text))),
+            },
+            KeyCode::Esc => PromptOutcome::Cancel,
+            _ => PromptOutcome::Unchanged,
+        };
+
+        Some(outcome)
+    }
+
+    /// Switch to the format a typed extension names
+    fn follow_extension(&mut self) {
+        if let Some(format) = ExportFormat::from_path(Path::new(&self.text)) {
+            self.format = format;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::Filter;
+
+    /// Projects with a value over 3000, by value, without the ID and Started columns
+    fn export(format: ExportFormat) -> String {
+        let mut table = DataTable::default();
+        let filter = Filter::parse("value > 3000", &table.dataset().columns).unwrap();
+        table.set_filter(filter);
+        table.hide_column();
+        for _ in 0..4 {
+            table.move_column(true);
+        }
+        table.hide_column();
+        table.toggle_sort();
+
+        let mut out = Vec::new();
+        Export::new(&table, format).write(&mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_exports_rows_as_shown() {
+        assert_eq!(
+            export(ExportFormat::Csv),
+            "Name,Status,Value\nProject Delta,Active,3456\nProject Beta,Pending,5678\nProject Gamma,Completed,9012\n"
+        );
+        assert_eq!(
+            export(ExportFormat::Ndjson).lines().next(),
+            Some(r#"{"Name":"Project Delta","Status":"Active","Value":3456}"#)
+        );
+        assert_eq!(
+            export(ExportFormat::Markdown)
+                .lines()
+                .take(3)
+                .collect::<Vec<_>>(),
+            vec![
+                "| Name | Status | Value |",
+                "| --- | --- | ---: |",
+                "| Project Delta | Active | 3456 |"
+            ]
+        );
+
+        let json: JsonValue = serde_json::from_str(&export(ExportFormat::Json)).unwrap();
+        assert_eq!(json[2]["Value"], JsonValue::from(9012));
+    }
+
+    #[test]
+    fn test_selected_rows_are_exported_alone() {
+        let mut table = DataTable::default();
+        table.go_to(2);
+        table.toggle_mark();
+
+        let mut out = Vec::new();
+        Export::new(&table, ExportFormat::Ndjson)
+            .write(&mut out)
+            .unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert_eq!(out.lines().count(), 1);
+        assert!(out.contains(r#""Started":"2023-11-20""#));
+
+        let err = Export::new(&table, ExportFormat::Csv)
+            .write_file(Path::new("/nonexistent/out.csv"))
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("failed to write /nonexistent/out.csv"));
+    }
+
+    #[test]
+    fn test_prompt_follows_format() {
+        let key = |code| KeyCombo::new(code, KeyModifiers::NONE);
+        let mut prompt = ExportPrompt::new(Some("sales.csv"));
+        assert_eq!(prompt.text, "sales-export.csv");
+
+        prompt.input(key(KeyCode::Tab));
+        assert_eq!(
+            (prompt.text.as_str(), prompt.format),
+            ("sales-export.json", ExportFormat::Json)
+        );
+
+        // Typing an extension picks its format
+        prompt.input(key(KeyCode::Backspace));
+        prompt.input(key(KeyCode::Backspace));
+        assert_eq!(
+            (prompt.text.as_str(), prompt.format),
+            ("sales-export.js", ExportFormat::Json)
+        );
+        for c in "onl".chars() {
+            prompt.input(key(KeyCode::Char(c)));
+        }
+        assert_eq!(prompt.format, ExportFormat::Ndjson);
+        assert_eq!(
+            prompt.input(key(KeyCode::Enter)),
+            Some(PromptOutcome::Submit(ExportTarget::File(PathBuf::from(
+                "sales-export.jsonl"
+            ))))
+        );
+
+        let mut prompt = ExportPrompt::new(None);
+        prompt.text = "-".to_string();
+        prompt.input(key(KeyCode::Tab));
+        assert_eq!(prompt.text, "-");
+        assert_eq!(
+            prompt.input(key(KeyCode::Enter)),
+            Some(PromptOutcome::Submit(ExportTarget::Stdout))
+        );
+    }
+}
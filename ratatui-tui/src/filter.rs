@@ -0,0 +1,450 @@
+use std::{borrow::Cow, cmp::Ordering};
+
+use crossterm::event::{KeyCode, KeyModifiers};
+use thiserror::Error;
+
+use crate::{
+    data::{Column, InvalidValue, Value},
+    keymap::KeyCombo,
+    table::DataTable,
+};
+
+/// Filters remembered for recall with Up/Down
+const HISTORY_LIMIT: usize = 50;
+
+/// Comparison operators, longest first so `>=` is not read as `>`
+const OPERATORS: [(&str, Operator); 7] = [
+    ("!=", Operator::Ne),
+    (">=", Operator::Ge),
+    ("<=", Operator::Le),
+    ("=", Operator::Eq),
+    (">", Operator::Gt),
+    ("<", Operator::Lt),
+    ("~", Operator::Contains),
+];
+
+/// Errors in a typed filter
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum FilterError {
+    #[error("no column named '{0}'")]
+    UnknownColumn(String),
+    #[error(transparent)]
+    InvalidValue(#[from] InvalidValue),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operator {
+    Eq,
+    Ne,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+    Contains,
+}
+
+/// One condition every shown row meets
+#[derive(Debug, Clone, PartialEq)]
+enum Clause {
+    /// Letters that appear in order in some cell, ignoring case
+    Fuzzy(Vec<char>),
+    /// A cell compared with a value of its column's kind
+    Compare {
+        column: usize,
+        operator: Operator,
+        value: Value,
+    },
+    /// A cell containing some text, ignoring case
+    Contains { column: usize, text: Vec<char> },
+}
+
+/// A parsed filter: clauses joined by `and`, each either fuzzy text matched
+/// against every column or a predicate on one column, like `value > 1000`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Filter {
+    text: String,
+    clauses: Vec<Clause>,
+}
+
+impl Filter {
+    /// Parse a filter against the columns it will be applied to; `None` for blank text
+    ///
+    /// Predicate values are read as the column's kind, so `value > 1000`
+    /// compares numbers and `started < 2024-01-01` compares dates.
+    pub fn parse(text: &str, columns: &[Column]) -> Result<Option<Filter>, FilterError> {
+        let mut clauses = Vec::new();
+        for part in split_and(text) {
+            if let Some(clause) = parse_clause(part, columns)? {
+                clauses.push(clause);
+            }
+        }
+
+        if clauses.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(Filter {
+            text: text.trim().to_string(),
+            clauses,
+        }))
+    }
+
+    /// The filter as typed
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Whether a row of the table, by dataset index, meets every clause
+    pub fn matches(&self, table: &DataTable, row: usize) -> bool {
+        self.clauses.iter().all(|clause| match clause {
+            Clause::Fuzzy(pattern) => (0..table.dataset().columns.len()).any(|column| {
+                fuzzy_positions(pattern, &cell_text(table.value(row, column))).is_some()
+            }),
+            Clause::Compare {
+                column,
+                operator,
+                value,
+            } => compare(table.value(row, *column), *operator, value),
+            Clause::Contains { column, text } => {
+                find(&lower_chars(&cell_text(table.value(row, *column))), text).is_some()
+            }
+        })
+    }
+
+    /// Positions of the characters in a cell's text that the filter matched, in order
+    pub fn highlights(&self, column: usize, text: &str) -> Vec<usize> {
+        let mut positions = Vec::new();
+        for clause in &self.clauses {
+            match clause {
+                Clause::Fuzzy(pattern) => {
+                    positions.extend(fuzzy_positions(pattern, text).unwrap_or_default())
+                }
+                Clause::Compare {
+                    column: compared, ..
+                } if *compared == column => positions.extend(0..text.chars().count()),
+                Clause::Contains {
+                    column: searched,
+                    text: needle,
+                } if *searched == column => {
+                    if let Some(start) = find(&lower_chars(text), needle) {
+                        positions.extend(start..start + needle.len());
+                    }
+                }
+                _ => {}
+            }
+        }
+        positions.sort_unstable();
+        positions.dedup();
+        positions
+    }
+}
+
+/// Add a filter to the end of the history, moving it there if it was already in it
+pub fn remember(history: &mut Vec<String>, text: &str) {
+    let text = text.trim();
+    if text.is_empty() {
+        return;
+    }
+    history.retain(|entry| entry != text);
+    history.push(text.to_string());
+    if history.len() > HISTORY_LIMIT {
+        history.remove(0);
+    }
+}
+
+/// What a key press in the filter bar asks for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterOutcome {
+    /// The text changed, so the filter should be parsed again
+    Edited,
+    Unchanged,
+    /// Keep the filter and close the bar
+    Apply,
+    /// Go back to the filter in force when the bar opened
+    Cancel,
+}
+
+/// The `/` prompt under the Data Browser
+#[derive(Debug, Clone, PartialEq)]
+pub struct FilterBar {
+    pub text: String,
+    /// Why the text does not parse; the last filter that did stays applied
+    pub error: Option<String>,
+    /// The filter in force when the bar opened
+    pub previous: Option<Filter>,
+    /// Position in the history while Up/Down are recalling it
+    recalled: Option<usize>,
+}
+
+impl FilterBar {
+    /// A bar for editing the filter in force, if any
+    pub fn new(previous: Option<Filter>) -> Self {
+        Self {
+            text: previous
+                .as_ref()
+                .map(|filter| filter.text().to_string())
+                .unwrap_or_default(),
+            error: None,
+            previous,
+            recalled: None,
+        }
+    }
+
+    /// Type, recall earlier filters with Up/Down, apply or cancel; `None` for
+    /// keys the bar leaves to the keymap
+    pub fn input(&mut self, key: KeyCombo, history: &[String]) -> Option<FilterOutcome> {
+        if key.modifiers.contains(KeyModifiers::CONTROL) {
+            return None;
+        }
+
+        let outcome = match key.code {
+            KeyCode::Char(c) => {
+                self.text.push(c);
+                self.recalled = None;
+                FilterOutcome::Edited
+            }
+            KeyCode::Backspace if !self.text.is_empty() => {
+                self.text.pop();
+                self.recalled = None;
+                FilterOutcome::Edited
+            }
+            KeyCode::Up if !history.is_empty() => {
+                let position = self
+                    .recalled
+                    .map_or(history.len() - 1, |position| position.saturating_sub(1));
+                self.recall(history, Some(position))
+            }
+            // Past the newest entry the bar is empty again
+            KeyCode::Down if self.recalled.is_some() => {
+                let position = self
+                    .recalled
+                    .map(|position| position + 1)
+                    .filter(|next| *next < history.len());
+                self.recall(history, position)
+            }
+            KeyCode::Enter => FilterOutcome::Apply,
+            KeyCode::Esc => FilterOutcome::Cancel,
+            _ => FilterOutcome::Unchanged,
+        };
+
+        Some(outcome)
+    }
+
+    fn recall(&mut self, history: &[String], position: Option<usize>) -> FilterOutcome {
+        self.recalled = position;
+        self.text = position
+            .map(|position| history[position].clone())
+            .unwrap_or_default();
+        FilterOutcome::Edited
+    }
+}
+
+/// Split on the word `and`, ignoring case
+fn split_and(text: &str) -> Vec<&str> {
+    // ASCII lowercasing keeps byte offsets the same as in `text`
+    let lower = text.to_ascii_lowercase();
+    let mut parts = Vec::new();
+    let mut start = 0;
+    for (index, separator) in lower.match_indices(" and ") {
+        parts.push(&text[start..index]);
+        start = index + separator.len();
+    }
+    parts.push(&text[start..]);
+    parts
+}
+
+fn parse_clause(text: &str, columns: &[Column]) -> Result<Option<Clause>, FilterError> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Ok(None);
+    }
+
+    // The first operator in the text splits the column name from the value
+    let found = text.char_indices().find_map(|(index, _)| {
+        OPERATORS
+            .iter()
+            .find(|(symbol, _)| text[index..].starts_with(symbol))
+            .map(|(symbol, operator)| (index, symbol.len(), *operator))
+    });
+    let Some((index, length, operator)) = found else {
+        return Ok(Some(Clause::Fuzzy(lower_chars(&text.replace(' ', "")))));
+    };
+
+    let name = text[..index].trim();
+    let value = unquote(text[index + length..].trim());
+    let column = columns
+        .iter()
+        .position(|column| column.name.eq_ignore_ascii_case(name))
+        .ok_or_else(|| FilterError::UnknownColumn(name.to_string()))?;
+
+    if operator == Operator::Contains {
+        return Ok(Some(Clause::Contains {
+            column,
+            text: lower_chars(value),
+        }));
+    }
+
+    let value = columns[column].parse(value)?;
+    Ok(Some(Clause::Compare {
+        column,
+        operator,
+        value,
+    }))
+}
+
+/// Text without the double quotes around it, so values can have leading spaces
+fn unquote(text: &str) -> &str {
+    text.strip_prefix('"')
+        .and_then(|text| text.strip_suffix('"'))
+        .unwrap_or(text)
+}
+
+fn compare(cell: &Value, operator: Operator, value: &Value) -> bool {
+    let ordering = cell.compare(value);
+    // Empty cells are neither more nor less than anything
+    if cell == &Value::Null && !matches!(operator, Operator::Eq | Operator::Ne) {
+        return false;
+    }
+
+    match operator {
+        Operator::Eq => ordering == Ordering::Equal,
+        Operator::Ne => ordering != Ordering::Equal,
+        Operator::Gt => ordering == Ordering::Greater,
+        Operator::Ge => ordering != Ordering::Less,
+        Operator::Lt => ordering == Ordering::Less,
+        Operator::Le => ordering != Ordering::Greater,
+        Operator::Contains => unreachable!("contains is matched on text"),
+    }
+}
+
+/// A cell as shown, borrowing text cells rather than copying them
+fn cell_text(value: &Value) -> Cow<'_, str> {
+    match value {
+        Value::Text(text) => Cow::Borrowed(text),
+        value => Cow::Owned(value.to_string()),
+    }
+}
+
+/// Lowercase one character at a time, so positions still line up with the original
+fn lower_chars(text: &str) -> Vec<char> {
+    text.chars()
+        .map(|c| c.to_lowercase().next().unwrap_or(c))
+        .collect()
+}
+
+/// Where each letter of a lowercase pattern was found, in order, in `text`
+fn fuzzy_positions(pattern: &[char], text: &str) -> Option<Vec<usize>> {
+    let mut positions = Vec::with_capacity(pattern.len());
+    let mut wanted = pattern.iter().peekable();
+    for (index, c) in lower_chars(text).into_iter().enumerate() {
+        match wanted.peek() {
+            Some(next) if **next == c => {
+                positions.push(index);
+                wanted.next();
+            }
+            Some(_) => {}
+            None => break,
+        }
+    }
+    wanted.peek().is_none().then_some(positions)
+}
+
+/// Position of the first occurrence of `needle` in `haystack`
+fn find(haystack: &[char], needle: &[char]) -> Option<usize> {
+    if needle.is_empty() {
+        return Some(0);
+    }
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::Dataset;
+
+    fn rows(filter: &str) -> Vec<usize> {
+        let table = DataTable::default();
+        let filter = Filter::parse(filter, &table.dataset().columns)
+            .unwrap()
+            .unwrap();
+        (0..table.len())
+            .filter(|row| filter.matches(&table, *row))
+            .collect()
+    }
+
+    #[test]
+    fn test_predicates_compare_typed_values() {
+        assert_eq!(rows("status = active"), vec![0, 3]);
+        assert_eq!(rows("Value > 1000"), vec![0, 1, 2, 3]);
+        // 900 < 1234 as numbers, though not as text
+        assert_eq!(rows("value >= 900 and value < 5000"), vec![0, 3]);
+        assert_eq!(rows("started < 2024/01/01"), vec![2, 4]);
+        assert_eq!(rows("name ~ BET"), vec![1]);
+        assert_eq!(rows("status != Active and name ~ a"), vec![1, 2]);
+    }
+
+    #[test]
+    fn test_fuzzy_text_matches_any_column() {
+        assert_eq!(rows("pgam"), vec![2]);
+        assert_eq!(rows("proj delta"), vec![3]);
+        assert_eq!(rows("2023-11"), vec![2]);
+        assert!(rows("xyz").is_empty());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let columns = Dataset::sample().columns;
+        assert_eq!(
+            Filter::parse("owner = me", &columns),
+            Err(FilterError::UnknownColumn("owner".to_string()))
+        );
+        assert_eq!(
+            Filter::parse("value > lots", &columns)
+                .unwrap_err()
+                .to_string(),
+            "Value is a number column; 'lots' is not a number"
+        );
+        assert_eq!(Filter::parse("  ", &columns), Ok(None));
+    }
+
+    #[test]
+    fn test_highlights() {
+        let columns = Dataset::sample().columns;
+        let filter = Filter::parse("pa and name ~ alp and value > 0", &columns)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(filter.highlights(1, "Project Alpha"), vec![0, 8, 9, 10]);
+        assert_eq!(filter.highlights(3, "1234"), vec![0, 1, 2, 3]);
+        assert!(filter.highlights(2, "Active").is_empty());
+    }
+
+    #[test]
+    fn test_history_is_recalled_newest_first() {
+        let mut history = Vec::new();
+        for text in ["a", "b", "a", " "] {
+            remember(&mut history, text);
+        }
+        assert_eq!(history, vec!["b", "a"]);
+
+        let key = |code| KeyCombo::new(code, KeyModifiers::NONE);
+        let mut bar = FilterBar::new(None);
+        bar.input(key(KeyCode::Up), &history);
+        assert_eq!(bar.text, "a");
+        bar.input(key(KeyCode::Up), &history);
+        bar.input(key(KeyCode::Up), &history);
+        assert_eq!(bar.text, "b");
+        bar.input(key(KeyCode::Down), &history);
+        bar.input(key(KeyCode::Down), &history);
+        assert_eq!(bar.text, "");
+        assert_eq!(
+            bar.input(key(KeyCode::Down), &history),
+            Some(FilterOutcome::Unchanged)
+        );
+        assert_eq!(
+            bar.input(key(KeyCode::Enter), &history),
+            Some(FilterOutcome::Apply)
+        );
+    }
+}
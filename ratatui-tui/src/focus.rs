@@ -0,0 +1,179 @@
+use std::collections::HashMap;
+
+use crate::views::ViewId;
+
+/// Something on screen that can hold keyboard focus
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum FocusId {
+    Sidebar,
+    /// The current view as a whole
+    #[default]
+    Content,
+    /// A focusable element inside the current view, by position
+    Child(usize),
+}
+
+impl FocusId {
+    /// The element that gets an event this one leaves unhandled
+    pub fn parent(&self) -> Option<FocusId> {
+        match self {
+            FocusId::Child(_) => Some(FocusId::Content),
+            FocusId::Sidebar | FocusId::Content => None,
+        }
+    }
+
+    /// Whether this is the current view or something in it
+    pub fn in_content(&self) -> bool {
+        matches!(self, FocusId::Content | FocusId::Child(_))
+    }
+}
+
+/// Tracks which element has focus and remembers it for each view
+#[derive(Debug, Clone, Default)]
+pub struct FocusManager {
+    current: FocusId,
+    remembered: HashMap<ViewId, FocusId>,
+}
+
+impl FocusManager {
+    /// The elements focus cycles through: the sidebar if shown, then the
+    /// view's children, or the view itself when it has none
+    pub fn ring(sidebar: bool, children: usize) -> Vec<FocusId> {
+        let content: Vec<FocusId> = if children == 0 {
+            vec![FocusId::Content]
+        } else {
+            (0..children).map(FocusId::Child).collect()
+        };
+
+        sidebar
+            .then_some(FocusId::Sidebar)
+            .into_iter()
+            .chain(content)
+            .collect()
+    }
+
+    pub fn focused(&self) -> FocusId {
+        self.current
+    }
+
+    /// The focused element followed by its parents, in the order events bubble
+    pub fn path(&self) -> Vec<FocusId> {
+        std::iter::successors(Some(self.current), FocusId::parent).collect()
+    }
+
+    pub fn focus(&mut self, id: FocusId) {
+        self.current = id;
+    }
+
+    pub fn focus_next(&mut self, ring: &[FocusId]) {
+        self.step(ring, 1);
+    }
+
+    pub fn focus_prev(&mut self, ring: &[FocusId]) {
+        self.step(ring, ring.len().saturating_sub(1));
+    }
+
+    /// Remember focus in the view being left and restore it in the one being entered
+    pub fn switch_view(&mut self, from: ViewId, to: ViewId, ring: &[FocusId]) {
+        self.remembered.insert(from, self.current);
+        self.current = self
+            .remembered
+            .get(&to)
+            .copied()
+            .filter(|id| ring.contains(id))
+            .unwrap_or_else(|| default_focus(ring));
+    }
+
+    /// Focus the view, keeping whichever of its children already has focus
+    pub fn focus_content(&mut self, ring: &[FocusId]) {
+        if !self.current.in_content() {
+            self.current = default_focus(ring);
+        }
+    }
+
+    /// Move focus into the ring if the element holding it has gone, e.g. a hidden sidebar
+    pub fn retain(&mut self, ring: &[FocusId]) {
+        if !ring.contains(&self.current) {
+            self.current = default_focus(ring);
+        }
+    }
+
+    fn step(&mut self, ring: &[FocusId], by: usize) {
+        if ring.is_empty() {
+            return;
+        }
+        self.current = match ring.iter().position(|id| *id == self.current) {
+            Some(index) => ring[(index + by) % ring.len()],
+            None => default_focus(ring),
+        };
+    }
+}
+
+/// The first element of the view, which is where focus starts
+fn default_focus(ring: &[FocusId]) -> FocusId {
+    ring.iter()
+        .copied()
+        .find(FocusId::in_content)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ring_wraps_both_ways() {
+        let ring = FocusManager::ring(true, 2);
+        assert_eq!(
+            ring,
+            vec![FocusId::Sidebar, FocusId::Child(0), FocusId::Child(1)]
+        );
+
+        let mut focus = FocusManager::default();
+        focus.focus_next(&ring);
+        assert_eq!(focus.focused(), FocusId::Child(0));
+        focus.focus_prev(&ring);
+        assert_eq!(focus.focused(), FocusId::Sidebar);
+        focus.focus_prev(&ring);
+        assert_eq!(focus.focused(), FocusId::Child(1));
+        focus.focus_next(&ring);
+        assert_eq!(focus.focused(), FocusId::Sidebar);
+    }
+
+    #[test]
+    fn test_events_bubble_from_children_to_content() {
+        let mut focus = FocusManager::default();
+        focus.focus(FocusId::Child(3));
+        assert_eq!(focus.path(), vec![FocusId::Child(3), FocusId::Content]);
+
+        focus.focus(FocusId::Sidebar);
+        assert_eq!(focus.path(), vec![FocusId::Sidebar]);
+    }
+
+    #[test]
+    fn test_focus_is_restored_per_view() {
+        let plain = FocusManager::ring(true, 0);
+        let form = FocusManager::ring(true, 3);
+        let mut focus = FocusManager::default();
+
+        focus.focus(FocusId::Sidebar);
+        focus.switch_view(ViewId::Dashboard, ViewId::Settings, &form);
+        assert_eq!(focus.focused(), FocusId::Child(0));
+
+        focus.focus(FocusId::Child(2));
+        focus.switch_view(ViewId::Settings, ViewId::Dashboard, &plain);
+        assert_eq!(focus.focused(), FocusId::Sidebar);
+
+        focus.switch_view(ViewId::Dashboard, ViewId::Settings, &form);
+        assert_eq!(focus.focused(), FocusId::Child(2));
+    }
+
+    #[test]
+    fn test_hidden_sidebar_loses_focus() {
+        let mut focus = FocusManager::default();
+        focus.focus(FocusId::Sidebar);
+        focus.retain(&FocusManager::ring(false, 0));
+
+        assert_eq!(focus.focused(), FocusId::Content);
+    }
+}
@@ -0,0 +1,784 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use thiserror::Error;
+
+use crate::{action::Action, dashboard::Side, views::ViewId};
+
+const KEYMAP_FILE: &str = "keymap.toml";
+
+/// Value used in a keymap file to remove a default binding
+const UNBIND: &str = "none";
+
+/// Errors raised while loading a keymap
+#[derive(Debug, Error)]
+pub enum KeymapError {
+    #[error("failed to read keymap {path}: {source}")]
+    Io { path: PathBuf, source: io::Error },
+    #[error("invalid keymap file: {0}")]
+    Parse(#[from] toml::de::Error),
+    #[error("unknown keymap context '{0}'")]
+    UnknownContext(String),
+    #[error("invalid key '{0}'")]
+    InvalidKey(String),
+    #[error("invalid key '{0}': write the character Shift types instead, e.g. 'A' or '?'")]
+    ShiftedChar(String),
+    #[error("{0}")]
+    UnknownAction(String),
+    #[error("conflicting bindings in [{context}]: '{first}' ({first_action}) and '{second}' ({second_action})")]
+    Conflict {
+        context: String,
+        first: String,
+        first_action: String,
+        second: String,
+        second_action: String,
+    },
+}
+
+/// Where a binding applies
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum KeyContext {
+    Global,
+    View(ViewId),
+}
+
+impl KeyContext {
+    pub fn name(&self) -> &'static str {
+        match self {
+            KeyContext::Global => "global",
+            KeyContext::View(view) => view.as_str(),
+        }
+    }
+}
+
+impl FromStr for KeyContext {
+    type Err = KeymapError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "global" {
+            return Ok(KeyContext::Global);
+        }
+        s.parse()
+            .map(KeyContext::View)
+            .map_err(|_| KeymapError::UnknownContext(s.to_string()))
+    }
+}
+
+/// A single key press together with its modifiers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyCombo {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyCombo {
+    pub fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        // Terminals disagree on how shift is reported, so fold it into the key
+        // itself: 'G' rather than Shift+g, BackTab rather than Shift+BackTab.
+        let (code, modifiers) = match code {
+            KeyCode::Char(c) if modifiers.contains(KeyModifiers::CONTROL) => (
+                KeyCode::Char(c.to_ascii_lowercase()),
+                modifiers - KeyModifiers::SHIFT,
+            ),
+            KeyCode::Char(_) | KeyCode::BackTab => (code, modifiers - KeyModifiers::SHIFT),
+            KeyCode::Tab if modifiers.contains(KeyModifiers::SHIFT) => {
+                (KeyCode::BackTab, modifiers - KeyModifiers::SHIFT)
+            }
+            _ => (code, modifiers),
+        };
+
+        Self { code, modifiers }
+    }
+}
+
+impl From<KeyEvent> for KeyCombo {
+    fn from(key: KeyEvent) -> Self {
+        Self::new(key.code, key.modifiers)
+    }
+}
+
+impl FromStr for KeyCombo {
+    type Err = KeymapError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || KeymapError::InvalidKey(s.to_string());
+
+        if s.chars().count() == 1 {
+            let c = s.chars().next().ok_or_else(invalid)?;
+            return Ok(Self::new(KeyCode::Char(c), KeyModifiers::NONE));
+        }
+
+        let mut parts: Vec<&str> = s.split('+').collect();
+        let key = parts.pop().ok_or_else(invalid)?;
+
+        let mut modifiers = KeyModifiers::NONE;
+        for part in parts {
+            modifiers |= match part.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return Err(invalid()),
+            };
+        }
+
+        let code = if key.chars().count() == 1 {
+            let c = key.chars().next().ok_or_else(invalid)?;
+            // Shift is folded into the character typed, so Shift+a is 'A'; what
+            // Shift does to other characters depends on the keyboard layout
+            if modifiers.contains(KeyModifiers::SHIFT) && !modifiers.contains(KeyModifiers::CONTROL)
+            {
+                if !c.is_ascii_alphabetic() {
+                    return Err(KeymapError::ShiftedChar(s.to_string()));
+                }
+                KeyCode::Char(c.to_ascii_uppercase())
+            } else {
+                KeyCode::Char(c)
+            }
+        } else {
+            match key.to_ascii_lowercase().as_str() {
+                "esc" | "escape" => KeyCode::Esc,
+                "enter" | "return" => KeyCode::Enter,
+                "tab" => KeyCode::Tab,
+                "backtab" => KeyCode::BackTab,
+                "space" => KeyCode::Char(' '),
+                "backspace" => KeyCode::Backspace,
+                "del" | "delete" => KeyCode::Delete,
+                "ins" | "insert" => KeyCode::Insert,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pgup" | "pageup" => KeyCode::PageUp,
+                "pgdn" | "pagedown" => KeyCode::PageDown,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                name => match name.strip_prefix('f').and_then(|n| n.parse().ok()) {
+                    Some(n @ 1..=12) => KeyCode::F(n),
+                    _ => return Err(invalid()),
+                },
+            }
+        };
+
+        Ok(Self::new(code, modifiers))
+    }
+}
+
+impl fmt::Display for KeyCombo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "Ctrl+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "Alt+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            write!(f, "Shift+")?;
+        }
+
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "Space"),
+            KeyCode::Char(c) if !self.modifiers.is_empty() => {
+                write!(f, "{}", c.to_ascii_uppercase())
+            }
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::F(n) => write!(f, "F{}", n),
+            KeyCode::Esc => write!(f, "Esc"),
+            KeyCode::Enter => write!(f, "Enter"),
+            KeyCode::Tab => write!(f, "Tab"),
+            KeyCode::BackTab => write!(f, "Shift+Tab"),
+            KeyCode::Backspace => write!(f, "Backspace"),
+            KeyCode::Delete => write!(f, "Del"),
+            KeyCode::Insert => write!(f, "Ins"),
+            KeyCode::Home => write!(f, "Home"),
+            KeyCode::End => write!(f, "End"),
+            KeyCode::PageUp => write!(f, "PgUp"),
+            KeyCode::PageDown => write!(f, "PgDn"),
+            KeyCode::Up => write!(f, "Up"),
+            KeyCode::Down => write!(f, "Down"),
+            KeyCode::Left => write!(f, "Left"),
+            KeyCode::Right => write!(f, "Right"),
+            other => write!(f, "{:?}", other),
+        }
+    }
+}
+
+/// One or more key presses that trigger an action, e.g. `g g`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct KeySequence(Vec<KeyCombo>);
+
+impl KeySequence {
+    fn is_prefix_of(&self, other: &KeySequence) -> bool {
+        self.0.len() < other.0.len() && other.0.starts_with(&self.0)
+    }
+}
+
+impl FromStr for KeySequence {
+    type Err = KeymapError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let keys = s
+            .split_whitespace()
+            .map(KeyCombo::from_str)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if keys.is_empty() {
+            return Err(KeymapError::InvalidKey(s.to_string()));
+        }
+        Ok(Self(keys))
+    }
+}
+
+impl fmt::Display for KeySequence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let keys: Vec<String> = self.0.iter().map(KeyCombo::to_string).collect();
+        write!(f, "{}", keys.join(" "))
+    }
+}
+
+/// Outcome of feeding pending key presses to the keymap
+#[derive(Debug, Clone, PartialEq)]
+pub enum KeyResolution {
+    /// The keys complete a binding
+    Action(Action),
+    /// The keys are the start of a longer binding
+    Pending,
+    /// Nothing is bound to the keys
+    Unbound,
+}
+
+/// An action together with the keys currently bound to it
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyHint {
+    pub action: Action,
+    pub keys: Vec<String>,
+}
+
+impl KeyHint {
+    pub fn keys_label(&self) -> String {
+        self.keys.join("/")
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Binding {
+    context: KeyContext,
+    sequence: KeySequence,
+    action: Action,
+}
+
+/// Key bindings grouped by context
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    bindings: Vec<Binding>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let global = [
+            ("q", Action::Quit),
+            ("Esc", Action::Quit),
+            ("Ctrl+C", Action::Quit),
+            ("1", Action::Navigate(ViewId::Dashboard)),
+            ("2", Action::Navigate(ViewId::Data)),
+            ("3", Action::Navigate(ViewId::Settings)),
+            ("4", Action::Navigate(ViewId::Help)),
+            ("t", Action::ToggleTheme),
+            ("k", Action::ScrollUp),
+            ("Up", Action::ScrollUp),
+            ("j", Action::ScrollDown),
+            ("Down", Action::ScrollDown),
+            ("g g", Action::ScrollTop),
+            ("PgUp", Action::PageUp),
+            ("PgDn", Action::PageDown),
+            ("Home", Action::ScrollTop),
+            ("End", Action::ScrollBottom),
+            ("G", Action::ScrollBottom),
+            ("/", Action::Search),
+            ("n", Action::NextMatch),
+            ("N", Action::PrevMatch),
+            ("Tab", Action::FocusNext),
+            ("Shift+Tab", Action::FocusPrev),
+            ("Ctrl+P", Action::OpenPalette),
+            ("Ctrl+Z", Action::Suspend),
+        ];
+        let dashboard = [
+            ("Right", Action::NextPanel),
+            ("l", Action::NextPanel),
+            ("Left", Action::PrevPanel),
+            ("h", Action::PrevPanel),
+            ("H", Action::MovePanel(Side::Left)),
+            ("J", Action::MovePanel(Side::Down)),
+            ("K", Action::MovePanel(Side::Up)),
+            ("L", Action::MovePanel(Side::Right)),
+            ("<", Action::ResizePanel(Side::Left)),
+            (">", Action::ResizePanel(Side::Right)),
+            ("-", Action::ResizePanel(Side::Up)),
+            ("+", Action::ResizePanel(Side::Down)),
+            ("m", Action::MaximisePanel),
+            ("Enter", Action::MaximisePanel),
+            ("s", Action::SaveLayout),
+        ];
+        let settings = [
+            ("Down", Action::FocusNext),
+            ("j", Action::FocusNext),
+            ("Up", Action::FocusPrev),
+            ("k", Action::FocusPrev),
+            ("Enter", Action::Activate),
+            ("Space", Action::Activate),
+            ("Right", Action::Increase),
+            ("l", Action::Increase),
+            ("Left", Action::Decrease),
+            ("h", Action::Decrease),
+            ("s", Action::SaveConfig),
+            ("r", Action::RevertConfig),
+        ];
+        let data = [
+            ("Left", Action::ColumnLeft),
+            ("h", Action::ColumnLeft),
+            ("Right", Action::ColumnRight),
+            ("l", Action::ColumnRight),
+            ("s", Action::ToggleSort),
+            ("c", Action::HideColumn),
+            ("C", Action::ShowAllColumns),
+            ("Space", Action::ToggleMark),
+            ("a", Action::ToggleMarkAll),
+            ("d", Action::ToggleDetail),
+            (":", Action::JumpToRow),
+            ("o", Action::OpenFile),
+            ("/", Action::Filter),
+            ("e", Action::Export),
+            ("Enter", Action::EditCell),
+            ("u", Action::UndoEdit),
+            ("w", Action::SaveData),
+            ("D", Action::ToggleChanges),
+        ];
+
+        let contexts = [
+            (KeyContext::Global, &global[..]),
+            (KeyContext::View(ViewId::Dashboard), &dashboard[..]),
+            (KeyContext::View(ViewId::Settings), &settings[..]),
+            (KeyContext::View(ViewId::Data), &data[..]),
+        ];
+        let bindings = contexts
+            .into_iter()
+            .flat_map(|(context, bindings)| {
+                bindings.iter().map(move |(keys, action)| Binding {
+                    context,
+                    sequence: keys.parse().expect("default key binding is valid"),
+                    action: *action,
+                })
+            })
+            .collect();
+
+        Self { bindings }
+    }
+}
+
+impl Keymap {
+    /// Load the default keymap with overrides from the keymap file in `config_dir`, if present
+    pub fn load(config_dir: Option<&Path>) -> Result<Self, KeymapError> {
+        match config_dir.map(|dir| dir.join(KEYMAP_FILE)) {
+            Some(path) if path.exists() => Self::load_file(&path),
+            _ => Ok(Self::default()),
+        }
+    }
+
+    /// Load the default keymap with overrides from the given file
+    pub fn load_file(path: &Path) -> Result<Self, KeymapError> {
+        let source = fs::read_to_string(path).map_err(|source| KeymapError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        Self::from_toml(&source)
+    }
+
+    /// Apply overrides written as `[context]` tables of `"keys" = "action"`
+    pub fn from_toml(source: &str) -> Result<Self, KeymapError> {
+        let tables: BTreeMap<String, BTreeMap<String, String>> = toml::from_str(source)?;
+        let mut keymap = Self::default();
+
+        for (context_name, entries) in tables {
+            let context: KeyContext = context_name.parse()?;
+            let mut seen: Vec<(KeySequence, &String, &String)> = Vec::new();
+
+            for (keys, action_name) in &entries {
+                let sequence: KeySequence = keys.parse()?;
+                let action = match action_name.as_str() {
+                    UNBIND => None,
+                    name => Some(name.parse().map_err(KeymapError::UnknownAction)?),
+                };
+
+                // Two spellings of the same key in one table, e.g. "ctrl+c" and "Ctrl+C"
+                if let Some((_, first, first_action)) = seen
+                    .iter()
+                    .find(|(seen_sequence, _, _)| *seen_sequence == sequence)
+                {
+                    return Err(KeymapError::Conflict {
+                        context: context_name.clone(),
+                        first: first.to_string(),
+                        first_action: first_action.to_string(),
+                        second: keys.clone(),
+                        second_action: action_name.clone(),
+                    });
+                }
+                seen.push((sequence.clone(), keys, action_name));
+
+                keymap.bind(context, sequence, action);
+            }
+        }
+
+        keymap.validate()?;
+        Ok(keymap)
+    }
+
+    /// Every binding written in the keymap file format, suitable as a starting point for edits
+    pub fn to_toml(&self) -> String {
+        let mut contexts: Vec<KeyContext> = self
+            .bindings
+            .iter()
+            .map(|binding| binding.context)
+            .collect();
+        contexts.sort();
+        contexts.dedup();
+
+        let quote = |s: String| toml::Value::String(s).to_string();
+        let mut out = String::new();
+
+        for context in contexts {
+            if !out.is_empty() {
+                out.push('\n');
+            }
+            out.push_str(&format!("[{}]\n", context.name()));
+
+            for binding in self
+                .bindings
+                .iter()
+                .filter(|binding| binding.context == context)
+            {
+                out.push_str(&format!(
+                    "{} = {}\n",
+                    quote(binding.sequence.to_string()),
+                    quote(binding.action.to_string())
+                ));
+            }
+        }
+
+        out
+    }
+
+    /// Bind a sequence in a context, replacing any existing binding; `None` unbinds it
+    fn bind(&mut self, context: KeyContext, sequence: KeySequence, action: Option<Action>) {
+        self.bindings
+            .retain(|binding| !(binding.context == context && binding.sequence == sequence));
+
+        if let Some(action) = action {
+            self.bindings.push(Binding {
+                context,
+                sequence,
+                action,
+            });
+        }
+    }
+
+    /// Bindings active in a view: the view's own first, then global ones it does not shadow
+    fn effective(&self, view: ViewId) -> Vec<&Binding> {
+        let context = KeyContext::View(view);
+        let local: Vec<&Binding> = self
+            .bindings
+            .iter()
+            .filter(|binding| binding.context == context)
+            .collect();
+        let shadowed: HashSet<&KeySequence> =
+            local.iter().map(|binding| &binding.sequence).collect();
+
+        let global = self.bindings.iter().filter(|binding| {
+            binding.context == KeyContext::Global && !shadowed.contains(&binding.sequence)
+        });
+
+        local.iter().copied().chain(global).collect()
+    }
+
+    /// Reject bindings that can never fire because another binding is a prefix of them
+    fn validate(&self) -> Result<(), KeymapError> {
+        for view in ViewId::ALL {
+            let bindings = self.effective(view);
+
+            for first in &bindings {
+                if let Some(second) = bindings
+                    .iter()
+                    .find(|other| first.sequence.is_prefix_of(&other.sequence))
+                {
+                    let context = if first.context == second.context {
+                        first.context.name().to_string()
+                    } else {
+                        format!("{}/{}", first.context.name(), second.context.name())
+                    };
+
+                    return Err(KeymapError::Conflict {
+                        context,
+                        first: first.sequence.to_string(),
+                        first_action: first.action.to_string(),
+                        second: second.sequence.to_string(),
+                        second_action: second.action.to_string(),
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Look up the keys pressed so far in the given view
+    pub fn resolve(&self, view: ViewId, keys: &[KeyCombo]) -> KeyResolution {
+        let mut pending = false;
+
+        for binding in self.effective(view) {
+            if binding.sequence.0 == keys {
+                return KeyResolution::Action(binding.action);
+            }
+            pending |= binding.sequence.0.starts_with(keys);
+        }
+
+        if pending {
+            KeyResolution::Pending
+        } else {
+            KeyResolution::Unbound
+        }
+    }
+
+    /// Actions available in a view with the keys bound to each
+    pub fn hints(&self, view: ViewId) -> Vec<KeyHint> {
+        let mut by_action: BTreeMap<Action, Vec<String>> = BTreeMap::new();
+
+        for binding in self.effective(view) {
+            by_action
+                .entry(binding.action)
+                .or_default()
+                .push(binding.sequence.to_string());
+        }
+
+        by_action
+            .into_iter()
+            .map(|(action, keys)| KeyHint { action, keys })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn combo(s: &str) -> KeyCombo {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parse_key_combos() {
+        assert_eq!(
+            combo("q"),
+            KeyCombo::new(KeyCode::Char('q'), KeyModifiers::NONE)
+        );
+        assert_eq!(
+            combo("ctrl+c"),
+            KeyCombo::new(KeyCode::Char('c'), KeyModifiers::CONTROL)
+        );
+        assert_eq!(combo("Ctrl+C"), combo("ctrl+c"));
+        assert_eq!(
+            combo("shift+tab"),
+            KeyCombo::new(KeyCode::BackTab, KeyModifiers::NONE)
+        );
+        assert_eq!(
+            combo("PgDn"),
+            KeyCombo::new(KeyCode::PageDown, KeyModifiers::NONE)
+        );
+        // Shift+a is typed as 'A', so it must not shadow a binding for plain 'a'
+        assert_eq!(combo("Shift+a"), combo("A"));
+        assert_eq!(combo("alt+shift+a"), combo("Alt+A"));
+        assert!(matches!(
+            "shift+1".parse::<KeyCombo>(),
+            Err(KeymapError::ShiftedChar(_))
+        ));
+        assert!("hyper+x".parse::<KeyCombo>().is_err());
+        assert!("f13".parse::<KeyCombo>().is_err());
+    }
+
+    #[test]
+    fn test_display_round_trips() {
+        for key in [
+            "q",
+            "G",
+            "Ctrl+C",
+            "Alt+Enter",
+            "Shift+Tab",
+            "Space",
+            "F5",
+            "g g",
+        ] {
+            let sequence: KeySequence = key.parse().unwrap();
+            assert_eq!(sequence.to_string(), key);
+        }
+    }
+
+    #[test]
+    fn test_key_events_match_parsed_combos() {
+        let event = KeyEvent::new(KeyCode::Char('G'), KeyModifiers::SHIFT);
+        assert_eq!(KeyCombo::from(event), combo("G"));
+    }
+
+    #[test]
+    fn test_default_bindings() {
+        let keymap = Keymap::default();
+
+        assert_eq!(
+            keymap.resolve(ViewId::Dashboard, &[combo("2")]),
+            KeyResolution::Action(Action::Navigate(ViewId::Data))
+        );
+        assert_eq!(
+            keymap.resolve(ViewId::Dashboard, &[combo("Ctrl+C")]),
+            KeyResolution::Action(Action::Quit)
+        );
+        assert_eq!(
+            keymap.resolve(ViewId::Dashboard, &[combo("x")]),
+            KeyResolution::Unbound
+        );
+
+        // Form keys only apply in the settings view, where they shadow scrolling
+        assert_eq!(
+            keymap.resolve(ViewId::Settings, &[combo("j")]),
+            KeyResolution::Action(Action::FocusNext)
+        );
+        assert_eq!(
+            keymap.resolve(ViewId::Dashboard, &[combo("j")]),
+            KeyResolution::Action(Action::ScrollDown)
+        );
+        assert_eq!(
+            keymap.resolve(ViewId::Dashboard, &[combo("Tab")]),
+            KeyResolution::Action(Action::FocusNext)
+        );
+        assert_eq!(
+            keymap.resolve(ViewId::Dashboard, &[combo("L")]),
+            KeyResolution::Action(Action::MovePanel(Side::Right))
+        );
+        assert_eq!(
+            keymap.resolve(ViewId::Data, &[combo("s")]),
+            KeyResolution::Action(Action::ToggleSort)
+        );
+        assert_eq!(
+            keymap.resolve(ViewId::Data, &[combo("/")]),
+            KeyResolution::Action(Action::Filter)
+        );
+        assert_eq!(
+            keymap.resolve(ViewId::Help, &[combo("/")]),
+            KeyResolution::Action(Action::Search)
+        );
+        assert_eq!(
+            keymap.resolve(ViewId::Data, &[combo("g"), combo("g")]),
+            KeyResolution::Action(Action::ScrollTop)
+        );
+    }
+
+    #[test]
+    fn test_chords() {
+        let keymap = Keymap::default();
+
+        assert_eq!(
+            keymap.resolve(ViewId::Help, &[combo("g")]),
+            KeyResolution::Pending
+        );
+        assert_eq!(
+            keymap.resolve(ViewId::Help, &[combo("g"), combo("g")]),
+            KeyResolution::Action(Action::ScrollTop)
+        );
+    }
+
+    #[test]
+    fn test_user_overrides() {
+        let keymap = Keymap::from_toml(
+            r#"
+            [global]
+            "q" = "none"
+            "ctrl+q" = "quit"
+
+            [help]
+            "t" = "navigate:dashboard"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            keymap.resolve(ViewId::Dashboard, &[combo("q")]),
+            KeyResolution::Unbound
+        );
+        assert_eq!(
+            keymap.resolve(ViewId::Dashboard, &[combo("Ctrl+Q")]),
+            KeyResolution::Action(Action::Quit)
+        );
+        assert_eq!(
+            keymap.resolve(ViewId::Help, &[combo("t")]),
+            KeyResolution::Action(Action::Navigate(ViewId::Dashboard))
+        );
+        assert_eq!(
+            keymap.resolve(ViewId::Data, &[combo("t")]),
+            KeyResolution::Action(Action::ToggleTheme)
+        );
+    }
+
+    #[test]
+    fn test_conflicts_are_rejected() {
+        let prefix = Keymap::from_toml("[settings]\n\"g\" = \"quit\"\n");
+        assert!(matches!(prefix, Err(KeymapError::Conflict { .. })));
+
+        let duplicate =
+            Keymap::from_toml("[global]\n\"ctrl+x\" = \"quit\"\n\"Ctrl+X\" = \"toggle_theme\"\n");
+        assert!(matches!(duplicate, Err(KeymapError::Conflict { .. })));
+    }
+
+    #[test]
+    fn test_invalid_files_are_rejected() {
+        assert!(matches!(
+            Keymap::from_toml("[global]\n\"x\" = \"explode\"\n"),
+            Err(KeymapError::UnknownAction(_))
+        ));
+        assert!(matches!(
+            Keymap::from_toml("[nowhere]\n\"x\" = \"quit\"\n"),
+            Err(KeymapError::UnknownContext(_))
+        ));
+    }
+
+    #[test]
+    fn test_hints_follow_bindings() {
+        let keymap = Keymap::from_toml("[global]\n\"ctrl+q\" = \"quit\"\n").unwrap();
+        let hints = keymap.hints(ViewId::Dashboard);
+        let quit = hints
+            .iter()
+            .find(|hint| hint.action == Action::Quit)
+            .unwrap();
+
+        assert_eq!(quit.keys_label(), "q/Esc/Ctrl+C/Ctrl+Q");
+    }
+
+    #[test]
+    fn test_dump_round_trips() {
+        let keymap = Keymap::from_toml("[data]\n\"s\" = \"toggle_theme\"\n").unwrap();
+        let dumped = keymap.to_toml();
+
+        assert!(dumped.contains("[data]"));
+        assert!(dumped.contains("\"g g\" = \"scroll_top\""));
+
+        // Reloading rebinds every key, so only the order of keys within a hint may differ
+        let sorted_hints = |keymap: &Keymap, view| {
+            let mut hints = keymap.hints(view);
+            hints.iter_mut().for_each(|hint| hint.keys.sort());
+            hints
+        };
+        let reloaded = Keymap::from_toml(&dumped).unwrap();
+        for view in ViewId::ALL {
+            assert_eq!(sorted_hints(&reloaded, view), sorted_hints(&keymap, view));
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+use log::{Log, Metadata, Record};
+
+/// Log lines kept for the dashboard's log panel
+pub const TAIL_LEN: usize = 200;
+
+#[derive(Debug, Default)]
+struct Lines {
+    recent: VecDeque<String>,
+    /// Every line ever pushed, so readers can tell when there are new ones
+    written: u64,
+}
+
+/// The latest log lines, shared between the logger and the interface
+#[derive(Debug, Clone, Default)]
+pub struct LogTail {
+    lines: Arc<Mutex<Lines>>,
+}
+
+impl LogTail {
+    pub fn push(&self, line: String) {
+        let mut lines = self
+            .lines
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if lines.recent.len() == TAIL_LEN {
+            lines.recent.pop_front();
+        }
+        lines.recent.push_back(line);
+        lines.written += 1;
+    }
+
+    pub fn written(&self) -> u64 {
+        self.lines
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .written
+    }
+
+    /// The newest lines, oldest first
+    pub fn recent(&self) -> Vec<String> {
+        let lines = self
+            .lines
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        lines.recent.iter().cloned().collect()
+    }
+}
+
+/// Writes records through env_logger and keeps a copy of each in a tail
+pub struct TailLogger {
+    inner: env_logger::Logger,
+    tail: LogTail,
+}
+
+impl TailLogger {
+    pub fn new(inner: env_logger::Logger, tail: LogTail) -> Self {
+        Self { inner, tail }
+    }
+}
+
+impl Log for TailLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.inner.matches(record) {
+            return;
+        }
+        self.inner.log(record);
+        self.tail.push(format!(
+            "{} {:<5} {}",
+            chrono::Local::now().format("%H:%M:%S"),
+            record.level(),
+            record.args()
+        ));
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tail_keeps_the_newest_lines() {
+        let tail = LogTail::default();
+        for line in 0..TAIL_LEN + 5 {
+            tail.clone().push(line.to_string());
+        }
+
+        let recent = tail.recent();
+        assert_eq!(tail.written(), TAIL_LEN as u64 + 5);
+        assert_eq!(recent.len(), TAIL_LEN);
+        assert_eq!(recent[0], "5");
+        assert_eq!(recent[TAIL_LEN - 1], (TAIL_LEN + 4).to_string());
+    }
+}
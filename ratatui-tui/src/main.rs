@@ -1,36 +1,150 @@
-use anyhow::Result;
+use std::{
+    env,
+    fs::OpenOptions,
+    io::{self, Write},
+    path::Path,
+    time::Duration,
+};
+
+use anyhow::{Context, Result};
+use clap::Parser;
 use log::info;
 
+mod action;
 mod app;
+mod cli;
+mod clock;
+mod colors;
+mod config;
+mod context;
+mod dashboard;
+mod data;
+mod edit;
+mod event;
+mod export;
+mod filter;
+mod focus;
+mod keymap;
+mod logs;
+mod metrics;
+mod modal;
+mod palette;
+mod paths;
+mod picker;
+mod record;
+mod script;
+mod scroll;
+mod settings;
+#[cfg(test)]
+mod snapshot;
+mod source;
+mod state;
 mod styles;
+mod table;
+#[cfg(test)]
+mod tempdir;
+mod terminal;
 mod views;
+mod watcher;
 mod widgets;
 
-use app::{restore_terminal, setup_terminal, App};
+use app::App;
+use cli::{Cli, Command, KeymapCommand, ThemesCommand};
+use config::{Config, Overrides};
+use event::{EventLoop, DEFAULT_FRAME_RATE};
+use keymap::Keymap;
+use logs::{LogTail, TailLogger};
+use ratatui::Terminal;
+use record::Recorder;
+use script::{HeadlessBackend, Script, SCREEN_SIZE};
+use state::AppState;
+use styles::{StyleManager, Theme, BUILTIN_THEMES};
+use terminal::TerminalGuard;
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    // Initialize logging
-    env_logger::Builder::from_default_env()
-        .filter_level(log::LevelFilter::Info)
-        .init();
+    let cli = Cli::parse();
+    let log_tail = init_logging(cli.log_file.as_deref())?;
 
     info!("Starting Ratatui TUI application");
 
-    // Setup terminal
-    let mut terminal = setup_terminal()?;
-    info!("Terminal setup complete");
+    let config_dir = cli.config.clone().or_else(paths::config_dir);
+    let themes_dir = config_dir.as_ref().map(|dir| dir.join(styles::THEMES_DIR));
+
+    // Load settings and key bindings before touching the terminal so errors print normally
+    let config = Config::load(config_dir.as_deref())?;
+    let keymap = Keymap::load(config_dir.as_deref())?;
+    let script = cli.script.as_deref().map(Script::load).transpose()?;
+
+    // RUST_LOG, when set, takes precedence over the configured level
+    if env::var_os("RUST_LOG").is_none() {
+        log::set_max_level(config.log_level.filter());
+    }
+
+    match cli.command {
+        Some(Command::Themes(ThemesCommand::List)) => {
+            list_themes(themes_dir.as_deref());
+            return Ok(());
+        }
+        Some(Command::Keymap(KeymapCommand::Dump)) => {
+            print!("{}", keymap.to_toml());
+            return Ok(());
+        }
+        None => {}
+    }
 
-    // Create and run application
-    let mut app = App::new();
+    // Only a session that runs the interface is recorded, still before the terminal is taken over
+    let recorder = match &cli.record {
+        Some(path) => Some(Recorder::create(path, crossterm::terminal::size()?)?),
+        None => None,
+    };
+
+    // Command-line options override the saved settings without being saved themselves
+    let mut state = AppState::with_config(config);
+    // A data file opens in the Data Browser unless another view was asked for
+    if let Some(view) = cli.view.or(cli.file.as_ref().map(|_| views::ViewId::Data)) {
+        state.current_view = view;
+    }
+    let overrides = Overrides {
+        theme: cli.theme.clone(),
+        mouse: cli.no_mouse.then_some(false),
+    };
+    state.settings.set_overrides(&mut state.config, overrides);
+    let mouse_capture = state.config.mouse;
+    state.style_manager =
+        StyleManager::new(Theme::resolve(&state.config.theme, themes_dir.as_deref())?);
+    state
+        .style_manager
+        .set_color_depth(state.config.color_mode.depth());
+    info!(
+        "Using {} colours",
+        state.style_manager.color_depth().as_str()
+    );
+    state.settings.themes = styles::theme_names(themes_dir.as_deref());
+    state.recent_commands = palette::load_recent(config_dir.as_deref());
+
+    let mut app = App::with_state(keymap, state);
+    app.mouse_capture = mouse_capture;
+    app.config_dir = config_dir;
+    app.data_file = cli.file.clone();
+    app.log_tail = Some(log_tail);
+    app.recorder = recorder.clone();
     info!("Application created, starting main loop");
 
-    // Run the application
-    let result = app.run(&mut terminal).await;
+    let result = match script {
+        Some(script) => run_script(&mut app, script, cli.tick_rate()).await,
+        None => run_terminal(&mut app, recorder, cli.tick_rate()).await,
+    };
+    let stdout_exports = app.take_stdout_exports();
 
-    // Restore terminal
-    restore_terminal(&mut terminal)?;
-    info!("Terminal restored");
+    // Rows exported to stdout are printed now the screen is back to normal
+    let mut stdout = io::stdout().lock();
+    for export in stdout_exports {
+        export
+            .write(&mut stdout)
+            .context("failed to write exported rows")?;
+    }
+    stdout.flush()?;
 
     // Handle result
     match result {
@@ -44,3 +158,78 @@ async fn main() -> Result<()> {
         }
     }
 }
+
+/// Run on the real terminal until the application quits, recording it if asked to
+async fn run_terminal(
+    app: &mut App,
+    recorder: Option<Recorder>,
+    tick_rate: Duration,
+) -> Result<()> {
+    // Setup terminal; the guard restores it when this function returns, and the
+    // panic hook it installs restores it if the application panics
+    let mut terminal = TerminalGuard::new(app.mouse_capture, recorder)?;
+    info!("Terminal setup complete");
+
+    let mut events = EventLoop::new(tick_rate, DEFAULT_FRAME_RATE);
+    let result = app.run(&mut terminal, &mut events).await;
+    drop(terminal);
+    info!("Terminal restored");
+    result
+}
+
+/// Play a script on a screen in memory; if it fails, the screen as it was then
+/// is printed to stderr
+async fn run_script(app: &mut App, script: Script, tick_rate: Duration) -> Result<()> {
+    let backend = HeadlessBackend::new(SCREEN_SIZE.0, SCREEN_SIZE.1);
+    let mut terminal = Terminal::new(backend.clone())?;
+    let mut events = EventLoop::scripted(script, backend.clone(), tick_rate, DEFAULT_FRAME_RATE);
+    app.headless = true;
+
+    let result = app.run(&mut terminal, &mut events).await;
+    if result.is_err() {
+        eprintln!("Final screen:\n{}", backend.screen());
+    }
+    result
+}
+
+/// Log to the given file, keeping the latest lines for the dashboard; without a
+/// file, only write to stderr when RUST_LOG asks for it, since anything written
+/// there lands on top of the interface
+fn init_logging(log_file: Option<&Path>) -> Result<LogTail> {
+    let mut builder = env_logger::Builder::new();
+    // Everything passes the filter; the configured level is applied as the max level
+    builder.filter_level(log::LevelFilter::Trace);
+
+    match log_file {
+        Some(path) => {
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .with_context(|| format!("failed to open log file {}", path.display()))?;
+            builder.target(env_logger::Target::Pipe(Box::new(file)));
+        }
+        None if env::var_os("RUST_LOG").is_some() => {}
+        None => {
+            builder.target(env_logger::Target::Pipe(Box::new(io::sink())));
+        }
+    }
+
+    let logger = builder.parse_default_env().build();
+    let tail = LogTail::default();
+    log::set_max_level(logger.filter());
+    log::set_boxed_logger(Box::new(TailLogger::new(logger, tail.clone())))?;
+    Ok(tail)
+}
+
+fn list_themes(themes_dir: Option<&Path>) {
+    for name in BUILTIN_THEMES {
+        println!("{:<16} built-in", name);
+    }
+
+    if let Some(dir) = themes_dir {
+        for (name, path) in styles::theme_files(dir) {
+            println!("{:<16} {}", name, path.display());
+        }
+    }
+}
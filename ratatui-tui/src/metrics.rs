@@ -0,0 +1,474 @@
+use std::{
+    collections::VecDeque,
+    fmt, fs, io,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+/// Samples kept for charts: two minutes at one a second
+pub const HISTORY_LEN: usize = 120;
+
+/// Least time between samples, however fast the app ticks
+pub const SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Bytes in a sector as counted by /proc/diskstats, whatever the device uses
+const SECTOR_SIZE: u64 = 512;
+
+/// Raw system counters at one moment; rates come from the difference between two
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Counters {
+    /// Jiffies spent on anything but idling and waiting for I/O, across all CPUs
+    pub cpu_busy: u64,
+    pub cpu_total: u64,
+    pub cpus: usize,
+    /// Kibibytes
+    pub memory_total: u64,
+    pub memory_available: u64,
+    /// Load average over 1, 5 and 15 minutes
+    pub load: [f64; 3],
+    /// Bytes, summed over whole disks
+    pub disk_read: u64,
+    pub disk_written: u64,
+    /// Bytes, summed over every interface but loopback
+    pub net_received: u64,
+    pub net_sent: u64,
+}
+
+/// Somewhere system counters are read from
+pub trait MetricsSource: fmt::Debug + Send {
+    /// Read the counters as they are now
+    fn read(&mut self) -> io::Result<Counters>;
+}
+
+/// Counters from the Linux /proc filesystem
+#[derive(Debug, Clone)]
+pub struct ProcSource {
+    root: PathBuf,
+}
+
+impl Default for ProcSource {
+    fn default() -> Self {
+        Self::new(PathBuf::from("/proc"))
+    }
+}
+
+impl ProcSource {
+    /// Read from a /proc tree mounted at `root`
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    fn read_file(&self, name: &str) -> io::Result<String> {
+        fs::read_to_string(self.root.join(name))
+    }
+}
+
+impl MetricsSource for ProcSource {
+    fn read(&mut self) -> io::Result<Counters> {
+        let invalid = |name: &str| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("cannot parse /proc/{}", name),
+            )
+        };
+
+        let (cpu_busy, cpu_total, cpus) =
+            parse_stat(&self.read_file("stat")?).ok_or_else(|| invalid("stat"))?;
+        let (memory_total, memory_available) =
+            parse_meminfo(&self.read_file("meminfo")?).ok_or_else(|| invalid("meminfo"))?;
+        let load = parse_loadavg(&self.read_file("loadavg")?).ok_or_else(|| invalid("loadavg"))?;
+        // Disk and network counters are missing in some containers; show them as idle
+        let (disk_read, disk_written) = self
+            .read_file("diskstats")
+            .map(|text| parse_diskstats(&text))
+            .unwrap_or_default();
+        let (net_received, net_sent) = self
+            .read_file("net/dev")
+            .map(|text| parse_net_dev(&text))
+            .unwrap_or_default();
+
+        Ok(Counters {
+            cpu_busy,
+            cpu_total,
+            cpus,
+            memory_total,
+            memory_available,
+            load,
+            disk_read,
+            disk_written,
+            net_received,
+            net_sent,
+        })
+    }
+}
+
+/// Counters that grow by the same step every read, so what is drawn does not
+/// depend on the machine
+#[cfg(test)]
+#[derive(Debug, Clone)]
+pub struct SteadySource {
+    counters: Counters,
+    step: Counters,
+}
+
+#[cfg(test)]
+impl SteadySource {
+    pub fn new(counters: Counters, step: Counters) -> Self {
+        Self { counters, step }
+    }
+}
+
+#[cfg(test)]
+impl MetricsSource for SteadySource {
+    fn read(&mut self) -> io::Result<Counters> {
+        let counters = self.counters;
+        let (current, step) = (&mut self.counters, &self.step);
+        current.cpu_busy += step.cpu_busy;
+        current.cpu_total += step.cpu_total;
+        current.disk_read += step.disk_read;
+        current.disk_written += step.disk_written;
+        current.net_received += step.net_received;
+        current.net_sent += step.net_sent;
+        Ok(counters)
+    }
+}
+
+/// Busy and total jiffies from the `cpu` line of /proc/stat, and the number of CPUs
+fn parse_stat(text: &str) -> Option<(u64, u64, usize)> {
+    let mut lines = text.lines();
+    let fields: Vec<u64> = lines
+        .next()?
+        .strip_prefix("cpu ")?
+        .split_whitespace()
+        .map(|field| field.parse().ok())
+        .collect::<Option<_>>()?;
+    // user nice system idle iowait irq softirq steal; guest time is already in user
+    let total: u64 = fields.iter().take(8).sum();
+    let idle = fields.get(3)? + fields.get(4).unwrap_or(&0);
+    let cpus = lines
+        .filter(|line| {
+            line.starts_with("cpu") && line.as_bytes().get(3).is_some_and(u8::is_ascii_digit)
+        })
+        .count();
+    Some((total - idle, total, cpus.max(1)))
+}
+
+/// Total and available memory in kibibytes from /proc/meminfo
+fn parse_meminfo(text: &str) -> Option<(u64, u64)> {
+    let field = |name: &str| {
+        text.lines()
+            .find_map(|line| line.strip_prefix(name)?.strip_prefix(':'))
+            .and_then(|rest| rest.split_whitespace().next()?.parse::<u64>().ok())
+    };
+    Some((field("MemTotal")?, field("MemAvailable")?))
+}
+
+fn parse_loadavg(text: &str) -> Option<[f64; 3]> {
+    let mut fields = text.split_whitespace().map(|field| field.parse().ok());
+    Some([fields.next()??, fields.next()??, fields.next()??])
+}
+
+/// Bytes read and written from /proc/diskstats, leaving out partitions and
+/// virtual devices so nothing is counted twice
+fn parse_diskstats(text: &str) -> (u64, u64) {
+    text.lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let name = *fields.get(2)?;
+            if !is_disk(name) {
+                return None;
+            }
+            let read: u64 = fields.get(5)?.parse().ok()?;
+            let written: u64 = fields.get(9)?.parse().ok()?;
+            Some((read * SECTOR_SIZE, written * SECTOR_SIZE))
+        })
+        .fold((0, 0), |(read, written), (r, w)| (read + r, written + w))
+}
+
+/// Whether a block device is a whole physical disk, e.g. `sda` or `nvme0n1`
+/// but not `sda1`, `nvme0n1p1`, `loop0` or `dm-0`
+fn is_disk(name: &str) -> bool {
+    if ["loop", "ram", "zram", "dm-", "md", "sr"]
+        .iter()
+        .any(|prefix| name.starts_with(prefix))
+    {
+        return false;
+    }
+    if name.starts_with("nvme") || name.starts_with("mmcblk") {
+        // Partitions end in p and a number
+        let trimmed = name.trim_end_matches(|c: char| c.is_ascii_digit());
+        return !trimmed.ends_with('p');
+    }
+    !name.ends_with(|c: char| c.is_ascii_digit())
+}
+
+/// Bytes received and sent from /proc/net/dev, leaving out loopback
+fn parse_net_dev(text: &str) -> (u64, u64) {
+    text.lines()
+        .filter_map(|line| {
+            let (interface, counters) = line.split_once(':')?;
+            if interface.trim() == "lo" {
+                return None;
+            }
+            let fields: Vec<u64> = counters
+                .split_whitespace()
+                .filter_map(|field| field.parse().ok())
+                .collect();
+            Some((*fields.first()?, *fields.get(8)?))
+        })
+        .fold((0, 0), |(received, sent), (r, s)| (received + r, sent + s))
+}
+
+/// What the system is doing, worked out from two readings of its counters
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Reading {
+    /// Percentages, from 0 to 100
+    pub cpu: f64,
+    pub memory: f64,
+    /// Kibibytes
+    pub memory_used: u64,
+    pub memory_total: u64,
+    pub load: [f64; 3],
+    pub cpus: usize,
+    /// Bytes a second
+    pub disk_read: f64,
+    pub disk_written: f64,
+    pub net_received: f64,
+    pub net_sent: f64,
+}
+
+impl Reading {
+    fn between(previous: &Counters, current: &Counters, elapsed: Duration) -> Self {
+        let seconds = elapsed.as_secs_f64().max(f64::EPSILON);
+        let rate = |before: u64, after: u64| after.saturating_sub(before) as f64 / seconds;
+        let busy = current.cpu_busy.saturating_sub(previous.cpu_busy) as f64;
+        let total = current.cpu_total.saturating_sub(previous.cpu_total) as f64;
+        let memory_used = current
+            .memory_total
+            .saturating_sub(current.memory_available);
+
+        Self {
+            cpu: if total > 0.0 {
+                (busy / total * 100.0).clamp(0.0, 100.0)
+            } else {
+                0.0
+            },
+            memory: percent(memory_used, current.memory_total),
+            memory_used,
+            memory_total: current.memory_total,
+            load: current.load,
+            cpus: current.cpus,
+            disk_read: rate(previous.disk_read, current.disk_read),
+            disk_written: rate(previous.disk_written, current.disk_written),
+            net_received: rate(previous.net_received, current.net_received),
+            net_sent: rate(previous.net_sent, current.net_sent),
+        }
+    }
+
+    /// A load average as a percentage of what the CPUs can take
+    pub fn load_percent(&self, minutes: usize) -> f64 {
+        self.load[minutes] / self.cpus.max(1) as f64 * 100.0
+    }
+}
+
+fn percent(part: u64, whole: u64) -> f64 {
+    if whole == 0 {
+        0.0
+    } else {
+        part as f64 / whole as f64 * 100.0
+    }
+}
+
+/// Recent readings, oldest first, for charts and sparklines
+#[derive(Debug, Clone, Default)]
+pub struct Metrics {
+    pub latest: Option<Reading>,
+    pub history: VecDeque<Reading>,
+    /// Readings taken since the start, so charts can scroll
+    pub samples: u64,
+    /// Why the counters could not be read
+    pub error: Option<String>,
+}
+
+impl Metrics {
+    fn push(&mut self, reading: Reading) {
+        if self.history.len() == HISTORY_LEN {
+            self.history.pop_front();
+        }
+        self.history.push_back(reading);
+        self.latest = Some(reading);
+        self.samples += 1;
+        self.error = None;
+    }
+
+    /// One value from each reading in the history
+    pub fn series(&self, value: impl Fn(&Reading) -> f64) -> Vec<f64> {
+        self.history.iter().map(value).collect()
+    }
+}
+
+/// Reads counters at most once every `SAMPLE_INTERVAL` and keeps the readings
+#[derive(Debug)]
+pub struct MetricsSampler {
+    source: Box<dyn MetricsSource>,
+    /// The last counters read, as the baseline for the next rates
+    last: Option<(Counters, Instant)>,
+    sampled_at: Option<Instant>,
+    metrics: Metrics,
+}
+
+impl Default for MetricsSampler {
+    fn default() -> Self {
+        Self::new(Box::new(ProcSource::default()))
+    }
+}
+
+impl MetricsSampler {
+    pub fn new(source: Box<dyn MetricsSource>) -> Self {
+        Self {
+            source,
+            last: None,
+            sampled_at: None,
+            metrics: Metrics::default(),
+        }
+    }
+
+    pub fn metrics(&self) -> &Metrics {
+        &self.metrics
+    }
+
+    /// Read the counters if a sample is due; whether the metrics changed
+    pub fn sample(&mut self, now: Instant) -> bool {
+        if self
+            .sampled_at
+            .is_some_and(|at| now.saturating_duration_since(at) < SAMPLE_INTERVAL)
+        {
+            return false;
+        }
+        self.sampled_at = Some(now);
+
+        match self.source.read() {
+            Ok(counters) => {
+                // The first reading only sets the baseline for rates
+                let Some((previous, at)) = self.last.replace((counters, now)) else {
+                    return false;
+                };
+                let reading =
+                    Reading::between(&previous, &counters, now.saturating_duration_since(at));
+                self.metrics.push(reading);
+                true
+            }
+            Err(err) => {
+                // Rates are not worked out across a gap in the readings
+                self.last = None;
+                let error = format!("System metrics unavailable: {}", err);
+                let changed = self.metrics.error.as_ref() != Some(&error);
+                self.metrics.error = Some(error);
+                changed
+            }
+        }
+    }
+}
+
+/// A byte count with a binary unit, e.g. `1.5 MiB`
+pub fn format_bytes(bytes: f64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes.max(0.0);
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{:.0} {}", value, UNITS[unit])
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STAT: &str = "cpu  100 0 50 800 50 0 0 0 0 0\ncpu0 50 0 25 400 25 0 0 0 0 0\ncpu1 50 0 25 400 25 0 0 0 0 0\nintr 1 2 3\n";
+    const DISKSTATS: &str = "   7       0 loop0 9 0 9 0 0 0 0 0 0 0 0
+ 259       0 nvme0n1 10 0 100 0 5 0 20 0 0 0 0
+ 259       1 nvme0n1p1 10 0 100 0 5 0 20 0 0 0 0
+   8       0 sda 1 0 4 0 1 0 2 0 0 0 0
+   8       1 sda1 1 0 4 0 1 0 2 0 0 0 0
+";
+    const NET_DEV: &str = "Inter-|   Receive |  Transmit
+ face |bytes    packets errs drop fifo frame compressed multicast|bytes    packets
+    lo: 1000 10 0 0 0 0 0 0 1000 10 0 0 0 0 0 0
+  eth0: 2048 4 0 0 0 0 0 0 512 2 0 0 0 0 0 0
+";
+
+    #[test]
+    fn test_proc_files_are_parsed() {
+        assert_eq!(parse_stat(STAT), Some((150, 1000, 2)));
+        assert_eq!(
+            parse_meminfo("MemTotal:  1000 kB\nMemFree:  100 kB\nMemAvailable:  250 kB\n"),
+            Some((1000, 250))
+        );
+        assert_eq!(
+            parse_loadavg("0.50 0.25 1.00 2/72 13324\n"),
+            Some([0.5, 0.25, 1.0])
+        );
+        assert_eq!(
+            parse_diskstats(DISKSTATS),
+            (104 * SECTOR_SIZE, 22 * SECTOR_SIZE)
+        );
+        assert_eq!(parse_net_dev(NET_DEV), (2048, 512));
+        assert_eq!(parse_stat("intr 1 2 3"), None);
+    }
+
+    #[test]
+    fn test_readings_are_rates_over_the_interval() {
+        let counters = Counters {
+            cpus: 4,
+            memory_total: 1000,
+            memory_available: 400,
+            load: [2.0, 1.0, 0.5],
+            ..Counters::default()
+        };
+        let step = Counters {
+            cpu_busy: 25,
+            cpu_total: 100,
+            net_received: 2048,
+            ..Counters::default()
+        };
+        let mut sampler = MetricsSampler::new(Box::new(SteadySource::new(counters, step)));
+        let start = Instant::now();
+
+        assert!(!sampler.sample(start));
+        assert!(!sampler.sample(start + Duration::from_millis(500)));
+        assert!(sampler.sample(start + Duration::from_secs(2)));
+
+        let reading = sampler.metrics().latest.unwrap();
+        assert_eq!(reading.cpu, 25.0);
+        assert_eq!(reading.memory, 60.0);
+        assert_eq!(reading.net_received, 1024.0);
+        assert_eq!(reading.load_percent(0), 50.0);
+
+        for second in 3..(HISTORY_LEN as u64 + 10) {
+            sampler.sample(start + Duration::from_secs(second));
+        }
+        assert_eq!(sampler.metrics().history.len(), HISTORY_LEN);
+        assert_eq!(sampler.metrics().samples, HISTORY_LEN as u64 + 8);
+    }
+
+    #[test]
+    fn test_unreadable_counters_are_reported() {
+        let mut sampler =
+            MetricsSampler::new(Box::new(ProcSource::new(PathBuf::from("/nonexistent"))));
+        assert!(sampler.sample(Instant::now()));
+        assert!(sampler
+            .metrics()
+            .error
+            .as_ref()
+            .unwrap()
+            .starts_with("System metrics unavailable"));
+        assert_eq!(format_bytes(1536.0), "1.5 KiB");
+        assert_eq!(format_bytes(12.0), "12 B");
+    }
+}
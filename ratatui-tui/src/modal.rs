@@ -0,0 +1,330 @@
+use crossterm::event::{KeyCode, KeyModifiers};
+
+use crate::keymap::KeyCombo;
+
+/// What the reducer does with a dialog's answer, so the code that opened the
+/// dialog carries on where it left off
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reply {
+    /// Nothing follows, e.g. an error was read
+    Acknowledge,
+    /// Quitting was held back by unsaved edits: save first, quit anyway or stay
+    Quit,
+    /// Opening another file would drop unsaved edits
+    OpenFile,
+    /// Move the Data Browser's cursor to the row numbered
+    GoToRow,
+}
+
+/// What a dialog asks for
+#[derive(Debug, Clone, PartialEq)]
+pub enum DialogKind {
+    /// A message to read, closed with Enter or Esc
+    Alert,
+    /// A question answered by a button that carries it out or one that cancels
+    Confirm {
+        /// Label of the button that carries it out, e.g. "Quit"
+        label: String,
+        /// Whether that button is selected rather than Cancel
+        confirm: bool,
+    },
+    /// A line of text to type
+    Prompt {
+        text: String,
+        /// Why the text last entered was not accepted
+        error: Option<String>,
+    },
+    /// One of a list of options
+    Choice {
+        options: Vec<String>,
+        selected: usize,
+    },
+}
+
+/// How a dialog was closed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Answer {
+    /// An alert was read, or a confirmation's button pressed
+    Confirmed,
+    /// The text entered in a prompt
+    Text(String),
+    /// Index of the option chosen
+    Chosen(usize),
+    /// Esc, or a confirmation's Cancel button
+    Dismissed,
+}
+
+/// What a key press in a dialog asks for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DialogOutcome {
+    Edited,
+    Unchanged,
+    Close(Answer),
+}
+
+/// A modal dialog, drawn over everything else and taking key presses until it closes
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dialog {
+    pub title: String,
+    pub message: String,
+    pub kind: DialogKind,
+    pub reply: Reply,
+}
+
+impl Dialog {
+    /// A message, e.g. an error, that only needs reading
+    pub fn alert(title: &str, message: String) -> Self {
+        Self::new(title, message, DialogKind::Alert, Reply::Acknowledge)
+    }
+
+    /// A question carried out by the button labelled `label`; Cancel is selected to start with
+    pub fn confirm(title: &str, message: String, label: &str, reply: Reply) -> Self {
+        let kind = DialogKind::Confirm {
+            label: label.to_string(),
+            confirm: false,
+        };
+        Self::new(title, message, kind, reply)
+    }
+
+    /// A line of text to type, starting from `text`
+    pub fn prompt(title: &str, message: String, text: &str, reply: Reply) -> Self {
+        let kind = DialogKind::Prompt {
+            text: text.to_string(),
+            error: None,
+        };
+        Self::new(title, message, kind, reply)
+    }
+
+    /// A pick from `options`, the first selected to start with
+    pub fn choice(title: &str, message: String, options: &[&str], reply: Reply) -> Self {
+        let kind = DialogKind::Choice {
+            options: options.iter().map(|option| option.to_string()).collect(),
+            selected: 0,
+        };
+        Self::new(title, message, kind, reply)
+    }
+
+    fn new(title: &str, message: String, kind: DialogKind, reply: Reply) -> Self {
+        Self {
+            title: title.to_string(),
+            message,
+            kind,
+            reply,
+        }
+    }
+
+    /// Say why a prompt's text was not accepted, e.g. before opening it again
+    pub fn with_error(mut self, message: String) -> Self {
+        if let DialogKind::Prompt { error, .. } = &mut self.kind {
+            *error = Some(message);
+        }
+        self
+    }
+
+    /// Edit, move between buttons or options, or close the dialog; `None` for
+    /// keys the dialog leaves to the keymap, like Ctrl+Z
+    pub fn input(&mut self, key: KeyCombo) -> Option<DialogOutcome> {
+        if key.modifiers.contains(KeyModifiers::CONTROL) {
+            return None;
+        }
+        if key.code == KeyCode::Esc {
+            return Some(DialogOutcome::Close(Answer::Dismissed));
+        }
+
+        let outcome = match &mut self.kind {
+            DialogKind::Alert => match key.code {
+                KeyCode::Enter | KeyCode::Char(' ') => DialogOutcome::Close(Answer::Confirmed),
+                _ => DialogOutcome::Unchanged,
+            },
+            DialogKind::Confirm { confirm, .. } => match key.code {
+                KeyCode::Enter | KeyCode::Char(' ') if *confirm => {
+                    DialogOutcome::Close(Answer::Confirmed)
+                }
+                KeyCode::Enter | KeyCode::Char(' ') => DialogOutcome::Close(Answer::Dismissed),
+                KeyCode::Char('y') => DialogOutcome::Close(Answer::Confirmed),
+                KeyCode::Char('n') => DialogOutcome::Close(Answer::Dismissed),
+                KeyCode::Left
+                | KeyCode::Right
+                | KeyCode::Tab
+                | KeyCode::BackTab
+                | KeyCode::Char('h')
+                | KeyCode::Char('l') => {
+                    *confirm = !*confirm;
+                    DialogOutcome::Edited
+                }
+                _ => DialogOutcome::Unchanged,
+            },
+            DialogKind::Prompt { text, error } => match key.code {
+                KeyCode::Enter => DialogOutcome::Close(Answer::Text(text.clone())),
+                KeyCode::Backspace if !text.is_empty() => {
+                    text.pop();
+                    *error = None;
+                    DialogOutcome::Edited
+                }
+                KeyCode::Char(c) => {
+                    text.push(c);
+                    *error = None;
+                    DialogOutcome::Edited
+                }
+                _ => DialogOutcome::Unchanged,
+            },
+            DialogKind::Choice { options, selected } => {
+                let last = options.len().saturating_sub(1);
+                let moved = match key.code {
+                    KeyCode::Enter | KeyCode::Char(' ') if !options.is_empty() => {
+                        return Some(DialogOutcome::Close(Answer::Chosen(*selected)))
+                    }
+                    // Options are numbered from 1 on screen
+                    KeyCode::Char(c @ '1'..='9') => {
+                        let index = c as usize - '1' as usize;
+                        if index > last || options.is_empty() {
+                            return Some(DialogOutcome::Unchanged);
+                        }
+                        return Some(DialogOutcome::Close(Answer::Chosen(index)));
+                    }
+                    KeyCode::Up | KeyCode::BackTab | KeyCode::Char('k') => {
+                        selected.saturating_sub(1)
+                    }
+                    KeyCode::Down | KeyCode::Tab | KeyCode::Char('j') => (*selected + 1).min(last),
+                    _ => return Some(DialogOutcome::Unchanged),
+                };
+                if moved == *selected {
+                    return Some(DialogOutcome::Unchanged);
+                }
+                *selected = moved;
+                DialogOutcome::Edited
+            }
+        };
+
+        Some(outcome)
+    }
+
+    /// Keys that answer the dialog, for the line along its bottom edge
+    pub fn hint(&self) -> &'static str {
+        match self.kind {
+            DialogKind::Alert => " Enter/Esc close ",
+            DialogKind::Confirm { .. } => " ←/→ choose · Enter answer · Esc cancel ",
+            DialogKind::Prompt { .. } => " Enter accept · Esc cancel ",
+            DialogKind::Choice { .. } => " ↑/↓ move · Enter choose · Esc cancel ",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode) -> KeyCombo {
+        KeyCombo::new(code, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn test_alerts_and_confirmations_close_with_an_answer() {
+        let mut alert = Dialog::alert("Cannot save", "disk full".to_string());
+        assert_eq!(
+            alert.input(key(KeyCode::Char('q'))),
+            Some(DialogOutcome::Unchanged)
+        );
+        assert_eq!(
+            alert.input(key(KeyCode::Enter)),
+            Some(DialogOutcome::Close(Answer::Confirmed))
+        );
+
+        // Cancel is selected to start with, so Enter alone carries nothing out
+        let mut confirm = Dialog::confirm(
+            "Open file",
+            "Drop edits?".to_string(),
+            "Open",
+            Reply::OpenFile,
+        );
+        assert_eq!(
+            confirm.input(key(KeyCode::Enter)),
+            Some(DialogOutcome::Close(Answer::Dismissed))
+        );
+        assert_eq!(
+            confirm.input(key(KeyCode::Left)),
+            Some(DialogOutcome::Edited)
+        );
+        assert_eq!(
+            confirm.input(key(KeyCode::Enter)),
+            Some(DialogOutcome::Close(Answer::Confirmed))
+        );
+        assert_eq!(
+            confirm.input(key(KeyCode::Char('y'))),
+            Some(DialogOutcome::Close(Answer::Confirmed))
+        );
+
+        // Esc dismisses any dialog, and Ctrl keys are left to the keymap
+        for mut dialog in [alert, confirm] {
+            assert_eq!(
+                dialog.input(KeyCombo::new(KeyCode::Char('z'), KeyModifiers::CONTROL)),
+                None
+            );
+            assert_eq!(
+                dialog.input(key(KeyCode::Esc)),
+                Some(DialogOutcome::Close(Answer::Dismissed))
+            );
+        }
+    }
+
+    #[test]
+    fn test_prompts_return_the_text_typed() {
+        let mut prompt = Dialog::prompt("Go to row", "Row number".to_string(), "1", Reply::GoToRow)
+            .with_error("no row 0".to_string());
+        assert_eq!(
+            prompt.input(key(KeyCode::Char('2'))),
+            Some(DialogOutcome::Edited)
+        );
+        assert_eq!(
+            prompt.kind,
+            DialogKind::Prompt {
+                text: "12".to_string(),
+                error: None
+            }
+        );
+        prompt.input(key(KeyCode::Backspace));
+        prompt.input(key(KeyCode::Backspace));
+        assert_eq!(
+            prompt.input(key(KeyCode::Backspace)),
+            Some(DialogOutcome::Unchanged)
+        );
+        prompt.input(key(KeyCode::Char('7')));
+        assert_eq!(
+            prompt.input(key(KeyCode::Enter)),
+            Some(DialogOutcome::Close(Answer::Text("7".to_string())))
+        );
+    }
+
+    #[test]
+    fn test_choices_move_and_pick_by_number() {
+        let mut choice = Dialog::choice(
+            "Quit",
+            "Unsaved".to_string(),
+            &["Save", "Discard", "Cancel"],
+            Reply::Quit,
+        );
+        assert_eq!(
+            choice.input(key(KeyCode::Up)),
+            Some(DialogOutcome::Unchanged)
+        );
+        choice.input(key(KeyCode::Down));
+        choice.input(key(KeyCode::Down));
+        assert_eq!(
+            choice.input(key(KeyCode::Down)),
+            Some(DialogOutcome::Unchanged)
+        );
+        assert_eq!(
+            choice.input(key(KeyCode::Enter)),
+            Some(DialogOutcome::Close(Answer::Chosen(2)))
+        );
+
+        assert_eq!(
+            choice.input(key(KeyCode::Char('2'))),
+            Some(DialogOutcome::Close(Answer::Chosen(1)))
+        );
+        assert_eq!(
+            choice.input(key(KeyCode::Char('4'))),
+            Some(DialogOutcome::Unchanged)
+        );
+    }
+}
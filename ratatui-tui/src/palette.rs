@@ -0,0 +1,392 @@
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crossterm::event::{KeyCode, KeyModifiers};
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    action::Action,
+    dashboard::Side,
+    keymap::{KeyCombo, Keymap},
+    settings::SettingField,
+    views::ViewId,
+};
+
+/// File in the config directory the recently run commands are kept in
+const HISTORY_FILE: &str = "history.toml";
+
+/// How many recently run commands are remembered
+const RECENT_LIMIT: usize = 20;
+
+/// Every action the palette offers, in the order listed when nothing is typed
+/// and nothing has been run yet
+pub fn commands() -> Vec<Action> {
+    let mut commands: Vec<Action> = ViewId::ALL.into_iter().map(Action::Navigate).collect();
+    commands.extend([
+        Action::ToggleTheme,
+        Action::OpenFile,
+        Action::Filter,
+        Action::Export,
+        Action::SaveData,
+        Action::Search,
+        Action::NextMatch,
+        Action::PrevMatch,
+        Action::ScrollTop,
+        Action::ScrollBottom,
+        Action::PageUp,
+        Action::PageDown,
+        Action::JumpToRow,
+        Action::ToggleSort,
+        Action::HideColumn,
+        Action::ShowAllColumns,
+        Action::ToggleMark,
+        Action::ToggleMarkAll,
+        Action::ToggleDetail,
+        Action::EditCell,
+        Action::UndoEdit,
+        Action::ToggleChanges,
+        Action::NextPanel,
+        Action::PrevPanel,
+    ]);
+    commands.extend(Side::ALL.map(Action::MovePanel));
+    commands.extend(Side::ALL.map(Action::ResizePanel));
+    commands.extend([Action::MaximisePanel, Action::SaveLayout]);
+    commands.extend(SettingField::ALL.map(Action::ChangeSetting));
+    commands.extend([
+        Action::SaveConfig,
+        Action::RevertConfig,
+        Action::FocusNext,
+        Action::FocusPrev,
+        Action::Suspend,
+        Action::Quit,
+    ]);
+    commands
+}
+
+/// Where `query`'s characters appear, in order, in `text`, ignoring case, and how
+/// well they fit: runs of consecutive characters and characters starting a word
+/// score higher, gaps lower. `None` if some character does not appear.
+pub fn fuzzy_match(query: &str, text: &str) -> Option<(i64, Vec<usize>)> {
+    let text: Vec<char> = text.chars().collect();
+    let mut positions = Vec::new();
+    let mut score = 0;
+    let mut from = 0;
+
+    for wanted in query.chars().filter(|c| !c.is_whitespace()) {
+        let wanted = wanted.to_lowercase().collect::<String>();
+        let index = (from..text.len())
+            .find(|&index| text[index].to_lowercase().collect::<String>() == wanted)?;
+
+        let word_start = index == 0 || !text[index - 1].is_alphanumeric();
+        score += match positions.last() {
+            Some(&last) if index == last + 1 => 8,
+            _ if word_start => 6,
+            _ => 1,
+        };
+        score -= (index - from) as i64;
+        positions.push(index);
+        from = index + 1;
+    }
+
+    Some((score, positions))
+}
+
+/// A command listed in the palette, with the characters the query matched
+#[derive(Debug, Clone, PartialEq)]
+pub struct PaletteMatch {
+    pub action: Action,
+    pub positions: Vec<usize>,
+}
+
+/// What a key press in the palette asks for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaletteOutcome {
+    /// The query or the selection changed
+    Edited,
+    Unchanged,
+    /// Close the palette and carry out the command
+    Run(Action),
+    Cancel,
+}
+
+/// Popup listing every command, narrowed by a fuzzy query
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommandPalette {
+    pub query: String,
+    pub selected: usize,
+    /// Commands matching the query, best first
+    pub matches: Vec<PaletteMatch>,
+}
+
+impl CommandPalette {
+    /// A palette listing every command, the most recently run first
+    pub fn new(recent: &[Action]) -> Self {
+        let mut palette = Self {
+            query: String::new(),
+            selected: 0,
+            matches: Vec::new(),
+        };
+        palette.refilter(recent);
+        palette
+    }
+
+    /// Edit the query, move the selection or run the selected command; `None`
+    /// for keys the palette leaves to the keymap
+    pub fn input(&mut self, key: KeyCombo, recent: &[Action]) -> Option<PaletteOutcome> {
+        if key.modifiers.contains(KeyModifiers::CONTROL) {
+            return None;
+        }
+        let last = self.matches.len().saturating_sub(1);
+
+        let selected = match key.code {
+            KeyCode::Up | KeyCode::BackTab => self.selected.saturating_sub(1),
+            KeyCode::Down | KeyCode::Tab => (self.selected + 1).min(last),
+            KeyCode::PageUp => self.selected.saturating_sub(10),
+            KeyCode::PageDown => (self.selected + 10).min(last),
+            KeyCode::Enter => {
+                return Some(match self.matches.get(self.selected) {
+                    Some(command) => PaletteOutcome::Run(command.action),
+                    None => PaletteOutcome::Unchanged,
+                })
+            }
+            KeyCode::Esc => return Some(PaletteOutcome::Cancel),
+            KeyCode::Backspace => {
+                if self.query.pop().is_none() {
+                    return Some(PaletteOutcome::Unchanged);
+                }
+                self.refilter(recent);
+                return Some(PaletteOutcome::Edited);
+            }
+            KeyCode::Char(c) => {
+                self.query.push(c);
+                self.refilter(recent);
+                return Some(PaletteOutcome::Edited);
+            }
+            _ => return Some(PaletteOutcome::Unchanged),
+        };
+
+        if selected == self.selected {
+            return Some(PaletteOutcome::Unchanged);
+        }
+        self.selected = selected;
+        Some(PaletteOutcome::Edited)
+    }
+
+    /// List the commands matching the query, best match first; among equally
+    /// good ones the most recently run come first
+    fn refilter(&mut self, recent: &[Action]) {
+        let recency = |action: &Action| {
+            recent
+                .iter()
+                .position(|used| used == action)
+                .unwrap_or(usize::MAX)
+        };
+        let mut matches: Vec<(i64, usize, PaletteMatch)> = commands()
+            .into_iter()
+            .filter_map(|action| {
+                let (score, positions) = fuzzy_match(&self.query, &action.description())?;
+                Some((score, recency(&action), PaletteMatch { action, positions }))
+            })
+            .collect();
+        // Stable, so commands otherwise level keep their listed order
+        matches.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+
+        self.matches = matches.into_iter().map(|(_, _, command)| command).collect();
+        self.selected = 0;
+    }
+}
+
+/// Move a command to the front of the recently run list
+pub fn remember(recent: &mut Vec<Action>, action: Action) {
+    recent.retain(|used| *used != action);
+    recent.insert(0, action);
+    recent.truncate(RECENT_LIMIT);
+}
+
+/// What is kept between sessions in the history file
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct History {
+    /// Commands run from the palette, most recent first, by their keymap names
+    #[serde(default)]
+    palette: Vec<String>,
+}
+
+/// The commands last run from the palette, most recent first; a missing or
+/// unreadable history starts empty, and commands no longer known are dropped
+pub fn load_recent(config_dir: Option<&Path>) -> Vec<Action> {
+    let Some(path) = config_dir.map(|dir| dir.join(HISTORY_FILE)) else {
+        return Vec::new();
+    };
+    let source = match fs::read_to_string(&path) {
+        Ok(source) => source,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Vec::new(),
+        Err(err) => {
+            warn!("Cannot read {}: {}", path.display(), err);
+            return Vec::new();
+        }
+    };
+
+    match toml::from_str::<History>(&source) {
+        Ok(history) => history
+            .palette
+            .iter()
+            .filter_map(|name| name.parse().ok())
+            .take(RECENT_LIMIT)
+            .collect(),
+        Err(err) => {
+            warn!("Ignoring invalid {}: {}", path.display(), err);
+            Vec::new()
+        }
+    }
+}
+
+/// Write the recently run commands into `config_dir`, creating it if needed
+pub fn save_recent(config_dir: &Path, recent: &[Action]) -> io::Result<PathBuf> {
+    let history = History {
+        palette: recent.iter().map(Action::to_string).collect(),
+    };
+    let source =
+        toml::to_string(&history).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+    let path = config_dir.join(HISTORY_FILE);
+    fs::create_dir_all(config_dir)?;
+    fs::write(&path, source)?;
+    Ok(path)
+}
+
+/// The keys bound to each command: where the command works on one view, as
+/// bound in that view, otherwise as bound in `view`
+pub fn bindings(keymap: &Keymap, view: ViewId) -> BTreeMap<Action, String> {
+    let mut bindings = BTreeMap::new();
+    for context in std::iter::once(view).chain(ViewId::ALL) {
+        for hint in keymap.hints(context) {
+            if hint.action.view().unwrap_or(view) == context {
+                bindings
+                    .entry(hint.action)
+                    .or_insert_with(|| hint.keys_label());
+            }
+        }
+    }
+    bindings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tempdir::TempDir;
+
+    fn key(code: KeyCode) -> KeyCombo {
+        KeyCombo::new(code, KeyModifiers::NONE)
+    }
+
+    fn titles(palette: &CommandPalette) -> Vec<String> {
+        palette
+            .matches
+            .iter()
+            .map(|command| command.action.description())
+            .collect()
+    }
+
+    #[test]
+    fn test_fuzzy_matching_prefers_runs_and_word_starts() {
+        assert_eq!(
+            fuzzy_match("tt", "Toggle theme").map(|(_, positions)| positions),
+            Some(vec![0, 7])
+        );
+        assert_eq!(fuzzy_match("", "Quit"), Some((0, Vec::new())));
+        assert_eq!(fuzzy_match("xyz", "Toggle theme"), None);
+        assert_eq!(fuzzy_match("ba", "Toggle theme"), None);
+
+        let score = |query, text| fuzzy_match(query, text).unwrap().0;
+        assert!(score("exp", "Export rows") > score("exp", "Open data file (e.x.p)"));
+        assert!(score("sr", "Show all columns rows") < score("sr", "Scroll row"));
+    }
+
+    #[test]
+    fn test_typing_narrows_and_runs_the_best_match() {
+        let mut palette = CommandPalette::new(&[]);
+        assert_eq!(palette.matches.len(), commands().len());
+        assert_eq!(titles(&palette)[0], "Dashboard");
+
+        for c in "theme".chars() {
+            assert_eq!(
+                palette.input(key(KeyCode::Char(c)), &[]),
+                Some(PaletteOutcome::Edited)
+            );
+        }
+        assert_eq!(titles(&palette)[0], "Toggle theme");
+        assert!(titles(&palette).contains(&"Change setting: Theme".to_string()));
+        assert_eq!(
+            palette.input(key(KeyCode::Enter), &[]),
+            Some(PaletteOutcome::Run(Action::ToggleTheme))
+        );
+
+        palette.input(key(KeyCode::Down), &[]);
+        assert_eq!(palette.selected, 1);
+        for _ in 0.."theme".len() {
+            palette.input(key(KeyCode::Backspace), &[]);
+        }
+        assert_eq!((palette.query.as_str(), palette.selected), ("", 0));
+        assert_eq!(
+            palette.input(key(KeyCode::Backspace), &[]),
+            Some(PaletteOutcome::Unchanged)
+        );
+        assert_eq!(
+            palette.input(key(KeyCode::Esc), &[]),
+            Some(PaletteOutcome::Cancel)
+        );
+        assert_eq!(
+            palette.input(
+                KeyCombo::new(KeyCode::Char('c'), KeyModifiers::CONTROL),
+                &[]
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_recently_run_commands_come_first() {
+        let mut recent = Vec::new();
+        remember(&mut recent, Action::Export);
+        remember(&mut recent, Action::Quit);
+        remember(&mut recent, Action::Export);
+        assert_eq!(recent, vec![Action::Export, Action::Quit]);
+
+        let palette = CommandPalette::new(&recent);
+        assert_eq!(titles(&palette)[..3], ["Export rows", "Quit", "Dashboard"]);
+    }
+
+    #[test]
+    fn test_recent_commands_are_kept_between_sessions() {
+        let dir = TempDir::new();
+        assert!(load_recent(Some(&dir)).is_empty());
+
+        let recent = vec![
+            Action::ChangeSetting(SettingField::Mouse),
+            Action::Navigate(ViewId::Data),
+        ];
+        let path = save_recent(&dir, &recent).unwrap();
+        assert_eq!(load_recent(Some(&dir)), recent);
+
+        fs::write(&path, "palette = [\"explode\", \"toggle_theme\"]\n").unwrap();
+        assert_eq!(load_recent(Some(&dir)), vec![Action::ToggleTheme]);
+        fs::write(&path, "palette = 3\n").unwrap();
+        assert!(load_recent(Some(&dir)).is_empty());
+    }
+
+    #[test]
+    fn test_bindings_come_from_the_view_a_command_works_on() {
+        let bindings = bindings(&Keymap::default(), ViewId::Help);
+
+        assert_eq!(bindings[&Action::ToggleTheme], "t");
+        assert_eq!(bindings[&Action::Navigate(ViewId::Data)], "2");
+        // Bound in the Data Browser, though the palette was opened from Help
+        assert_eq!(bindings[&Action::Export], "e");
+        assert_eq!(bindings[&Action::MaximisePanel], "m/Enter");
+        assert_eq!(bindings[&Action::Search], "/");
+        assert!(!bindings.contains_key(&Action::ChangeSetting(SettingField::Mouse)));
+    }
+}
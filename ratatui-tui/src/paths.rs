@@ -0,0 +1,17 @@
+use std::{env, path::PathBuf};
+
+const APP_DIR: &str = "ratatui-tui";
+
+/// Directory holding user configuration, following the XDG base directory spec
+pub fn config_dir() -> Option<PathBuf> {
+    xdg_dir("XDG_CONFIG_HOME", ".config")
+}
+
+fn xdg_dir(var: &str, home_fallback: &str) -> Option<PathBuf> {
+    let base = env::var_os(var)
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(home_fallback)))?;
+
+    Some(base.join(APP_DIR))
+}
@@ -0,0 +1,204 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crossterm::event::{KeyCode, KeyModifiers};
+
+use crate::{keymap::KeyCombo, source};
+
+/// A directory or data file listed by the picker
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileEntry {
+    pub name: String,
+    pub path: PathBuf,
+    pub is_dir: bool,
+}
+
+/// What a key press in the picker asks for
+#[derive(Debug, Clone, PartialEq)]
+pub enum PickerOutcome {
+    /// The highlighted entry changed, or nothing did
+    Moved,
+    /// List the contents of another directory
+    Enter(PathBuf),
+    /// Load this file
+    Choose(PathBuf),
+    Cancel,
+}
+
+/// Browser for the directories and data files `source::open` can read
+#[derive(Debug, Clone, PartialEq)]
+pub struct FilePicker {
+    pub dir: PathBuf,
+    pub entries: Vec<FileEntry>,
+    pub selected: usize,
+    /// Why the directory could not be listed
+    pub error: Option<String>,
+}
+
+impl FilePicker {
+    /// A picker for `dir`, empty until its entries are listed
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            entries: Vec::new(),
+            selected: 0,
+            error: None,
+        }
+    }
+
+    /// Show the listing of a directory, as resolved by `list_dir`
+    pub fn set_listing(&mut self, listing: io::Result<(PathBuf, Vec<FileEntry>)>) {
+        self.selected = 0;
+        match listing {
+            Ok((dir, entries)) => {
+                self.dir = dir;
+                self.entries = entries;
+                self.error = None;
+            }
+            Err(err) => {
+                self.entries.clear();
+                self.error = Some(err.to_string());
+            }
+        }
+    }
+
+    /// Move through the entries, open a directory or choose a file; `None` for
+    /// keys the picker leaves to the keymap
+    pub fn input(&mut self, key: KeyCombo) -> Option<PickerOutcome> {
+        if key.modifiers.contains(KeyModifiers::CONTROL) {
+            return None;
+        }
+        let last = self.entries.len().saturating_sub(1);
+
+        let outcome = match key.code {
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.selected = self.selected.saturating_sub(1);
+                PickerOutcome::Moved
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.selected = (self.selected + 1).min(last);
+                PickerOutcome::Moved
+            }
+            KeyCode::Home => {
+                self.selected = 0;
+                PickerOutcome::Moved
+            }
+            KeyCode::End => {
+                self.selected = last;
+                PickerOutcome::Moved
+            }
+            KeyCode::Enter | KeyCode::Right | KeyCode::Char('l') => {
+                match self.entries.get(self.selected) {
+                    Some(entry) if entry.is_dir => PickerOutcome::Enter(entry.path.clone()),
+                    Some(entry) => PickerOutcome::Choose(entry.path.clone()),
+                    None => PickerOutcome::Moved,
+                }
+            }
+            KeyCode::Backspace | KeyCode::Left | KeyCode::Char('h') => match self.dir.parent() {
+                Some(parent) => PickerOutcome::Enter(parent.to_path_buf()),
+                None => PickerOutcome::Moved,
+            },
+            KeyCode::Esc | KeyCode::Char('q') => PickerOutcome::Cancel,
+            _ => PickerOutcome::Moved,
+        };
+
+        Some(outcome)
+    }
+}
+
+/// The absolute path of `dir` with its subdirectories and readable data files:
+/// the parent first, then directories, then files, each by name. Hidden entries are skipped.
+pub fn list_dir(dir: &Path) -> io::Result<(PathBuf, Vec<FileEntry>)> {
+    let dir = dir.canonicalize()?;
+    let mut entries = Vec::new();
+
+    for entry in fs::read_dir(&dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let path = entry.path();
+        // Follows symlinks, so a link to a directory can be entered
+        let is_dir = path.is_dir();
+        if name.starts_with('.') || !(is_dir || source::is_supported(&path)) {
+            continue;
+        }
+        entries.push(FileEntry { name, path, is_dir });
+    }
+    entries.sort_by(|a, b| b.is_dir.cmp(&a.is_dir).then_with(|| a.name.cmp(&b.name)));
+
+    if let Some(parent) = dir.parent() {
+        entries.insert(
+            0,
+            FileEntry {
+                name: "..".to_string(),
+                path: parent.to_path_buf(),
+                is_dir: true,
+            },
+        );
+    }
+
+    Ok((dir, entries))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tempdir::TempDir;
+
+    fn key(code: KeyCode) -> KeyCombo {
+        KeyCombo::new(code, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn test_lists_directories_then_data_files() {
+        let dir = TempDir::new();
+        fs::create_dir_all(dir.join("nested")).unwrap();
+        for name in ["b.csv", "a.ndjson", "notes.txt", ".hidden.csv"] {
+            fs::write(dir.join(name), "").unwrap();
+        }
+
+        let (resolved, entries) = list_dir(&dir).unwrap();
+        let names: Vec<&str> = entries.iter().map(|entry| entry.name.as_str()).collect();
+        assert_eq!(names, vec!["..", "nested", "a.ndjson", "b.csv"]);
+        assert_eq!(resolved, dir.canonicalize().unwrap());
+    }
+
+    #[test]
+    fn test_keys_open_directories_and_choose_files() {
+        let mut picker = FilePicker::new(PathBuf::from("/data"));
+        let entry = |name: &str, is_dir| FileEntry {
+            name: name.to_string(),
+            path: PathBuf::from("/data").join(name),
+            is_dir,
+        };
+        picker.set_listing(Ok((
+            PathBuf::from("/data"),
+            vec![entry("logs", true), entry("sales.csv", false)],
+        )));
+
+        assert_eq!(
+            picker.input(key(KeyCode::Enter)),
+            Some(PickerOutcome::Enter(PathBuf::from("/data/logs")))
+        );
+        picker.input(key(KeyCode::Down));
+        picker.input(key(KeyCode::Down));
+        assert_eq!(picker.selected, 1);
+        assert_eq!(
+            picker.input(key(KeyCode::Enter)),
+            Some(PickerOutcome::Choose(PathBuf::from("/data/sales.csv")))
+        );
+        assert_eq!(
+            picker.input(key(KeyCode::Backspace)),
+            Some(PickerOutcome::Enter(PathBuf::from("/")))
+        );
+        assert_eq!(
+            picker.input(key(KeyCode::Char('q'))),
+            Some(PickerOutcome::Cancel)
+        );
+        assert_eq!(
+            picker.input(KeyCombo::new(KeyCode::Char('c'), KeyModifiers::CONTROL)),
+            None
+        );
+    }
+}
@@ -0,0 +1,359 @@
+//! Recording a session: what is drawn goes to an asciicast v2 file, for
+//! `asciinema play`, and the input to a script that replays it with `--script`.
+
+use std::{
+    fmt,
+    fs::File,
+    io::{self, LineWriter, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+use crossterm::event::{KeyEventKind, MouseEventKind};
+use log::warn;
+use serde_json::json;
+use thiserror::Error;
+
+use crate::{event::Event, keymap::KeyCombo, script::Step};
+
+/// Extension of the input log written next to a recording
+pub const INPUT_EXTENSION: &str = "script";
+
+/// Pauses shorter than this are not written to the input log
+const MIN_WAIT: Duration = Duration::from_millis(1);
+
+#[derive(Debug, Error)]
+pub enum RecordError {
+    #[error("failed to create recording {path}: {source}")]
+    Create { path: String, source: io::Error },
+    #[error(
+        "cannot record to {0}: its input log would overwrite it; use another extension, e.g. .cast"
+    )]
+    InputClash(String),
+    #[error("failed to write recording: {0}")]
+    Write(#[from] io::Error),
+}
+
+/// Where the input log for the recording at `cast` goes: `demo.cast` → `demo.script`
+pub fn input_path(cast: &Path) -> PathBuf {
+    cast.with_extension(INPUT_EXTENSION)
+}
+
+/// Writes a session's output and input as it happens; clones share the files
+#[derive(Clone)]
+pub struct Recorder {
+    inner: Arc<Mutex<Recording>>,
+}
+
+struct Recording {
+    cast: Box<dyn Write + Send>,
+    input: Box<dyn Write + Send>,
+    start: Instant,
+    last_input: Instant,
+    /// Output written since the last flush, sent as one event
+    pending: Vec<u8>,
+    /// Set after a write fails, so the failure is only reported once
+    failed: bool,
+}
+
+impl fmt::Debug for Recorder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Recorder").finish_non_exhaustive()
+    }
+}
+
+impl Recorder {
+    /// Create `cast` and the input log next to it, for a terminal of the given size
+    pub fn create(cast: &Path, size: (u16, u16)) -> Result<Self, RecordError> {
+        if input_path(cast) == cast {
+            return Err(RecordError::InputClash(cast.display().to_string()));
+        }
+        let create = |path: &Path| {
+            File::create(path).map_err(|source| RecordError::Create {
+                path: path.display().to_string(),
+                source,
+            })
+        };
+        let cast_file = create(cast)?;
+        let input_file = create(&input_path(cast))?;
+        // Lines reach the files as they are written, so a crash keeps what led up to it
+        Self::new(
+            LineWriter::new(cast_file),
+            LineWriter::new(input_file),
+            size,
+        )
+    }
+
+    fn new(
+        mut cast: impl Write + Send + 'static,
+        mut input: impl Write + Send + 'static,
+        (width, height): (u16, u16),
+    ) -> Result<Self, RecordError> {
+        let mut header = json!({
+            "version": 2,
+            "width": width,
+            "height": height,
+            "timestamp": chrono::Utc::now().timestamp(),
+        });
+        if let Ok(term) = std::env::var("TERM") {
+            header["env"] = json!({ "TERM": term });
+        }
+        writeln!(cast, "{}", header)?;
+
+        writeln!(
+            input,
+            "# Recorded by {} {} on {}",
+            env!("CARGO_PKG_NAME"),
+            env!("CARGO_PKG_VERSION"),
+            chrono::Local::now().format("%Y-%m-%d %H:%M:%S")
+        )?;
+        writeln!(input, "{}", Step::Resize { width, height })?;
+
+        let now = Instant::now();
+        Ok(Self {
+            inner: Arc::new(Mutex::new(Recording {
+                cast: Box::new(cast),
+                input: Box::new(input),
+                start: now,
+                last_input: now,
+                pending: Vec::new(),
+                failed: false,
+            })),
+        })
+    }
+
+    /// Note an event from the event loop; key presses, mouse buttons and
+    /// resizes go to the input log, resizes to the recording too
+    pub fn input(&self, event: &Event) {
+        let step = match event {
+            Event::Key(key) if key.kind == KeyEventKind::Press => {
+                let combo = KeyCombo::from(*key);
+                // Keys a script cannot name are left out rather than replayed as something else
+                if combo.to_string().parse::<KeyCombo>().ok() != Some(combo) {
+                    warn!("Not recording key {:?}", key);
+                    return;
+                }
+                Step::Press(vec![combo])
+            }
+            Event::Mouse(mouse) => match mouse.kind {
+                MouseEventKind::Down(_) | MouseEventKind::Up(_) | MouseEventKind::Drag(_) => {
+                    Step::Mouse {
+                        kind: mouse.kind,
+                        column: mouse.column,
+                        row: mouse.row,
+                    }
+                }
+                MouseEventKind::ScrollUp | MouseEventKind::ScrollDown => Step::Scroll {
+                    down: mouse.kind == MouseEventKind::ScrollDown,
+                    column: mouse.column,
+                    row: mouse.row,
+                },
+                _ => return,
+            },
+            &Event::Resize(width, height) => Step::Resize { width, height },
+            _ => return,
+        };
+
+        let mut recording = self.lock();
+        let result = recording.write_input(&step);
+        recording.check(result);
+        if let Step::Resize { width, height } = step {
+            let result = recording.write_event("r", &format!("{}x{}", width, height));
+            recording.check(result);
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Recording> {
+        self.inner
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl Recording {
+    fn write_input(&mut self, step: &Step) -> io::Result<()> {
+        let now = Instant::now();
+        let pause = now - self.last_input;
+        self.last_input = now;
+        if pause >= MIN_WAIT {
+            writeln!(self.input, "{}", Step::Wait(pause))?;
+        }
+        writeln!(self.input, "{}", step)
+    }
+
+    /// Write an asciicast event of the given type, timed from the start
+    fn write_event(&mut self, kind: &str, data: &str) -> io::Result<()> {
+        let time = self.start.elapsed().as_micros() as f64 / 1_000_000.0;
+        writeln!(self.cast, "{}", json!([time, kind, data]))
+    }
+
+    /// Send what was drawn since the last flush as one output event
+    fn flush_output(&mut self) -> io::Result<()> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        let output = std::mem::take(&mut self.pending);
+        self.write_event("o", &String::from_utf8_lossy(&output))
+    }
+
+    fn check(&mut self, result: io::Result<()>) {
+        if let Err(err) = result {
+            if !std::mem::replace(&mut self.failed, true) {
+                warn!("Failed to write recording: {}", err);
+            }
+        }
+    }
+}
+
+/// Passes output on to the terminal, keeping a copy in the recording
+pub struct Recorded<W> {
+    inner: W,
+    recorder: Recorder,
+}
+
+impl<W> Recorded<W> {
+    pub fn new(inner: W, recorder: Recorder) -> Self {
+        Self { inner, recorder }
+    }
+}
+
+impl<W: Write> Write for Recorded<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.recorder
+            .lock()
+            .pending
+            .extend_from_slice(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()?;
+        let mut recording = self.recorder.lock();
+        let result = recording.flush_output();
+        recording.check(result);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::script::Script;
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent};
+
+    /// A file in memory that the test can read while the recorder writes it
+    #[derive(Clone, Default)]
+    struct Shared(Arc<Mutex<Vec<u8>>>);
+
+    impl Shared {
+        fn text(&self) -> String {
+            String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+        }
+    }
+
+    impl Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_output_is_recorded_as_asciicast() {
+        let cast = Shared::default();
+        let recorder = Recorder::new(cast.clone(), io::sink(), (80, 24)).unwrap();
+        let mut output = Recorded::new(Vec::new(), recorder.clone());
+
+        write!(output, "\x1b[2J").unwrap();
+        write!(output, "héllo").unwrap();
+        output.flush().unwrap();
+        output.flush().unwrap();
+        recorder.input(&Event::Resize(100, 30));
+
+        assert_eq!(output.inner, "\x1b[2Jhéllo".as_bytes());
+        let text = cast.text();
+        let lines: Vec<serde_json::Value> = text
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0]["version"], 2);
+        assert_eq!(
+            (lines[0]["width"].clone(), lines[0]["height"].clone()),
+            (json!(80), json!(24))
+        );
+        assert_eq!(lines[1][1], "o");
+        assert_eq!(lines[1][2], "\x1b[2Jhéllo");
+        assert_eq!(lines[2][1], "r");
+        assert_eq!(lines[2][2], "100x30");
+        assert!(lines[2][0].as_f64().unwrap() >= lines[1][0].as_f64().unwrap());
+    }
+
+    #[test]
+    fn test_input_is_recorded_as_a_script() {
+        let input = Shared::default();
+        let recorder = Recorder::new(io::sink(), input.clone(), (120, 40)).unwrap();
+        let mouse = |kind, column, row| {
+            Event::Mouse(MouseEvent {
+                kind,
+                column,
+                row,
+                modifiers: KeyModifiers::NONE,
+            })
+        };
+
+        recorder.input(&Event::Key(KeyEvent::new(
+            KeyCode::Char('2'),
+            KeyModifiers::NONE,
+        )));
+        std::thread::sleep(Duration::from_millis(5));
+        recorder.input(&Event::Key(KeyEvent::new(
+            KeyCode::Char('c'),
+            KeyModifiers::CONTROL,
+        )));
+        recorder.input(&Event::Tick);
+        recorder.input(&mouse(MouseEventKind::Moved, 3, 4));
+        recorder.input(&mouse(MouseEventKind::Down(MouseButton::Left), 19, 20));
+        recorder.input(&mouse(MouseEventKind::ScrollDown, 40, 10));
+        recorder.input(&Event::Resize(100, 30));
+
+        let text = input.text();
+        assert!(text.starts_with("# Recorded by ratatui-tui"));
+        let script: Script = text.parse().unwrap();
+        let steps: Vec<&Step> = script.steps.iter().map(|(_, step)| step).collect();
+        assert!(matches!(steps[2], Step::Wait(pause) if *pause >= Duration::from_millis(5)));
+        let input: Vec<String> = steps
+            .iter()
+            .filter(|step| !matches!(step, Step::Wait(_)))
+            .map(|step| step.to_string())
+            .collect();
+        assert_eq!(
+            input,
+            [
+                "resize 120x40",
+                "press 2",
+                "press Ctrl+C",
+                "mouse down left 19 20",
+                "scroll down 40 10",
+                "resize 100x30"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_input_log_sits_next_to_the_recording() {
+        assert_eq!(
+            input_path(Path::new("bugs/demo.cast")),
+            PathBuf::from("bugs/demo.script")
+        );
+
+        // Nothing is created when both would be the same file
+        let clash = Recorder::create(Path::new("bugs/demo.script"), (80, 24));
+        assert!(matches!(clash, Err(RecordError::InputClash(_))));
+    }
+}
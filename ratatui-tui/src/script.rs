@@ -0,0 +1,603 @@
+use std::{
+    fmt, fs, io,
+    path::Path,
+    str::FromStr,
+    sync::{Arc, Mutex, MutexGuard},
+    time::Duration,
+};
+
+use crossterm::event::{MouseButton, MouseEventKind};
+use ratatui::{
+    backend::{Backend, ClearType, TestBackend, WindowSize},
+    buffer::{Buffer, Cell},
+    layout::{Rect, Size},
+};
+use thiserror::Error;
+use unicode_width::UnicodeWidthStr;
+
+use crate::{keymap::KeyCombo, widgets::ScreenAreas};
+
+/// Size of the screen a script starts on, until it resizes it
+pub const SCREEN_SIZE: (u16, u16) = (80, 24);
+
+#[derive(Debug, Error)]
+pub enum ScriptError {
+    #[error("failed to read script {path}: {source}")]
+    Io { path: String, source: io::Error },
+    #[error("line {line}: {message}")]
+    Invalid { line: usize, message: String },
+    #[error("line {line}: expected {expect}")]
+    Failed { line: usize, expect: Expect },
+}
+
+/// Part of the screen an expectation looks in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Region {
+    Screen,
+    Header,
+    Sidebar,
+    Content,
+    StatusBar,
+}
+
+impl Region {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Region::Screen => "screen",
+            Region::Header => "header",
+            Region::Sidebar => "sidebar",
+            Region::Content => "content",
+            Region::StatusBar => "status",
+        }
+    }
+
+    fn area(&self, screen: Rect, areas: &ScreenAreas) -> Rect {
+        match self {
+            Region::Screen => screen,
+            Region::Header => areas.header,
+            Region::Sidebar => areas.sidebar,
+            Region::Content => areas.content,
+            Region::StatusBar => areas.status_bar,
+        }
+    }
+}
+
+impl FromStr for Region {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "screen" => Ok(Region::Screen),
+            "header" => Ok(Region::Header),
+            "sidebar" => Ok(Region::Sidebar),
+            "content" => Ok(Region::Content),
+            "status" => Ok(Region::StatusBar),
+            _ => Err(format!(
+                "unknown region '{}'; expected screen, header, sidebar, content or status",
+                s
+            )),
+        }
+    }
+}
+
+/// Text that should, or should not, be on screen
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Expect {
+    pub text: String,
+    pub present: bool,
+    pub region: Region,
+}
+
+impl Expect {
+    /// Whether the screen as drawn, laid out in `areas`, meets the expectation
+    pub fn check(&self, buffer: &Buffer, areas: &ScreenAreas) -> bool {
+        let area = self
+            .region
+            .area(buffer.area, areas)
+            .intersection(buffer.area);
+        let found =
+            (area.top()..area.bottom()).any(|row| row_text(buffer, area, row).contains(&self.text));
+        found == self.present
+    }
+}
+
+impl fmt::Display for Expect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let no = if self.present { "" } else { "no " };
+        write!(f, "{}text '{}' in {}", no, self.text, self.region.as_str())
+    }
+}
+
+/// One line of a script
+#[derive(Debug, Clone, PartialEq)]
+pub enum Step {
+    Press(Vec<KeyCombo>),
+    Type(String),
+    Click {
+        column: u16,
+        row: u16,
+    },
+    /// A single button going down, up or dragging, as a recording has it
+    Mouse {
+        kind: MouseEventKind,
+        column: u16,
+        row: u16,
+    },
+    Scroll {
+        down: bool,
+        column: u16,
+        row: u16,
+    },
+    Resize {
+        width: u16,
+        height: u16,
+    },
+    Wait(Duration),
+    Expect(Expect),
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Step::Press(keys) => {
+                write!(f, "press")?;
+                for key in keys {
+                    write!(f, " {}", key)?;
+                }
+                Ok(())
+            }
+            Step::Type(text) => write!(f, "type {}", text),
+            Step::Click { column, row } => write!(f, "click {} {}", column, row),
+            Step::Mouse { kind, column, row } => {
+                let (action, button) = match kind {
+                    MouseEventKind::Down(button) => ("down", button),
+                    MouseEventKind::Up(button) => ("up", button),
+                    MouseEventKind::Drag(button) => ("drag", button),
+                    _ => return write!(f, "# unsupported mouse event {:?}", kind),
+                };
+                write!(
+                    f,
+                    "mouse {} {} {} {}",
+                    action,
+                    button_name(*button),
+                    column,
+                    row
+                )
+            }
+            Step::Scroll { down, column, row } => {
+                let direction = if *down { "down" } else { "up" };
+                write!(f, "scroll {} {} {}", direction, column, row)
+            }
+            Step::Resize { width, height } => write!(f, "resize {}x{}", width, height),
+            Step::Wait(duration) => write!(f, "wait {}ms", duration.as_millis()),
+            Step::Expect(expect) => write!(f, "expect {}", expect),
+        }
+    }
+}
+
+fn button_name(button: MouseButton) -> &'static str {
+    match button {
+        MouseButton::Left => "left",
+        MouseButton::Right => "right",
+        MouseButton::Middle => "middle",
+    }
+}
+
+fn parse_button(name: &str) -> Result<MouseButton, String> {
+    match name {
+        "left" => Ok(MouseButton::Left),
+        "right" => Ok(MouseButton::Right),
+        "middle" => Ok(MouseButton::Middle),
+        _ => Err(format!(
+            "unknown mouse button '{}'; expected left, right or middle",
+            name
+        )),
+    }
+}
+
+/// What a script asks of the main loop besides input
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScriptEvent {
+    /// Draw, then check the screen
+    Expect { line: usize, expect: Expect },
+    /// Every step has run
+    End,
+}
+
+/// Steps to run headless, each with the line it came from
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Script {
+    pub steps: Vec<(usize, Step)>,
+}
+
+impl Script {
+    pub fn load(path: &Path) -> Result<Self, ScriptError> {
+        let text = fs::read_to_string(path).map_err(|source| ScriptError::Io {
+            path: path.display().to_string(),
+            source,
+        })?;
+        text.parse()
+    }
+}
+
+impl FromStr for Script {
+    type Err = ScriptError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut steps = Vec::new();
+        for (index, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let step = parse_step(line).map_err(|message| ScriptError::Invalid {
+                line: index + 1,
+                message,
+            })?;
+            steps.push((index + 1, step));
+        }
+        Ok(Self { steps })
+    }
+}
+
+fn parse_step(line: &str) -> Result<Step, String> {
+    let (command, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+    let rest = rest.trim();
+    let args: Vec<&str> = rest.split_whitespace().collect();
+
+    match (command, &args[..]) {
+        ("press", [_, ..]) => args
+            .iter()
+            .map(|key| key.parse::<KeyCombo>().map_err(|err| err.to_string()))
+            .collect::<Result<_, _>>()
+            .map(Step::Press),
+        ("type", [_, ..]) => Ok(Step::Type(rest.to_string())),
+        ("click", [column, row]) => Ok(Step::Click {
+            column: number(column)?,
+            row: number(row)?,
+        }),
+        ("mouse", [action @ ("down" | "up" | "drag"), button, column, row]) => {
+            let button = parse_button(button)?;
+            let kind = match *action {
+                "down" => MouseEventKind::Down(button),
+                "up" => MouseEventKind::Up(button),
+                _ => MouseEventKind::Drag(button),
+            };
+            Ok(Step::Mouse {
+                kind,
+                column: number(column)?,
+                row: number(row)?,
+            })
+        }
+        ("scroll", [direction @ ("up" | "down"), column, row]) => Ok(Step::Scroll {
+            down: *direction == "down",
+            column: number(column)?,
+            row: number(row)?,
+        }),
+        ("resize", [size]) => {
+            let (width, height) = size
+                .split_once('x')
+                .ok_or_else(|| format!("invalid size '{}'; expected WIDTHxHEIGHT", size))?;
+            Ok(Step::Resize {
+                width: number(width)?,
+                height: number(height)?,
+            })
+        }
+        ("wait", [duration]) => parse_duration(duration).map(Step::Wait),
+        ("expect", _) => parse_expect(rest).map(Step::Expect),
+        _ => Err(format!("cannot understand '{}'", line)),
+    }
+}
+
+fn number(text: &str) -> Result<u16, String> {
+    text.parse()
+        .map_err(|_| format!("invalid number '{}'", text))
+}
+
+/// A duration such as `500ms` or `2s`
+fn parse_duration(text: &str) -> Result<Duration, String> {
+    let invalid = || format!("invalid duration '{}'; expected e.g. 500ms or 2s", text);
+    if let Some(millis) = text.strip_suffix("ms") {
+        return millis
+            .parse()
+            .map(Duration::from_millis)
+            .map_err(|_| invalid());
+    }
+    let seconds: f64 = text
+        .strip_suffix('s')
+        .ok_or_else(invalid)?
+        .parse()
+        .map_err(|_| invalid())?;
+    Duration::try_from_secs_f64(seconds).map_err(|_| invalid())
+}
+
+/// `[no] text 'TEXT' [in REGION]`, quoting with single or double quotes
+fn parse_expect(text: &str) -> Result<Expect, String> {
+    let invalid = || {
+        format!(
+            "invalid expectation '{}'; expected [no] text 'TEXT' [in REGION]",
+            text
+        )
+    };
+    let (present, text_part) = match text.strip_prefix("no ") {
+        Some(rest) => (false, rest.trim_start()),
+        None => (true, text),
+    };
+    let quoted = text_part
+        .strip_prefix("text")
+        .ok_or_else(invalid)?
+        .trim_start();
+    let quote = quoted
+        .chars()
+        .next()
+        .filter(|c| *c == '\'' || *c == '"')
+        .ok_or_else(invalid)?;
+    let (expected, rest) = quoted[1..].split_once(quote).ok_or_else(invalid)?;
+
+    let region = match rest.trim() {
+        "" => Region::Screen,
+        rest => rest
+            .strip_prefix("in ")
+            .ok_or_else(invalid)?
+            .trim()
+            .parse()?,
+    };
+    Ok(Expect {
+        text: expected.to_string(),
+        present,
+        region,
+    })
+}
+
+/// What a row of the screen shows within an area, leaving out the cells hidden by wide characters
+fn row_text(buffer: &Buffer, area: Rect, row: u16) -> String {
+    let mut text = String::new();
+    let mut skip = 0;
+    for column in area.left()..area.right() {
+        let symbol = buffer.get(column, row).symbol();
+        if skip == 0 {
+            text.push_str(symbol);
+        }
+        skip = skip.max(symbol.width()).saturating_sub(1);
+    }
+    text
+}
+
+/// The whole screen as text, a line per row
+pub fn screen_text(buffer: &Buffer) -> String {
+    let area = buffer.area;
+    (area.top()..area.bottom())
+        .map(|row| row_text(buffer, area, row).trim_end().to_string())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// A screen kept in memory, for running without a terminal; clones share the
+/// screen, so a script can resize it while the app draws to it
+#[derive(Debug, Clone)]
+pub struct HeadlessBackend {
+    inner: Arc<Mutex<TestBackend>>,
+}
+
+impl HeadlessBackend {
+    pub fn new(width: u16, height: u16) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(TestBackend::new(width, height))),
+        }
+    }
+
+    pub fn resize(&self, width: u16, height: u16) {
+        self.lock().resize(width, height);
+    }
+
+    /// The screen as last drawn
+    pub fn screen(&self) -> String {
+        screen_text(self.lock().buffer())
+    }
+
+    fn lock(&self) -> MutexGuard<'_, TestBackend> {
+        self.inner
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl Backend for HeadlessBackend {
+    fn draw<'a, I>(&mut self, content: I) -> io::Result<()>
+    where
+        I: Iterator<Item = (u16, u16, &'a Cell)>,
+    {
+        self.lock().draw(content)
+    }
+
+    fn hide_cursor(&mut self) -> io::Result<()> {
+        self.lock().hide_cursor()
+    }
+
+    fn show_cursor(&mut self) -> io::Result<()> {
+        self.lock().show_cursor()
+    }
+
+    fn get_cursor(&mut self) -> io::Result<(u16, u16)> {
+        self.lock().get_cursor()
+    }
+
+    fn set_cursor(&mut self, x: u16, y: u16) -> io::Result<()> {
+        self.lock().set_cursor(x, y)
+    }
+
+    fn clear(&mut self) -> io::Result<()> {
+        self.lock().clear()
+    }
+
+    fn clear_region(&mut self, clear_type: ClearType) -> io::Result<()> {
+        self.lock().clear_region(clear_type)
+    }
+
+    fn size(&self) -> io::Result<Rect> {
+        self.lock().size()
+    }
+
+    fn window_size(&mut self) -> io::Result<WindowSize> {
+        let size = self.size()?;
+        Ok(WindowSize {
+            columns_rows: Size::new(size.width, size.height),
+            pixels: Size::default(),
+        })
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.lock().flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::{KeyCode, KeyModifiers};
+    use ratatui::{text::Line, widgets::Paragraph, Terminal};
+
+    #[test]
+    fn test_scripts_are_parsed_line_by_line() {
+        let script: Script = "
+            # Open the Data Browser and filter it
+            press 2
+            press g g Ctrl+C
+            type status = active
+            click 5 9
+            scroll down 40 10
+            resize 100x30
+            wait 250ms
+            wait 1.5s
+            expect text 'Data Browser' in content
+            expect no text \"Error\"
+        "
+        .parse()
+        .unwrap();
+
+        let steps: Vec<Step> = script.steps.iter().map(|(_, step)| step.clone()).collect();
+        assert_eq!(script.steps[0].0, 3);
+        assert_eq!(
+            steps[1],
+            Step::Press(vec![
+                KeyCombo::new(KeyCode::Char('g'), KeyModifiers::NONE),
+                KeyCombo::new(KeyCode::Char('g'), KeyModifiers::NONE),
+                KeyCombo::new(KeyCode::Char('c'), KeyModifiers::CONTROL),
+            ])
+        );
+        assert_eq!(steps[2], Step::Type("status = active".to_string()));
+        assert_eq!(
+            steps[4],
+            Step::Scroll {
+                down: true,
+                column: 40,
+                row: 10
+            }
+        );
+        assert_eq!(
+            steps[5],
+            Step::Resize {
+                width: 100,
+                height: 30
+            }
+        );
+        assert_eq!(steps[7], Step::Wait(Duration::from_millis(1500)));
+        assert_eq!(
+            steps[8],
+            Step::Expect(Expect {
+                text: "Data Browser".to_string(),
+                present: true,
+                region: Region::Content,
+            })
+        );
+        assert_eq!(
+            steps[9],
+            Step::Expect(Expect {
+                text: "Error".to_string(),
+                present: false,
+                region: Region::Screen,
+            })
+        );
+    }
+
+    #[test]
+    fn test_steps_are_written_as_they_are_parsed() {
+        let lines = [
+            "press g g Ctrl+C Space",
+            "type status = active",
+            "click 5 9",
+            "mouse down left 19 20",
+            "mouse drag left 29 20",
+            "mouse up right 29 20",
+            "scroll up 40 10",
+            "resize 100x30",
+            "wait 1500ms",
+            "expect no text 'Error' in status",
+        ];
+        for line in lines {
+            let script: Script = line.parse().unwrap();
+            assert_eq!(script.steps[0].1.to_string(), line);
+        }
+    }
+
+    #[test]
+    fn test_script_errors_name_the_line() {
+        let errors = [
+            ("press 2\nfly away", "line 2: cannot understand 'fly away'"),
+            ("press Hyper+x", "line 1: invalid key 'Hyper+x'"),
+            (
+                "wait soon",
+                "line 1: invalid duration 'soon'; expected e.g. 500ms or 2s",
+            ),
+            (
+                "expect text 'x' in footer",
+                "line 1: unknown region 'footer'",
+            ),
+            (
+                "resize 80",
+                "line 1: invalid size '80'; expected WIDTHxHEIGHT",
+            ),
+            (
+                "mouse down thumb 1 2",
+                "line 1: unknown mouse button 'thumb'",
+            ),
+        ];
+        for (script, message) in errors {
+            let err = script.parse::<Script>().unwrap_err().to_string();
+            assert!(err.starts_with(message), "{}", err);
+        }
+    }
+
+    #[test]
+    fn test_expectations_look_in_their_region() {
+        let backend = HeadlessBackend::new(20, 3);
+        let mut terminal = Terminal::new(backend.clone()).unwrap();
+        terminal
+            .draw(|f| {
+                f.render_widget(
+                    Paragraph::new(vec![Line::from("Title"), Line::from("📁 Data")]),
+                    f.size(),
+                )
+            })
+            .unwrap();
+        let areas = ScreenAreas {
+            header: Rect::new(0, 0, 20, 1),
+            content: Rect::new(0, 1, 20, 2),
+            ..ScreenAreas::default()
+        };
+
+        let expect = |text: &str, present, region| Expect {
+            text: text.to_string(),
+            present,
+            region,
+        };
+        let buffer = terminal.backend().lock().buffer().clone();
+        assert!(expect("📁 Data", true, Region::Content).check(&buffer, &areas));
+        assert!(expect("Title", false, Region::Content).check(&buffer, &areas));
+        assert!(!expect("Title", true, Region::Sidebar).check(&buffer, &areas));
+        assert_eq!(backend.screen(), "Title\n📁 Data\n");
+
+        backend.resize(10, 2);
+        assert_eq!(terminal.size().unwrap(), Rect::new(0, 0, 10, 2));
+    }
+}
@@ -0,0 +1,478 @@
+use crossterm::event::{KeyCode, KeyModifiers};
+use ratatui::{
+    style::Style,
+    text::{Line, Span},
+};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::keymap::KeyCombo;
+
+/// How far a view's text is scrolled, what it is searched for, and how it was last drawn
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TextScroll {
+    /// First row on screen
+    pub offset: usize,
+    /// Rows of text once wrapped, and how many fit on screen, as last drawn
+    pub rows: usize,
+    pub viewport: usize,
+    /// Highlighted wherever it appears; empty when not searching
+    pub query: String,
+    /// Open while the query is being typed
+    pub search: Option<SearchBar>,
+    /// Matches of the query found when last drawn
+    pub matches: usize,
+    /// Index of the current match, if one has been picked
+    pub current: Option<usize>,
+    /// Bring the current match on screen when next drawn; with none picked,
+    /// the first at or below the top of the screen becomes current
+    pub reveal: bool,
+    /// Line the view keeps on screen, such as the focused field, as last drawn
+    pub cursor: Option<usize>,
+}
+
+impl TextScroll {
+    pub fn max_offset(&self) -> usize {
+        self.rows.saturating_sub(self.viewport)
+    }
+
+    fn scroll_to(&mut self, offset: usize) -> bool {
+        let offset = offset.min(self.max_offset());
+        let changed = offset != self.offset;
+        self.offset = offset;
+        changed
+    }
+
+    pub fn scroll_by(&mut self, delta: isize) -> bool {
+        self.scroll_to(self.offset.saturating_add_signed(delta))
+    }
+
+    /// Move a screenful, keeping a row of the last one for context
+    pub fn page(&mut self, forward: bool) -> bool {
+        let page = self.viewport.saturating_sub(1).max(1) as isize;
+        self.scroll_by(if forward { page } else { -page })
+    }
+
+    pub fn scroll_top(&mut self) -> bool {
+        self.scroll_to(0)
+    }
+
+    pub fn scroll_bottom(&mut self) -> bool {
+        self.scroll_to(self.max_offset())
+    }
+
+    /// Make the next (or previous) match current, wrapping around the text
+    pub fn next_match(&mut self, forward: bool) -> bool {
+        if self.matches == 0 {
+            return false;
+        }
+        self.current = match self.current {
+            Some(current) if forward => Some((current + 1) % self.matches),
+            Some(current) => Some((current + self.matches - 1) % self.matches),
+            None => None,
+        };
+        self.reveal = true;
+        true
+    }
+
+    /// Start typing a query, beginning with the one in use
+    pub fn open_search(&mut self) {
+        self.search = Some(SearchBar::new(&self.query));
+    }
+
+    /// Type into the open search bar, searching as the query changes; `None` for
+    /// keys the bar leaves to the keymap, else whether anything changed
+    pub fn input(&mut self, key: KeyCombo) -> Option<bool> {
+        let outcome = self.search.as_mut()?.input(key)?;
+        match outcome {
+            SearchOutcome::Unchanged => return Some(false),
+            SearchOutcome::Edited => {
+                self.query = self
+                    .search
+                    .as_ref()
+                    .map(|bar| bar.text.clone())
+                    .unwrap_or_default();
+            }
+            // The query was searched for as it was typed, so there is nothing left to find
+            SearchOutcome::Keep => {
+                self.search = None;
+                return Some(true);
+            }
+            SearchOutcome::Cancel => {
+                self.query = self
+                    .search
+                    .take()
+                    .map(|bar| bar.previous)
+                    .unwrap_or_default();
+            }
+        }
+        self.current = None;
+        self.reveal = true;
+        Some(true)
+    }
+
+    /// Record how text was laid out to be drawn, settling the offset and current match
+    pub fn lay_out(
+        &mut self,
+        rows: usize,
+        viewport: usize,
+        matches: &[Match],
+        cursor: Option<(usize, usize)>,
+    ) {
+        self.rows = rows;
+        self.viewport = viewport;
+        self.matches = matches.len();
+        self.current = self.current.filter(|current| *current < matches.len());
+
+        // Follow the view's cursor when it moves, so a focused field is never off screen
+        if let Some((line, row)) = cursor {
+            if self.cursor != Some(line) {
+                self.show_row(row);
+            }
+        }
+        self.cursor = cursor.map(|(line, _)| line);
+
+        if std::mem::take(&mut self.reveal) && !matches.is_empty() {
+            let current = self.current.unwrap_or_else(|| {
+                matches
+                    .iter()
+                    .position(|found| found.row >= self.offset)
+                    .unwrap_or(0)
+            });
+            self.current = Some(current);
+            self.show_row(matches[current].row);
+        }
+        self.offset = self.offset.min(self.max_offset());
+    }
+
+    fn show_row(&mut self, row: usize) {
+        if row < self.offset {
+            self.offset = row;
+        } else if row >= self.offset + self.viewport {
+            self.offset = row + 1 - self.viewport.max(1);
+        }
+    }
+}
+
+/// What a key press in the search bar asks for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchOutcome {
+    Edited,
+    Unchanged,
+    Keep,
+    /// Go back to the query in use when the bar opened
+    Cancel,
+}
+
+/// Query being typed after `/`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchBar {
+    pub text: String,
+    previous: String,
+}
+
+impl SearchBar {
+    pub fn new(query: &str) -> Self {
+        Self {
+            text: query.to_string(),
+            previous: query.to_string(),
+        }
+    }
+
+    /// Type, keep or cancel; `None` for keys the bar leaves to the keymap
+    pub fn input(&mut self, key: KeyCombo) -> Option<SearchOutcome> {
+        if key.modifiers.contains(KeyModifiers::CONTROL) {
+            return None;
+        }
+
+        let outcome = match key.code {
+            KeyCode::Char(c) => {
+                self.text.push(c);
+                SearchOutcome::Edited
+            }
+            KeyCode::Backspace if !self.text.is_empty() => {
+                self.text.pop();
+                SearchOutcome::Edited
+            }
+            KeyCode::Enter => SearchOutcome::Keep,
+            KeyCode::Esc => SearchOutcome::Cancel,
+            _ => SearchOutcome::Unchanged,
+        };
+
+        Some(outcome)
+    }
+}
+
+/// Text wrapped to a width, one line per row on screen
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Wrapped {
+    pub rows: Vec<Line<'static>>,
+    /// First row of each line of the original text
+    pub starts: Vec<usize>,
+}
+
+/// Wrap lines at spaces to fit `width` columns, breaking words too long for a row;
+/// spaces where a line breaks are dropped
+pub fn wrap(lines: Vec<Line<'static>>, width: usize) -> Wrapped {
+    let width = width.max(1);
+    let mut wrapped = Wrapped::default();
+
+    for line in lines {
+        wrapped.starts.push(wrapped.rows.len());
+        let mut row: Vec<Span<'static>> = Vec::new();
+        let mut used = 0;
+
+        for span in &line.spans {
+            for piece in pieces(&span.content) {
+                let piece_width = piece.width();
+                let blank = piece.trim().is_empty();
+                if used + piece_width <= width {
+                    used += piece_width;
+                    push(&mut row, piece, span.style);
+                    continue;
+                }
+                if used > 0 {
+                    wrapped.rows.push(break_row(&mut row, line.style));
+                    used = 0;
+                }
+                if blank {
+                    continue;
+                }
+                // Words wider than a row are broken wherever they reach the edge
+                for c in piece.chars() {
+                    let char_width = c.width().unwrap_or(0);
+                    if used + char_width > width && used > 0 {
+                        wrapped.rows.push(break_row(&mut row, line.style));
+                        used = 0;
+                    }
+                    used += char_width;
+                    push(&mut row, &c.to_string(), span.style);
+                }
+            }
+        }
+        wrapped.rows.push(Line::from(row).style(line.style));
+    }
+
+    wrapped
+}
+
+/// End a row where a line breaks, dropping the spaces it ends with
+fn break_row(row: &mut Vec<Span<'static>>, style: Style) -> Line<'static> {
+    let mut spans = std::mem::take(row);
+    while let Some(last) = spans.last_mut() {
+        let trimmed = last.content.trim_end().len();
+        if trimmed > 0 {
+            last.content.to_mut().truncate(trimmed);
+            break;
+        }
+        spans.pop();
+    }
+    Line::from(spans).style(style)
+}
+
+/// Words and the runs of spaces between them
+fn pieces(text: &str) -> Vec<&str> {
+    let mut pieces = Vec::new();
+    let mut start = 0;
+    let mut blank = None;
+    for (index, c) in text.char_indices() {
+        let is_blank = c.is_whitespace();
+        if blank.is_some_and(|blank| blank != is_blank) {
+            pieces.push(&text[start..index]);
+            start = index;
+        }
+        blank = Some(is_blank);
+    }
+    if start < text.len() {
+        pieces.push(&text[start..]);
+    }
+    pieces
+}
+
+/// Add text to a row, joining it to the last span when the style is the same
+fn push(row: &mut Vec<Span<'static>>, text: &str, style: Style) {
+    match row.last_mut() {
+        Some(last) if last.style == style => last.content.to_mut().push_str(text),
+        _ => row.push(Span::styled(text.to_string(), style)),
+    }
+}
+
+/// Where a query was found: a row and a range of characters in it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Match {
+    pub row: usize,
+    pub start: usize,
+    pub len: usize,
+}
+
+/// Every place `query` appears in the rows, ignoring case, in reading order
+pub fn find(rows: &[Line], query: &str) -> Vec<Match> {
+    let query: Vec<char> = query.chars().map(fold).collect();
+    if query.is_empty() {
+        return Vec::new();
+    }
+
+    let mut matches = Vec::new();
+    for (row, line) in rows.iter().enumerate() {
+        let text: Vec<char> = line
+            .spans
+            .iter()
+            .flat_map(|span| span.content.chars())
+            .map(fold)
+            .collect();
+        let mut start = 0;
+        while start + query.len() <= text.len() {
+            if text[start..start + query.len()] == query[..] {
+                matches.push(Match {
+                    row,
+                    start,
+                    len: query.len(),
+                });
+                start += query.len();
+            } else {
+                start += 1;
+            }
+        }
+    }
+    matches
+}
+
+fn fold(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+/// Restyle the matched characters of a row, patching `style` over what they had
+pub fn highlight(line: Line<'static>, matches: &[(Match, Style)]) -> Line<'static> {
+    if matches.is_empty() {
+        return line;
+    }
+
+    let mut spans: Vec<Span<'static>> = Vec::new();
+    let mut index = 0;
+    for span in &line.spans {
+        for c in span.content.chars() {
+            let style = matches
+                .iter()
+                .find(|(found, _)| (found.start..found.start + found.len).contains(&index))
+                .map_or(span.style, |(_, style)| span.style.patch(*style));
+            push(&mut spans, &c.to_string(), style);
+            index += 1;
+        }
+    }
+    Line::from(spans).style(line.style)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::style::{Color, Modifier};
+
+    fn text(line: &Line) -> String {
+        line.spans
+            .iter()
+            .map(|span| span.content.as_ref())
+            .collect()
+    }
+
+    #[test]
+    fn test_lines_wrap_at_spaces_keeping_styles() {
+        let bold = Style::default().add_modifier(Modifier::BOLD);
+        let lines = vec![
+            Line::from(vec![
+                Span::styled("Keyboard", bold),
+                Span::raw(" shortcuts are listed below"),
+            ]),
+            Line::from(""),
+            Line::from("  note"),
+            Line::from("abcdefghij"),
+        ];
+        let wrapped = wrap(lines, 8);
+
+        let rows: Vec<String> = wrapped.rows.iter().map(text).collect();
+        assert_eq!(
+            rows,
+            ["Keyboard", "shortcut", "s are", "listed", "below", "", "  note", "abcdefgh", "ij"]
+        );
+        assert_eq!(wrapped.starts, [0, 5, 6, 7]);
+        assert_eq!(wrapped.rows[0].spans[0].style, bold);
+    }
+
+    #[test]
+    fn test_matches_are_found_ignoring_case_and_highlighted() {
+        let rows = vec![
+            Line::from("Scroll up, scroll down"),
+            Line::from("nothing"),
+            Line::from("SCROLL"),
+        ];
+        let matches = find(&rows, "scroll");
+        assert_eq!(matches.len(), 3);
+        assert_eq!(
+            matches[1],
+            Match {
+                row: 0,
+                start: 11,
+                len: 6
+            }
+        );
+        assert_eq!(matches[2].row, 2);
+        assert!(find(&rows, "").is_empty());
+
+        let style = Style::default().bg(Color::Yellow);
+        let line = highlight(rows[0].clone(), &[(matches[1], style)]);
+        assert_eq!(text(&line), "Scroll up, scroll down");
+        assert_eq!(line.spans[1].content, "scroll");
+        assert_eq!(line.spans[1].style.bg, Some(Color::Yellow));
+    }
+
+    #[test]
+    fn test_scrolling_stays_within_the_text() {
+        let mut scroll = TextScroll::default();
+        scroll.lay_out(30, 10, &[], None);
+
+        assert!(!scroll.scroll_by(-1));
+        assert!(scroll.page(true));
+        assert_eq!(scroll.offset, 9);
+        assert!(scroll.scroll_bottom());
+        assert_eq!(scroll.offset, 20);
+        assert!(!scroll.scroll_by(1));
+
+        // A shorter text pulls the offset back
+        scroll.lay_out(15, 10, &[], None);
+        assert_eq!(scroll.offset, 5);
+    }
+
+    #[test]
+    fn test_search_reveals_matches_in_turn() {
+        let mut scroll = TextScroll::default();
+        scroll.lay_out(40, 10, &[], None);
+        scroll.scroll_by(5);
+
+        scroll.open_search();
+        let key = |code| KeyCombo::new(code, KeyModifiers::NONE);
+        assert_eq!(scroll.input(key(KeyCode::Char('q'))), Some(true));
+        assert_eq!(scroll.query, "q");
+
+        // The first match below the top of the screen becomes current
+        let at = |row| Match {
+            row,
+            start: 0,
+            len: 1,
+        };
+        let matches = [at(2), at(20), at(35)];
+        scroll.lay_out(40, 10, &matches, None);
+        assert_eq!(scroll.current, Some(1));
+        assert_eq!(scroll.offset, 11);
+
+        assert!(scroll.next_match(true));
+        scroll.lay_out(40, 10, &matches, None);
+        assert_eq!(scroll.offset, 26);
+        scroll.next_match(true);
+        scroll.lay_out(40, 10, &matches, None);
+        assert_eq!((scroll.current, scroll.offset), (Some(0), 2));
+
+        // Escape goes back to the query from before
+        assert_eq!(scroll.input(key(KeyCode::Esc)), Some(true));
+        assert_eq!(scroll.query, "");
+        assert!(scroll.search.is_none());
+        assert_eq!(scroll.input(key(KeyCode::Char('x'))), None);
+    }
+}
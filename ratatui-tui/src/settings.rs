@@ -0,0 +1,601 @@
+use std::{path::PathBuf, str::FromStr};
+
+use crossterm::event::{KeyCode, KeyModifiers};
+
+use crate::{
+    config::{self, ColorMode, Config, LogLevel, Overrides},
+    keymap::KeyCombo,
+};
+
+/// Longest number that can be typed into a numeric field
+const MAX_NUMBER_LEN: usize = 5;
+
+/// Fields of the Settings form, in focus order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum SettingField {
+    Theme,
+    ColorMode,
+    SidebarWidth,
+    ShowSidebar,
+    ShowStatusBar,
+    Compact,
+    Mouse,
+    LogLevel,
+}
+
+impl FromStr for SettingField {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        SettingField::ALL
+            .into_iter()
+            .find(|field| field.as_str() == s)
+            .ok_or_else(|| format!("unknown setting '{}'", s))
+    }
+}
+
+/// How a field is edited
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldKind {
+    /// On or off, flipped with Enter
+    Toggle,
+    /// One of a list of values, cycled with Enter or Left/Right
+    Select,
+    /// A number, stepped with Left/Right or typed after pressing Enter
+    Numeric,
+}
+
+impl SettingField {
+    pub const ALL: [SettingField; 8] = [
+        SettingField::Theme,
+        SettingField::ColorMode,
+        SettingField::SidebarWidth,
+        SettingField::ShowSidebar,
+        SettingField::ShowStatusBar,
+        SettingField::Compact,
+        SettingField::Mouse,
+        SettingField::LogLevel,
+    ];
+
+    /// The name used to refer to the field in configuration files
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SettingField::Theme => "theme",
+            SettingField::ColorMode => "color_mode",
+            SettingField::SidebarWidth => "sidebar_width",
+            SettingField::ShowSidebar => "show_sidebar",
+            SettingField::ShowStatusBar => "show_status_bar",
+            SettingField::Compact => "compact",
+            SettingField::Mouse => "mouse",
+            SettingField::LogLevel => "log_level",
+        }
+    }
+
+    /// Position in the form, and in focus order
+    pub fn index(&self) -> usize {
+        SettingField::ALL
+            .iter()
+            .position(|field| field == self)
+            .unwrap_or(0)
+    }
+
+    /// Heading of the group the field is shown under
+    pub fn section(&self) -> &'static str {
+        match self {
+            SettingField::Theme | SettingField::ColorMode => "Appearance",
+            SettingField::SidebarWidth
+            | SettingField::ShowSidebar
+            | SettingField::ShowStatusBar
+            | SettingField::Compact => "Layout",
+            SettingField::Mouse => "Input",
+            SettingField::LogLevel => "Logging",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SettingField::Theme => "Theme",
+            SettingField::ColorMode => "Colors",
+            SettingField::SidebarWidth => "Sidebar width",
+            SettingField::ShowSidebar => "Show sidebar",
+            SettingField::ShowStatusBar => "Show status bar",
+            SettingField::Compact => "Compact mode",
+            SettingField::Mouse => "Mouse",
+            SettingField::LogLevel => "Log level",
+        }
+    }
+
+    pub fn kind(&self) -> FieldKind {
+        match self {
+            SettingField::Theme | SettingField::ColorMode | SettingField::LogLevel => {
+                FieldKind::Select
+            }
+            SettingField::SidebarWidth => FieldKind::Numeric,
+            SettingField::ShowSidebar
+            | SettingField::ShowStatusBar
+            | SettingField::Compact
+            | SettingField::Mouse => FieldKind::Toggle,
+        }
+    }
+
+    /// The field's current value as text
+    pub fn value(&self, config: &Config) -> String {
+        match self {
+            SettingField::Theme => config.theme.clone(),
+            SettingField::ColorMode => config.color_mode.as_str().to_string(),
+            SettingField::SidebarWidth => config.layout.sidebar_width.to_string(),
+            SettingField::ShowSidebar => config.layout.show_sidebar.to_string(),
+            SettingField::ShowStatusBar => config.layout.show_status_bar.to_string(),
+            SettingField::Compact => config.layout.compact.to_string(),
+            SettingField::Mouse => config.mouse.to_string(),
+            SettingField::LogLevel => config.log_level.as_str().to_string(),
+        }
+    }
+
+    /// Whether a toggle field is on
+    pub fn is_on(&self, config: &Config) -> bool {
+        match self {
+            SettingField::ShowSidebar => config.layout.show_sidebar,
+            SettingField::ShowStatusBar => config.layout.show_status_bar,
+            SettingField::Compact => config.layout.compact,
+            SettingField::Mouse => config.mouse,
+            _ => false,
+        }
+    }
+}
+
+/// Outcome of the last save or revert, shown below the form
+#[derive(Debug, Clone, PartialEq)]
+pub enum FormNotice {
+    Saved(PathBuf),
+    Reverted,
+    SaveFailed(String),
+}
+
+/// List of themes opened from the theme field, previewing the selected theme
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThemePicker {
+    pub selected: usize,
+    /// Theme to restore if the pick is cancelled
+    original: String,
+}
+
+/// Editing state of the Settings form; the values themselves live in the app's `Config`
+#[derive(Debug, Clone, Default)]
+pub struct SettingsForm {
+    focus: usize,
+    /// Text typed into the focused numeric field, while it is being edited
+    pub editing: Option<String>,
+    /// Validation error for a field, shown under it
+    pub error: Option<(SettingField, String)>,
+    pub notice: Option<FormNotice>,
+    pub picker: Option<ThemePicker>,
+    /// The configuration as last loaded or saved, with the command-line overrides in force
+    pub saved: Config,
+    /// What the config file holds
+    pub on_disk: Config,
+    /// Settings from the command line, kept out of the file
+    pub overrides: Overrides,
+    /// Theme names offered by the theme selector
+    pub themes: Vec<String>,
+}
+
+impl SettingsForm {
+    pub fn new(saved: Config, themes: Vec<String>) -> Self {
+        Self {
+            on_disk: saved.clone(),
+            saved,
+            themes,
+            ..Self::default()
+        }
+    }
+
+    pub fn focused(&self) -> SettingField {
+        SettingField::ALL[self.focus]
+    }
+
+    /// Whether key presses are text or list navigation rather than bindings
+    pub fn capturing_input(&self) -> bool {
+        self.editing.is_some() || self.picker.is_some()
+    }
+
+    /// Replace the theme names offered, keeping the picker on the same theme where possible
+    pub fn set_themes(&mut self, themes: Vec<String>) {
+        if let Some(picker) = &mut self.picker {
+            let selected = &self.themes[picker.selected];
+            picker.selected = themes.iter().position(|name| name == selected).unwrap_or(0);
+        }
+        self.themes = themes;
+        if self.themes.is_empty() {
+            self.picker = None;
+        }
+    }
+
+    /// Put command-line overrides in force over the loaded settings, without
+    /// them counting as unsaved changes
+    pub fn set_overrides(&mut self, config: &mut Config, overrides: Overrides) {
+        *config = overrides.apply(config.clone());
+        self.saved = overrides.apply(self.saved.clone());
+        self.overrides = overrides;
+    }
+
+    /// What to write to the config file for the live configuration
+    pub fn persisted(&self, config: &Config) -> Config {
+        self.overrides.persisted(config, &self.on_disk)
+    }
+
+    /// Note that `written` went to the config file for the live `config`
+    pub fn mark_saved(&mut self, config: &Config, written: Config) {
+        self.saved = config.clone();
+        self.on_disk = written;
+    }
+
+    /// Whether the live configuration differs from the saved one
+    pub fn is_dirty(&self, config: &Config) -> bool {
+        *config != self.saved
+    }
+
+    /// Focus the field at `index` in `SettingField::ALL`, abandoning any number being typed
+    pub fn set_focus(&mut self, index: usize) {
+        if index != self.focus {
+            self.editing = None;
+        }
+        self.focus = index.min(SettingField::ALL.len() - 1);
+    }
+
+    /// Flip a toggle, cycle a select, open the theme picker, or start or commit
+    /// editing a number; returns whether the configuration changed
+    pub fn activate(&mut self, config: &mut Config) -> bool {
+        let field = self.focused();
+        match field.kind() {
+            FieldKind::Select if field == SettingField::Theme => {
+                self.open_picker(config);
+                false
+            }
+            FieldKind::Toggle | FieldKind::Select => self.step(config, true),
+            FieldKind::Numeric => match self.editing.take() {
+                Some(text) => self.commit(config, field, &text),
+                None => {
+                    self.editing = Some(field.value(config));
+                    false
+                }
+            },
+        }
+    }
+
+    /// Move the focused field's value forwards or backwards; returns whether the configuration changed
+    pub fn step(&mut self, config: &mut Config, forward: bool) -> bool {
+        self.editing = None;
+        self.notice = None;
+
+        let field = self.focused();
+        match field {
+            SettingField::Theme => {
+                if self.themes.is_empty() {
+                    return false;
+                }
+                config.theme = cycle(&self.themes, &config.theme, forward).clone();
+            }
+            SettingField::ColorMode => {
+                config.color_mode = *cycle(&ColorMode::ALL, &config.color_mode, forward);
+            }
+            SettingField::LogLevel => {
+                config.log_level = *cycle(&LogLevel::ALL, &config.log_level, forward);
+            }
+            SettingField::SidebarWidth => {
+                let width = config.layout.sidebar_width;
+                let width = if forward {
+                    width.saturating_add(1)
+                } else {
+                    width.saturating_sub(1)
+                };
+                if let Err(message) = config::validate_sidebar_width(width) {
+                    self.error = Some((field, message));
+                    return false;
+                }
+                config.layout.sidebar_width = width;
+            }
+            SettingField::ShowSidebar => config.layout.show_sidebar = !config.layout.show_sidebar,
+            SettingField::ShowStatusBar => {
+                config.layout.show_status_bar = !config.layout.show_status_bar
+            }
+            SettingField::Compact => config.layout.compact = !config.layout.compact,
+            SettingField::Mouse => config.mouse = !config.mouse,
+        }
+
+        self.clear_error(field);
+        true
+    }
+
+    /// Edit the number being typed or move through the theme picker; `None` if the
+    /// key should be handled as a normal binding, otherwise whether the configuration changed
+    pub fn input(&mut self, config: &mut Config, key: KeyCombo) -> Option<bool> {
+        if key.modifiers.contains(KeyModifiers::CONTROL) {
+            return None;
+        }
+        if self.picker.is_some() {
+            return Some(self.pick(config, key));
+        }
+        let text = self.editing.as_mut()?;
+
+        match key.code {
+            KeyCode::Char(c) if c.is_ascii_digit() && text.len() < MAX_NUMBER_LEN => text.push(c),
+            KeyCode::Backspace => {
+                text.pop();
+            }
+            KeyCode::Enter => return Some(self.activate(config)),
+            KeyCode::Esc => {
+                self.editing = None;
+                self.clear_error(self.focused());
+            }
+            _ => {}
+        }
+
+        Some(false)
+    }
+
+    /// Discard unsaved changes
+    pub fn revert(&mut self, config: &mut Config) {
+        *config = self.saved.clone();
+        self.editing = None;
+        self.picker = None;
+        self.error = None;
+        self.notice = Some(FormNotice::Reverted);
+    }
+
+    /// Validate and store a typed number; keeps editing if it is invalid
+    fn commit(&mut self, config: &mut Config, field: SettingField, text: &str) -> bool {
+        let width = text
+            .parse::<u16>()
+            .map_err(|_| "must be a number".to_string())
+            .and_then(|width| config::validate_sidebar_width(width).map(|_| width));
+
+        match width {
+            Ok(width) => {
+                self.clear_error(field);
+                self.notice = None;
+                let changed = config.layout.sidebar_width != width;
+                config.layout.sidebar_width = width;
+                changed
+            }
+            Err(message) => {
+                self.error = Some((field, message));
+                self.editing = Some(text.to_string());
+                false
+            }
+        }
+    }
+
+    fn open_picker(&mut self, config: &Config) {
+        if self.themes.is_empty() {
+            return;
+        }
+        self.notice = None;
+        self.picker = Some(ThemePicker {
+            selected: self
+                .themes
+                .iter()
+                .position(|name| *name == config.theme)
+                .unwrap_or(0),
+            original: config.theme.clone(),
+        });
+    }
+
+    /// Preview the theme under the cursor, keep it with Enter or restore the original with Esc
+    fn pick(&mut self, config: &mut Config, key: KeyCombo) -> bool {
+        let Some(picker) = self.picker.as_mut() else {
+            return false;
+        };
+        let len = self.themes.len();
+
+        let selected = match key.code {
+            KeyCode::Up | KeyCode::BackTab | KeyCode::Char('k') => {
+                (picker.selected + len - 1) % len
+            }
+            KeyCode::Down | KeyCode::Tab | KeyCode::Char('j') => (picker.selected + 1) % len,
+            KeyCode::Enter | KeyCode::Char(' ') => {
+                self.picker = None;
+                return false;
+            }
+            KeyCode::Esc => {
+                let original = picker.original.clone();
+                self.picker = None;
+                return set_theme(config, original);
+            }
+            _ => return false,
+        };
+
+        picker.selected = selected;
+        self.clear_error(SettingField::Theme);
+        set_theme(config, self.themes[selected].clone())
+    }
+
+    pub fn clear_error(&mut self, field: SettingField) {
+        if self
+            .error
+            .as_ref()
+            .is_some_and(|(error_field, _)| *error_field == field)
+        {
+            self.error = None;
+        }
+    }
+}
+
+fn set_theme(config: &mut Config, theme: String) -> bool {
+    let changed = config.theme != theme;
+    config.theme = theme;
+    changed
+}
+
+/// The item after (or before) `current`, wrapping around; the first item if `current` is not listed
+fn cycle<'a, T: PartialEq>(items: &'a [T], current: &T, forward: bool) -> &'a T {
+    let len = items.len();
+    let next = match items.iter().position(|item| item == current) {
+        Some(index) if forward => (index + 1) % len,
+        Some(index) => (index + len - 1) % len,
+        None => 0,
+    };
+    &items[next]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn form() -> SettingsForm {
+        SettingsForm::new(
+            Config::default(),
+            vec!["light".to_string(), "dark".to_string()],
+        )
+    }
+
+    fn focus(form: &mut SettingsForm, field: SettingField) {
+        let index = SettingField::ALL.iter().position(|f| *f == field).unwrap();
+        form.set_focus(index);
+    }
+
+    fn type_text(form: &mut SettingsForm, config: &mut Config, text: &str) {
+        for c in text.chars() {
+            form.input(config, KeyCombo::new(KeyCode::Char(c), KeyModifiers::NONE));
+        }
+    }
+
+    #[test]
+    fn test_moving_focus_abandons_editing() {
+        let mut form = form();
+        let mut config = Config::default();
+        focus(&mut form, SettingField::SidebarWidth);
+        form.activate(&mut config);
+        assert!(form.editing.is_some());
+
+        focus(&mut form, SettingField::LogLevel);
+        assert_eq!(form.focused(), SettingField::LogLevel);
+        assert_eq!(form.editing, None);
+    }
+
+    #[test]
+    fn test_toggle_and_select() {
+        let mut form = form();
+        let mut config = Config::default();
+
+        assert!(form.step(&mut config, true));
+        assert_eq!(config.theme, "dark");
+        assert!(form.is_dirty(&config));
+
+        focus(&mut form, SettingField::Compact);
+        form.activate(&mut config);
+        assert!(config.layout.compact);
+
+        focus(&mut form, SettingField::LogLevel);
+        form.step(&mut config, false);
+        assert_eq!(config.log_level, LogLevel::Warn);
+
+        focus(&mut form, SettingField::ColorMode);
+        form.activate(&mut config);
+        assert_eq!(config.color_mode, ColorMode::TrueColor);
+    }
+
+    #[test]
+    fn test_numeric_entry_is_validated() {
+        let mut form = form();
+        let mut config = Config::default();
+        focus(&mut form, SettingField::SidebarWidth);
+
+        form.activate(&mut config);
+        form.input(
+            &mut config,
+            KeyCombo::new(KeyCode::Backspace, KeyModifiers::NONE),
+        );
+        form.input(
+            &mut config,
+            KeyCombo::new(KeyCode::Backspace, KeyModifiers::NONE),
+        );
+        type_text(&mut form, &mut config, "5");
+
+        assert_eq!(
+            form.input(
+                &mut config,
+                KeyCombo::new(KeyCode::Enter, KeyModifiers::NONE)
+            ),
+            Some(false)
+        );
+        assert!(matches!(&form.error, Some((SettingField::SidebarWidth, _))));
+        assert_eq!(form.editing.as_deref(), Some("5"));
+        assert_eq!(config.layout.sidebar_width, 20);
+
+        type_text(&mut form, &mut config, "0");
+        assert_eq!(
+            form.input(
+                &mut config,
+                KeyCombo::new(KeyCode::Enter, KeyModifiers::NONE)
+            ),
+            Some(true)
+        );
+        assert_eq!(config.layout.sidebar_width, 50);
+        assert_eq!(form.error, None);
+        assert_eq!(form.editing, None);
+    }
+
+    #[test]
+    fn test_control_keys_are_not_text_input() {
+        let mut form = form();
+        let mut config = Config::default();
+        focus(&mut form, SettingField::SidebarWidth);
+        form.activate(&mut config);
+
+        let ctrl_c = KeyCombo::new(KeyCode::Char('c'), KeyModifiers::CONTROL);
+        assert_eq!(form.input(&mut config, ctrl_c), None);
+    }
+
+    #[test]
+    fn test_revert_restores_saved_config() {
+        let mut form = form();
+        let mut config = Config::default();
+        focus(&mut form, SettingField::ShowSidebar);
+        form.activate(&mut config);
+
+        form.revert(&mut config);
+        assert_eq!(config, Config::default());
+        assert_eq!(form.notice, Some(FormNotice::Reverted));
+    }
+
+    #[test]
+    fn test_theme_picker_previews_and_cancels() {
+        let mut form = form();
+        let mut config = Config::default();
+        let key = |code| KeyCombo::new(code, KeyModifiers::NONE);
+
+        assert!(!form.activate(&mut config));
+        assert!(form.capturing_input());
+
+        assert_eq!(form.input(&mut config, key(KeyCode::Down)), Some(true));
+        assert_eq!(config.theme, "dark");
+        assert_eq!(form.input(&mut config, key(KeyCode::Esc)), Some(true));
+        assert_eq!(config.theme, "light");
+        assert!(!form.capturing_input());
+
+        form.activate(&mut config);
+        form.input(&mut config, key(KeyCode::Up));
+        assert_eq!(form.input(&mut config, key(KeyCode::Enter)), Some(false));
+        assert_eq!(config.theme, "dark");
+        assert_eq!(form.picker, None);
+    }
+
+    #[test]
+    fn test_picker_follows_theme_list_changes() {
+        let mut form = form();
+        let mut config = Config::default();
+        form.activate(&mut config);
+        form.input(
+            &mut config,
+            KeyCombo::new(KeyCode::Down, KeyModifiers::NONE),
+        );
+
+        form.set_themes(vec![
+            "dark".to_string(),
+            "light".to_string(),
+            "solar".to_string(),
+        ]);
+        assert_eq!(form.picker.as_ref().map(|picker| picker.selected), Some(0));
+    }
+}
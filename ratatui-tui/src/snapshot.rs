@@ -0,0 +1,293 @@
+//! Snapshot tests: the app is driven through events at a terminal size and what it
+//! draws, text and styles, is compared with a file under `tests/snapshots`.
+//! Run with `UPDATE_SNAPSHOTS=1` to write the files afresh from what is drawn now.
+
+use std::{fs, path::PathBuf, time::Duration};
+
+use chrono::{Local, TimeZone};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+use ratatui::{backend::TestBackend, Terminal};
+
+use crate::{
+    app::App,
+    clock::FixedClock,
+    config::ColorMode,
+    event::Event,
+    keymap::{KeyCombo, Keymap},
+    metrics::{Counters, MetricsSampler, SteadySource},
+    state::AppState,
+};
+
+/// Set to write snapshots rather than compare with them
+const UPDATE_VAR: &str = "UPDATE_SNAPSHOTS";
+
+/// Readings taken before the first event, so charts have something to show
+const WARM_UP_SAMPLES: u64 = 8;
+
+fn snapshot_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("snapshots")
+}
+
+/// An app on a test terminal, with a clock and metrics that only move when told to
+pub struct Harness {
+    pub app: App,
+    terminal: Terminal<TestBackend>,
+    clock: FixedClock,
+}
+
+impl Harness {
+    pub fn new(width: u16, height: u16) -> Self {
+        let mut state = AppState::default();
+        // Colours are not quantised to whatever terminal runs the tests
+        state.config.color_mode = ColorMode::TrueColor;
+
+        let clock = FixedClock::new(Local.with_ymd_and_hms(2026, 1, 15, 9, 30, 0).unwrap());
+        let counters = Counters {
+            cpus: 4,
+            memory_total: 8 * 1024 * 1024,
+            memory_available: 5 * 1024 * 1024,
+            load: [1.5, 1.0, 0.5],
+            ..Counters::default()
+        };
+        let step = Counters {
+            cpu_busy: 30,
+            cpu_total: 100,
+            disk_read: 64 * 1024,
+            disk_written: 16 * 1024,
+            net_received: 256 * 1024,
+            net_sent: 32 * 1024,
+            ..Counters::default()
+        };
+
+        let mut app = App::with_state(Keymap::default(), state);
+        app.clock = Box::new(clock.clone());
+        app.metrics = MetricsSampler::new(Box::new(SteadySource::new(counters, step)));
+
+        let mut harness = Self {
+            app,
+            terminal: Terminal::new(TestBackend::new(width, height)).unwrap(),
+            clock,
+        };
+        harness.app.handle_event(Event::Tick);
+        for _ in 0..WARM_UP_SAMPLES {
+            harness.wait(Duration::from_secs(1));
+        }
+        harness.draw();
+        harness
+    }
+
+    /// Press keys named as in the keymap, separated by spaces, e.g. `"g g"` or `"Ctrl+C"`
+    pub fn press(&mut self, keys: &str) -> &mut Self {
+        for key in keys.split_whitespace() {
+            let combo: KeyCombo = key.parse().unwrap_or_else(|err| panic!("{}", err));
+            self.event(Event::Key(KeyEvent::new(combo.code, combo.modifiers)))
+        }
+        self
+    }
+
+    /// Type each character of `text` as a key press
+    pub fn type_text(&mut self, text: &str) -> &mut Self {
+        for c in text.chars() {
+            self.event(Event::Key(KeyEvent::new(
+                KeyCode::Char(c),
+                KeyModifiers::NONE,
+            )));
+        }
+        self
+    }
+
+    pub fn click(&mut self, column: u16, row: u16) -> &mut Self {
+        self.mouse(MouseEventKind::Down(MouseButton::Left), column, row);
+        self.mouse(MouseEventKind::Up(MouseButton::Left), column, row)
+    }
+
+    pub fn scroll(&mut self, down: bool, column: u16, row: u16) -> &mut Self {
+        let kind = if down {
+            MouseEventKind::ScrollDown
+        } else {
+            MouseEventKind::ScrollUp
+        };
+        self.mouse(kind, column, row)
+    }
+
+    fn mouse(&mut self, kind: MouseEventKind, column: u16, row: u16) -> &mut Self {
+        self.event(Event::Mouse(MouseEvent {
+            kind,
+            column,
+            row,
+            modifiers: KeyModifiers::NONE,
+        }));
+        self
+    }
+
+    pub fn resize(&mut self, width: u16, height: u16) -> &mut Self {
+        self.terminal.backend_mut().resize(width, height);
+        self.event(Event::Resize(width, height));
+        self
+    }
+
+    /// Let time pass, ticking once at the end of it
+    pub fn wait(&mut self, duration: Duration) -> &mut Self {
+        self.clock.advance(duration);
+        self.event(Event::Tick);
+        self
+    }
+
+    /// Handle an event, then draw as the event loop would, so mouse events that
+    /// follow land on what is on screen
+    fn event(&mut self, event: Event) {
+        self.app.handle_event(event);
+        self.draw();
+    }
+
+    fn draw(&mut self) {
+        let app = &mut self.app;
+        self.terminal.draw(|f| app.render(f)).unwrap();
+    }
+
+    /// The screen as text, a line per row
+    pub fn text(&self) -> String {
+        let buffer = self.terminal.backend().buffer();
+        let width = buffer.area.width as usize;
+        buffer
+            .content()
+            .chunks(width)
+            .map(|row| row.iter().map(|cell| cell.symbol()).collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Compare the screen with the snapshot called `name`, or write it when updating
+    pub fn assert_snapshot(&self, name: &str) {
+        let actual = format!("{:?}\n", self.terminal.backend().buffer());
+        let path = snapshot_dir().join(format!("{}.snap", name));
+        let pending = path.with_extension("snap.new");
+
+        if std::env::var(UPDATE_VAR).is_ok_and(|value| !value.is_empty() && value != "0") {
+            fs::create_dir_all(snapshot_dir()).unwrap();
+            fs::write(&path, actual).unwrap();
+            let _ = fs::remove_file(pending);
+            return;
+        }
+
+        let Ok(expected) = fs::read_to_string(&path) else {
+            fs::create_dir_all(snapshot_dir()).unwrap();
+            fs::write(&pending, actual).unwrap();
+            panic!(
+                "no snapshot at {}; what was drawn is in {}, run with {}=1 to accept it",
+                path.display(),
+                pending.display(),
+                UPDATE_VAR
+            );
+        };
+        if expected == actual {
+            let _ = fs::remove_file(pending);
+            return;
+        }
+
+        fs::write(&pending, &actual).unwrap();
+        let (line, (expected_line, actual_line)) = expected
+            .lines()
+            .zip(actual.lines())
+            .enumerate()
+            .find(|(_, (expected, actual))| expected != actual)
+            .unwrap_or((
+                expected.lines().count().min(actual.lines().count()),
+                ("", ""),
+            ));
+        panic!(
+            "snapshot {} differs at line {}:\n  expected: {}\n  actual:   {}\n\
+             what was drawn is in {}; run with {}=1 to accept it",
+            name,
+            line + 1,
+            expected_line,
+            actual_line,
+            pending.display(),
+            UPDATE_VAR
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Sizes every screen is checked at: a classic terminal, a roomy one and a cramped one
+    const SIZES: [(u16, u16); 3] = [(80, 24), (120, 40), (60, 16)];
+
+    fn at_each_size(name: &str, steps: impl Fn(&mut Harness)) {
+        for (width, height) in SIZES {
+            let mut harness = Harness::new(width, height);
+            steps(&mut harness);
+            harness.assert_snapshot(&format!("{}_{}x{}", name, width, height));
+        }
+    }
+
+    #[test]
+    fn test_dashboard_snapshots() {
+        at_each_size("dashboard", |_| {});
+        at_each_size("dashboard_maximised", |harness| {
+            harness.press("l l m");
+        });
+    }
+
+    #[test]
+    fn test_data_browser_snapshots() {
+        at_each_size("data", |harness| {
+            harness.press("2 j j s l");
+        });
+        at_each_size("data_filtered", |harness| {
+            harness.press("2 /").type_text("status = active");
+        });
+        at_each_size("data_quit_with_edits", |harness| {
+            harness.press("2 Enter").type_text("0").press("Enter q j");
+        });
+    }
+
+    #[test]
+    fn test_settings_snapshots() {
+        at_each_size("settings", |harness| {
+            harness.press("3 Tab j j Right");
+        });
+    }
+
+    #[test]
+    fn test_help_snapshots() {
+        at_each_size("help_searched", |harness| {
+            harness.press("4 /").type_text("scroll").press("Enter n");
+        });
+        // Below the header, the sidebar lists the views from its fourth row
+        at_each_size("help_scrolled_with_the_mouse", |harness| {
+            harness
+                .click(5, 9)
+                .scroll(true, 40, 10)
+                .scroll(true, 40, 10);
+        });
+    }
+
+    #[test]
+    fn test_command_palette_snapshots() {
+        at_each_size("palette", |harness| {
+            harness.press("Ctrl+P").type_text("sett").press("Down");
+        });
+    }
+
+    #[test]
+    fn test_snapshot_after_theme_toggle_and_resize() {
+        let mut harness = Harness::new(120, 40);
+        harness.press("t").resize(70, 20);
+        harness.assert_snapshot("light_resized_70x20");
+    }
+
+    #[test]
+    fn test_clock_and_metrics_are_fixed() {
+        let mut harness = Harness::new(120, 40);
+        assert!(harness.text().contains("09:30:08"));
+        assert!(harness.text().contains("30%"));
+
+        harness.wait(Duration::from_secs(2));
+        assert!(harness.text().contains("09:30:10"));
+    }
+}
@@ -0,0 +1,555 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use serde_json::{Map, Value as JsonValue};
+use thiserror::Error;
+
+use crate::data::{Dataset, Value};
+
+/// File extensions `open` knows how to read
+pub const EXTENSIONS: [&str; 4] = ["csv", "json", "ndjson", "jsonl"];
+
+/// Rows read between progress reports
+const PROGRESS_INTERVAL: usize = 1000;
+
+/// Errors raised while reading or writing a data source
+#[derive(Debug, Error)]
+pub enum SourceError {
+    #[error("failed to read {path}: {source}")]
+    Io { path: PathBuf, source: io::Error },
+    #[error("failed to write {path}: {source}")]
+    Write { path: PathBuf, source: io::Error },
+    #[error("unsupported file {0}: expected .csv, .json, .ndjson or .jsonl")]
+    Unsupported(PathBuf),
+    #[error("invalid CSV: {0}")]
+    Csv(#[from] csv::Error),
+    #[error("line {line} has {fields} fields but the header names {columns}")]
+    ExtraFields {
+        line: u64,
+        fields: usize,
+        columns: usize,
+    },
+    #[error("invalid JSON on line {line}: {message}")]
+    Json { line: usize, message: String },
+    #[error("expected a JSON array of objects")]
+    NotAnArray,
+    #[error("record {0} is not a JSON object")]
+    NotAnObject(usize),
+}
+
+/// Somewhere the Data Browser can load rows from
+pub trait DataSource: fmt::Debug + Send + Sync {
+    /// Short name shown while loading and under the table
+    fn name(&self) -> String;
+
+    /// Read every row, typing columns from their contents, and call `progress`
+    /// with the fraction read so far, from 0.0 to 1.0
+    fn load(&self, progress: &mut dyn FnMut(f64)) -> Result<Dataset, SourceError>;
+
+    /// Write every row back, replacing what the source held
+    fn save(&self, dataset: &Dataset) -> Result<(), SourceError>;
+}
+
+/// Where the Data Browser's rows came from, or are coming from
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum LoadStatus {
+    /// The built-in sample rows
+    #[default]
+    Sample,
+    Loading {
+        path: PathBuf,
+        progress: f64,
+    },
+    Loaded {
+        path: PathBuf,
+    },
+    /// Loading failed; the previous rows are still shown
+    Failed {
+        path: PathBuf,
+        error: String,
+    },
+}
+
+impl LoadStatus {
+    /// Name of the file, if the rows are not the sample
+    pub fn name(&self) -> Option<String> {
+        match self {
+            LoadStatus::Sample => None,
+            LoadStatus::Loading { path, .. }
+            | LoadStatus::Loaded { path }
+            | LoadStatus::Failed { path, .. } => Some(file_name(path)),
+        }
+    }
+
+    /// Whether `path` is the file being loaded, so results for any other can be dropped
+    pub fn is_loading(&self, path: &Path) -> bool {
+        matches!(self, LoadStatus::Loading { path: loading, .. } if loading == path)
+    }
+}
+
+/// The source for a file, picked by its extension
+pub fn open(path: &Path) -> Result<Box<dyn DataSource>, SourceError> {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| extension.to_ascii_lowercase());
+    let path = path.to_path_buf();
+
+    match extension.as_deref() {
+        Some("csv") => Ok(Box::new(CsvSource { path })),
+        Some("json") => Ok(Box::new(JsonSource { path })),
+        Some("ndjson" | "jsonl") => Ok(Box::new(NdjsonSource { path })),
+        _ => Err(SourceError::Unsupported(path)),
+    }
+}
+
+/// Whether `open` can read a file, judging by its name
+pub fn is_supported(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| EXTENSIONS.contains(&extension.to_ascii_lowercase().as_str()))
+}
+
+/// Comma-separated values with a header row
+#[derive(Debug, Clone)]
+pub struct CsvSource {
+    path: PathBuf,
+}
+
+impl DataSource for CsvSource {
+    fn name(&self) -> String {
+        file_name(&self.path)
+    }
+
+    fn load(&self, progress: &mut dyn FnMut(f64)) -> Result<Dataset, SourceError> {
+        let bytes = read(&self.path)?;
+        let total = bytes.len().max(1) as f64;
+        let mut reader = csv::ReaderBuilder::new()
+            .flexible(true)
+            .from_reader(bytes.as_slice());
+
+        let names: Vec<String> = reader.headers()?.iter().map(str::to_string).collect();
+        let mut rows = Vec::new();
+        for record in reader.records() {
+            let record = record?;
+            // Short rows are missing their last cells, but fields past the header have no column to go in
+            if record.len() > names.len() {
+                return Err(SourceError::ExtraFields {
+                    line: record.position().map_or(0, |position| position.line()),
+                    fields: record.len(),
+                    columns: names.len(),
+                });
+            }
+            rows.push(
+                record
+                    .iter()
+                    .map(|field| Some(JsonValue::from(field)))
+                    .collect(),
+            );
+            if rows.len() % PROGRESS_INTERVAL == 0 {
+                progress(record.position().map_or(0, |position| position.byte()) as f64 / total);
+            }
+        }
+
+        progress(1.0);
+        Ok(Dataset::infer(names, rows))
+    }
+
+    fn save(&self, dataset: &Dataset) -> Result<(), SourceError> {
+        write_atomically(&self.path, |out| {
+            let mut writer = csv::Writer::from_writer(out);
+            writer.write_record(dataset.columns.iter().map(|column| &column.name))?;
+            for row in 0..dataset.len() {
+                let fields = (0..dataset.columns.len()).map(|column| {
+                    match dataset.original(row, column) {
+                        // Unedited fields go back as they were read, "001" and all
+                        Some(JsonValue::String(text)) => text.clone(),
+                        _ => dataset.value(row, column).to_string(),
+                    }
+                });
+                writer.write_record(fields)?;
+            }
+            writer.flush()
+        })
+    }
+}
+
+/// A JSON array of objects, one per row
+#[derive(Debug, Clone)]
+pub struct JsonSource {
+    path: PathBuf,
+}
+
+impl DataSource for JsonSource {
+    fn name(&self) -> String {
+        file_name(&self.path)
+    }
+
+    fn load(&self, progress: &mut dyn FnMut(f64)) -> Result<Dataset, SourceError> {
+        let text = read_to_string(&self.path)?;
+        let value: JsonValue = serde_json::from_str(&text).map_err(json_error(0))?;
+        // Parsing is most of the work and cannot report as it goes
+        progress(0.5);
+
+        let JsonValue::Array(records) = value else {
+            return Err(SourceError::NotAnArray);
+        };
+        let total = records.len().max(1) as f64;
+        let mut objects = Vec::with_capacity(records.len());
+        for (index, record) in records.into_iter().enumerate() {
+            let JsonValue::Object(object) = record else {
+                return Err(SourceError::NotAnObject(index + 1));
+            };
+            objects.push(object);
+            if objects.len() % PROGRESS_INTERVAL == 0 {
+                progress(0.5 + 0.5 * objects.len() as f64 / total);
+            }
+        }
+
+        progress(1.0);
+        Ok(from_objects(objects))
+    }
+
+    fn save(&self, dataset: &Dataset) -> Result<(), SourceError> {
+        write_atomically(&self.path, |out| {
+            let records: Vec<JsonValue> = (0..dataset.len())
+                .map(|row| to_object(dataset, row))
+                .collect();
+            serde_json::to_writer_pretty(&mut *out, &records)?;
+            writeln!(out)
+        })
+    }
+}
+
+/// Newline-delimited JSON: one object per line
+#[derive(Debug, Clone)]
+pub struct NdjsonSource {
+    path: PathBuf,
+}
+
+impl DataSource for NdjsonSource {
+    fn name(&self) -> String {
+        file_name(&self.path)
+    }
+
+    fn load(&self, progress: &mut dyn FnMut(f64)) -> Result<Dataset, SourceError> {
+        let text = read_to_string(&self.path)?;
+        let total = text.len().max(1) as f64;
+        let mut read = 0;
+        let mut objects = Vec::new();
+
+        for (index, line) in text.lines().enumerate() {
+            read += line.len() + 1;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            match serde_json::from_str(line).map_err(json_error(index))? {
+                JsonValue::Object(object) => objects.push(object),
+                _ => return Err(SourceError::NotAnObject(index + 1)),
+            }
+            if objects.len() % PROGRESS_INTERVAL == 0 {
+                progress(read as f64 / total);
+            }
+        }
+
+        progress(1.0);
+        Ok(from_objects(objects))
+    }
+
+    fn save(&self, dataset: &Dataset) -> Result<(), SourceError> {
+        write_atomically(&self.path, |out| {
+            for row in 0..dataset.len() {
+                serde_json::to_writer(&mut *out, &to_object(dataset, row))?;
+                writeln!(out)?;
+            }
+            Ok(())
+        })
+    }
+}
+
+/// Rows from JSON objects, with a column for every key in the order keys first appear
+fn from_objects(objects: Vec<Map<String, JsonValue>>) -> Dataset {
+    let mut names: Vec<String> = Vec::new();
+    let mut columns: HashMap<String, usize> = HashMap::new();
+    for key in objects.iter().flat_map(|object| object.keys()) {
+        if !columns.contains_key(key) {
+            columns.insert(key.clone(), names.len());
+            names.push(key.clone());
+        }
+    }
+
+    let rows = objects
+        .into_iter()
+        .map(|object| {
+            let mut row = vec![None; names.len()];
+            for (key, value) in object {
+                row[columns[&key]] = Some(value);
+            }
+            row
+        })
+        .collect();
+
+    Dataset::infer(names, rows)
+}
+
+/// A row as a JSON object, the reverse of `from_objects`
+fn to_object(dataset: &Dataset, row: usize) -> JsonValue {
+    let mut object = Map::new();
+    for (column, field) in dataset.columns.iter().enumerate() {
+        let value = dataset.value(row, column);
+        let json = match (dataset.original(row, column), value) {
+            // Unedited cells go back as they were read, so "42" stays a string
+            (Some(original), _) => original.clone(),
+            // Keys an object left out stay out while the cell is empty
+            (None, Value::Null) if dataset.is_missing(row, column) => continue,
+            // Nested arrays and objects were kept as JSON text
+            (None, Value::Text(text)) if text.starts_with(['[', '{']) => {
+                serde_json::from_str(text).unwrap_or_else(|_| value.to_json())
+            }
+            (None, value) => value.to_json(),
+        };
+        object.insert(field.name.clone(), json);
+    }
+    JsonValue::Object(object)
+}
+
+/// Converts a parse error, numbering lines from `first_line` for sources read a line at a time
+fn json_error(first_line: usize) -> impl Fn(serde_json::Error) -> SourceError {
+    move |err| SourceError::Json {
+        line: first_line + err.line(),
+        message: err.to_string(),
+    }
+}
+
+fn read(path: &Path) -> Result<Vec<u8>, SourceError> {
+    fs::read(path).map_err(|source| SourceError::Io {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+fn read_to_string(path: &Path) -> Result<String, SourceError> {
+    fs::read_to_string(path).map_err(|source| SourceError::Io {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+/// Write a file by way of a temporary file beside it, so a failed write
+/// leaves the file as it was
+fn write_atomically(
+    path: &Path,
+    write: impl FnOnce(&mut dyn Write) -> io::Result<()>,
+) -> Result<(), SourceError> {
+    let temporary = path.with_file_name(format!(".{}.tmp", file_name(path)));
+    let result = File::create(&temporary)
+        .and_then(|file| {
+            let mut out = BufWriter::new(file);
+            write(&mut out)?;
+            out.into_inner().map_err(|err| err.into_error())?.sync_all()
+        })
+        .and_then(|_| fs::rename(&temporary, path));
+
+    result.map_err(|source| {
+        let _ = fs::remove_file(&temporary);
+        SourceError::Write {
+            path: path.to_path_buf(),
+            source,
+        }
+    })
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name().map_or_else(
+        || path.display().to_string(),
+        |name| name.to_string_lossy().into_owned(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        data::{ColumnKind, Value},
+        tempdir::TempDir,
+    };
+
+    fn load(name: &str, contents: &str) -> Result<Dataset, SourceError> {
+        let dir = TempDir::new();
+        let path = dir.join(name);
+        fs::write(&path, contents).unwrap();
+
+        let mut reported = Vec::new();
+        let result =
+            open(&path).and_then(|source| source.load(&mut |fraction| reported.push(fraction)));
+
+        if result.is_ok() {
+            assert_eq!(reported.last(), Some(&1.0));
+        }
+        result
+    }
+
+    fn kinds(dataset: &Dataset) -> Vec<ColumnKind> {
+        dataset.columns.iter().map(|column| column.kind).collect()
+    }
+
+    #[test]
+    fn test_csv_columns_are_typed() {
+        let dataset = load(
+            "projects.csv",
+            "name,value,started,active\n\"Alpha, Inc\",1234,2024-01-15,true\n  Beta ,,2024-03-02,false\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            kinds(&dataset),
+            vec![
+                ColumnKind::Text,
+                ColumnKind::Number,
+                ColumnKind::Date,
+                ColumnKind::Bool
+            ]
+        );
+        assert_eq!(dataset.rows[0][0], Value::Text("Alpha, Inc".to_string()));
+        assert_eq!(dataset.rows[1][0], Value::Text("  Beta ".to_string()));
+        assert_eq!(dataset.rows[1][1], Value::Null);
+
+        // A short row is missing its last cells, but a long one would lose fields
+        assert_eq!(
+            load("short.csv", "a,b\n1\n").unwrap().rows[0][1],
+            Value::Null
+        );
+        assert!(matches!(
+            load("long.csv", "a,b\n1,2\n3,4,5\n"),
+            Err(SourceError::ExtraFields {
+                line: 3,
+                fields: 3,
+                columns: 2
+            })
+        ));
+    }
+
+    #[test]
+    fn test_json_array_keeps_key_order() {
+        let dataset = load(
+            "projects.json",
+            r#"[{"name": "Alpha", "value": 10}, {"name": "Beta", "tags": ["a"], "value": null}]"#,
+        )
+        .unwrap();
+
+        let names: Vec<&str> = dataset
+            .columns
+            .iter()
+            .map(|column| column.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["name", "value", "tags"]);
+        assert_eq!(kinds(&dataset)[1], ColumnKind::Number);
+        assert_eq!(dataset.rows[0][2], Value::Null);
+        assert_eq!(dataset.rows[1][2], Value::Text("[\"a\"]".to_string()));
+    }
+
+    #[test]
+    fn test_saved_rows_read_back_the_same() {
+        let dir = TempDir::new();
+
+        // Rows that did not come from a file are written from their values, so
+        // IDs such as "001" would be read back as numbers
+        let mut dataset = Dataset::sample();
+        dataset.columns[0].kind = ColumnKind::Number;
+        for (index, row) in dataset.rows.iter_mut().enumerate() {
+            row[0] = Value::Number(index as f64 + 1.0);
+        }
+        dataset.rows[1][2] = Value::Null;
+        for name in ["projects.csv", "projects.json", "projects.ndjson"] {
+            let source = open(&dir.join(name)).unwrap();
+            source.save(&dataset).unwrap();
+            let loaded = source.load(&mut |_| {}).unwrap();
+            assert_eq!(
+                (loaded.columns, loaded.rows),
+                (dataset.columns.clone(), dataset.rows.clone()),
+                "{}",
+                name
+            );
+        }
+
+        // Nested values go back as JSON rather than as text
+        let path = dir.join("tags.ndjson");
+        fs::write(&path, "{\"tags\":[\"a\"],\"n\":1.5}\n").unwrap();
+        let source = open(&path).unwrap();
+        source.save(&source.load(&mut |_| {}).unwrap()).unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "{\"tags\":[\"a\"],\"n\":1.5}\n"
+        );
+
+        let missing = open(&dir.join("missing/out.csv")).unwrap();
+        assert!(matches!(
+            missing.save(&dataset),
+            Err(SourceError::Write { .. })
+        ));
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 4);
+    }
+
+    #[test]
+    fn test_only_edited_cells_are_rewritten() {
+        let dir = TempDir::new();
+
+        let path = dir.join("orders.csv");
+        let csv =
+            "id,price,day,paid,zip\n001,1.50,2024/01/15,TRUE,02134\n002,2,2024/02/01,FALSE, 0501\n";
+        fs::write(&path, csv).unwrap();
+        let source = open(&path).unwrap();
+        let mut dataset = source.load(&mut |_| {}).unwrap();
+        dataset.rows[1][1] = Value::Number(2.25);
+        source.save(&dataset).unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            csv.replace(",2,", ",2.25,")
+        );
+
+        // Strings stay strings, and keys left out or set to null stay that way
+        let path = dir.join("orders.json");
+        let json =
+            r#"[{"id": "001", "n": "42", "tags": ["a"]}, {"id": "002", "n": "7", "note": null}]"#;
+        fs::write(&path, json).unwrap();
+        let source = open(&path).unwrap();
+        let mut dataset = source.load(&mut |_| {}).unwrap();
+        dataset.rows[1][1] = Value::Number(8.0);
+        source.save(&dataset).unwrap();
+        let saved: JsonValue = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(
+            saved,
+            serde_json::json!([
+                {"id": "001", "n": "42", "tags": ["a"]},
+                {"id": "002", "n": 8, "note": null}
+            ])
+        );
+    }
+
+    #[test]
+    fn test_ndjson_reports_the_bad_line() {
+        let dataset = load("events.ndjson", "{\"id\": 1}\n\n{\"id\": 2}\n").unwrap();
+        assert_eq!(dataset.len(), 2);
+
+        let err = load("events.jsonl", "{\"id\": 1}\n{\"id\": \n").unwrap_err();
+        assert!(
+            matches!(err, SourceError::Json { line: 2, .. }),
+            "{:?}",
+            err
+        );
+        assert!(matches!(
+            load("list.json", "[1, 2]"),
+            Err(SourceError::NotAnObject(1))
+        ));
+        assert!(matches!(
+            load("notes.txt", ""),
+            Err(SourceError::Unsupported(_))
+        ));
+    }
+}
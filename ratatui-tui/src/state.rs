@@ -0,0 +1,1122 @@
+use std::{collections::BTreeMap, path::PathBuf};
+
+use crate::{
+    action::{Action, Effect},
+    config::{self, Config},
+    edit::{CellEditor, EditOutcome},
+    export::{ExportPrompt, PromptOutcome},
+    filter::{self, Filter, FilterBar, FilterOutcome},
+    focus::{FocusId, FocusManager},
+    keymap::KeyCombo,
+    modal::{Answer, Dialog, DialogOutcome, Reply},
+    palette::{self, CommandPalette, PaletteOutcome},
+    picker::{FilePicker, PickerOutcome},
+    scroll::TextScroll,
+    settings::{SettingField, SettingsForm},
+    source::LoadStatus,
+    styles::StyleManager,
+    table::DataTable,
+    views::ViewId,
+};
+
+/// Application state that actions operate on
+#[derive(Debug, Clone)]
+pub struct AppState {
+    pub current_view: ViewId,
+    pub should_quit: bool,
+    pub style_manager: StyleManager,
+    /// Scroll and search state of each view that is a page of text, kept while
+    /// other views are shown
+    pub scroll: BTreeMap<ViewId, TextScroll>,
+    /// Live configuration, including unsaved edits from the Settings view
+    pub config: Config,
+    pub settings: SettingsForm,
+    pub focus: FocusManager,
+    /// What the Data Browser shows
+    pub data: DataTable,
+    /// Where the Data Browser's rows come from
+    pub load: LoadStatus,
+    /// Open while a data file is being chosen
+    pub file_picker: Option<FilePicker>,
+    /// Open while a filter is being typed
+    pub filter_bar: Option<FilterBar>,
+    /// Filters applied this session, oldest first
+    pub filter_history: Vec<String>,
+    /// Open while the file to export rows to is being typed
+    pub export_prompt: Option<ExportPrompt>,
+    /// Open while a cell is being edited
+    pub cell_editor: Option<CellEditor>,
+    /// Open while a command is being picked from the palette
+    pub palette: Option<CommandPalette>,
+    /// Commands last run from the palette, most recent first
+    pub recent_commands: Vec<Action>,
+    /// Dialogs open over everything else, the last one on top and answering keys
+    pub modals: Vec<Dialog>,
+    /// Set while edits are saved on the way out, to quit once they are
+    pub quit_after_save: bool,
+    /// Index of the selected dashboard panel in the dashboard config
+    pub panel: usize,
+    /// Show the selected panel in place of the whole dashboard
+    pub maximised: bool,
+}
+
+impl Default for AppState {
+    fn default() -> Self {
+        Self::with_config(Config::default())
+    }
+}
+
+impl AppState {
+    /// State for a configuration as loaded from disk
+    pub fn with_config(config: Config) -> Self {
+        Self {
+            current_view: ViewId::Dashboard,
+            should_quit: false,
+            style_manager: StyleManager::default(),
+            scroll: [ViewId::Settings, ViewId::Help]
+                .into_iter()
+                .map(|view| (view, TextScroll::default()))
+                .collect(),
+            settings: SettingsForm::new(config.clone(), Vec::new()),
+            focus: FocusManager::default(),
+            data: DataTable::default(),
+            load: LoadStatus::default(),
+            file_picker: None,
+            filter_bar: None,
+            filter_history: Vec::new(),
+            export_prompt: None,
+            cell_editor: None,
+            palette: None,
+            recent_commands: Vec::new(),
+            modals: Vec::new(),
+            quit_after_save: false,
+            panel: 0,
+            maximised: false,
+            config,
+        }
+    }
+
+    /// Whether key presses are text for an input rather than bindings
+    pub fn capturing_input(&self) -> bool {
+        if !self.modals.is_empty() || self.palette.is_some() {
+            return true;
+        }
+        if self
+            .scroll
+            .get(&self.current_view)
+            .is_some_and(|scroll| scroll.search.is_some())
+        {
+            return true;
+        }
+        match self.current_view {
+            ViewId::Settings => self.settings.capturing_input(),
+            ViewId::Data => {
+                self.file_picker.is_some()
+                    || self.filter_bar.is_some()
+                    || self.export_prompt.is_some()
+                    || self.cell_editor.is_some()
+            }
+            ViewId::Dashboard | ViewId::Help => false,
+        }
+    }
+
+    /// What Tab cycles through in the current view
+    pub fn focus_ring(&self) -> Vec<FocusId> {
+        let children = match self.current_view {
+            ViewId::Settings => SettingField::ALL.len(),
+            ViewId::Dashboard | ViewId::Data | ViewId::Help => 0,
+        };
+        FocusManager::ring(self.config.layout.show_sidebar, children)
+    }
+
+    /// Keep focus within the ring, e.g. after the sidebar is hidden, and let the
+    /// view know which of its children is focused
+    pub fn sync_focus(&mut self) {
+        self.focus.retain(&self.focus_ring());
+        if let (ViewId::Settings, FocusId::Child(index)) = (self.current_view, self.focus.focused())
+        {
+            self.settings.set_focus(index);
+        }
+    }
+
+    /// Scroll state of the current view, if it is a page of text
+    fn text_scroll(&mut self) -> Option<&mut TextScroll> {
+        self.scroll.get_mut(&self.current_view)
+    }
+
+    /// Whether actions aimed at the view's contents, like Activate, apply
+    fn content_focused(&self, view: ViewId) -> bool {
+        self.current_view == view && self.focus.focused().in_content()
+    }
+}
+
+/// Apply an action to the state, returning the effects the shell should run
+pub fn update(state: &mut AppState, action: Action) -> Vec<Effect> {
+    match action {
+        Action::Navigate(view) => {
+            if state.current_view == view {
+                return Vec::new();
+            }
+            let from = std::mem::replace(&mut state.current_view, view);
+            let ring = state.focus_ring();
+            state.focus.switch_view(from, view, &ring);
+            state.sync_focus();
+            vec![Effect::Render]
+        }
+        // Between the built-in themes; a custom theme goes to light. The theme
+        // is loaded, and the file watched, from the config like any other change
+        Action::ToggleTheme => {
+            let name = if state.config.theme == "light" {
+                "dark"
+            } else {
+                "light"
+            };
+            state.config.theme = name.to_string();
+            vec![Effect::ApplyConfig]
+        }
+        // In the Data Browser scrolling moves the row cursor, and the view follows it
+        Action::ScrollUp if state.current_view == ViewId::Data => rendered(state.data.move_by(-1)),
+        Action::ScrollDown if state.current_view == ViewId::Data => rendered(state.data.move_by(1)),
+        Action::ScrollTop if state.current_view == ViewId::Data => rendered(state.data.go_to_top()),
+        Action::ScrollBottom if state.current_view == ViewId::Data => {
+            rendered(state.data.go_to_bottom())
+        }
+        Action::PageUp | Action::PageDown if state.current_view == ViewId::Data => {
+            rendered(state.data.page(action == Action::PageDown))
+        }
+        Action::GoToRow(position) if state.content_focused(ViewId::Data) => {
+            rendered(state.data.go_to(position))
+        }
+        Action::JumpToRow if state.content_focused(ViewId::Data) && state.data.len() > 0 => {
+            let message = format!("Row number, from 1 to {}", state.data.len());
+            let current = (state.data.cursor() + 1).to_string();
+            state.modals.push(Dialog::prompt(
+                "Jump to row",
+                message,
+                &current,
+                Reply::GoToRow,
+            ));
+            vec![Effect::Render]
+        }
+        Action::ColumnLeft | Action::ColumnRight if state.content_focused(ViewId::Data) => {
+            rendered(state.data.move_column(action == Action::ColumnRight))
+        }
+        Action::ToggleSort if state.content_focused(ViewId::Data) => {
+            state.data.toggle_sort();
+            vec![Effect::Render]
+        }
+        Action::HideColumn if state.content_focused(ViewId::Data) => {
+            rendered(state.data.hide_column())
+        }
+        Action::ShowAllColumns if state.content_focused(ViewId::Data) => {
+            rendered(state.data.show_all_columns())
+        }
+        Action::ToggleMark if state.content_focused(ViewId::Data) => {
+            rendered(state.data.toggle_mark())
+        }
+        Action::ToggleMarkAll if state.content_focused(ViewId::Data) => {
+            rendered(state.data.toggle_mark_all())
+        }
+        Action::ToggleDetail if state.content_focused(ViewId::Data) => {
+            state.data.show_detail = !state.data.show_detail;
+            state.data.show_changes = false;
+            vec![Effect::Render]
+        }
+        Action::ToggleChanges if state.content_focused(ViewId::Data) => {
+            state.data.show_changes = !state.data.show_changes;
+            state.data.show_detail = false;
+            vec![Effect::Render]
+        }
+        Action::NextPanel | Action::PrevPanel if state.content_focused(ViewId::Dashboard) => {
+            let dashboard = &state.config.dashboard;
+            if dashboard.panels.is_empty() {
+                return Vec::new();
+            }
+            state.panel = dashboard.next_panel(state.panel, action == Action::NextPanel);
+            vec![Effect::Render]
+        }
+        // Rearranging changes the live config, so the layout is saved with the settings
+        Action::MovePanel(side) if state.content_focused(ViewId::Dashboard) => {
+            rendered(state.config.dashboard.move_panel(state.panel, side))
+        }
+        Action::ResizePanel(side) if state.content_focused(ViewId::Dashboard) => {
+            rendered(state.config.dashboard.resize_panel(state.panel, side))
+        }
+        Action::MaximisePanel if state.content_focused(ViewId::Dashboard) => {
+            if state.panel >= state.config.dashboard.panels.len() {
+                return Vec::new();
+            }
+            state.maximised = !state.maximised;
+            vec![Effect::Render]
+        }
+        Action::EditCell if state.content_focused(ViewId::Data) => {
+            let Some(row) = state.data.current_row() else {
+                return Vec::new();
+            };
+            let column = state.data.column();
+            state.cell_editor = Some(CellEditor::new(row, column, state.data.value(row, column)));
+            vec![Effect::Render]
+        }
+        Action::UndoEdit if state.content_focused(ViewId::Data) => rendered(state.data.undo()),
+        Action::SaveData if state.content_focused(ViewId::Data) && state.data.has_changes() => {
+            vec![Effect::SaveData]
+        }
+        Action::OpenFile if state.content_focused(ViewId::Data) && state.data.has_changes() => {
+            let message = format!(
+                "{} rows have unsaved changes, which opening another file drops.",
+                state.data.edited_rows()
+            );
+            state.modals.push(Dialog::confirm(
+                "Open another file?",
+                message,
+                "Open",
+                Reply::OpenFile,
+            ));
+            vec![Effect::Render]
+        }
+        Action::OpenFile if state.content_focused(ViewId::Data) => open_file_picker(state),
+        Action::Filter if state.content_focused(ViewId::Data) => {
+            state.filter_bar = Some(FilterBar::new(state.data.filter().cloned()));
+            vec![Effect::Render]
+        }
+        Action::Export if state.content_focused(ViewId::Data) => {
+            state.export_prompt = Some(ExportPrompt::new(state.load.name().as_deref()));
+            vec![Effect::Render]
+        }
+        // Pages of text scroll within what was last drawn of them
+        Action::ScrollUp
+        | Action::ScrollDown
+        | Action::ScrollTop
+        | Action::ScrollBottom
+        | Action::PageUp
+        | Action::PageDown => {
+            let Some(scroll) = state.text_scroll() else {
+                return Vec::new();
+            };
+            rendered(match action {
+                Action::ScrollUp => scroll.scroll_by(-1),
+                Action::ScrollDown => scroll.scroll_by(1),
+                Action::ScrollTop => scroll.scroll_top(),
+                Action::ScrollBottom => scroll.scroll_bottom(),
+                _ => scroll.page(action == Action::PageDown),
+            })
+        }
+        Action::Search => {
+            let Some(scroll) = state.text_scroll() else {
+                return Vec::new();
+            };
+            scroll.open_search();
+            vec![Effect::Render]
+        }
+        Action::NextMatch | Action::PrevMatch => {
+            let forward = action == Action::NextMatch;
+            rendered(
+                state
+                    .text_scroll()
+                    .is_some_and(|scroll| scroll.next_match(forward)),
+            )
+        }
+        Action::FocusNext | Action::FocusPrev => {
+            let ring = state.focus_ring();
+            if action == Action::FocusNext {
+                state.focus.focus_next(&ring);
+            } else {
+                state.focus.focus_prev(&ring);
+            }
+            state.sync_focus();
+            vec![Effect::Render]
+        }
+        Action::Activate if state.content_focused(ViewId::Settings) => {
+            config_changed(state.settings.activate(&mut state.config))
+        }
+        Action::Increase | Action::Decrease if state.content_focused(ViewId::Settings) => {
+            let forward = action == Action::Increase;
+            config_changed(state.settings.step(&mut state.config, forward))
+        }
+        Action::ChangeSetting(field) => {
+            let mut effects = update(state, Action::Navigate(ViewId::Settings));
+            state.focus.focus(FocusId::Child(field.index()));
+            state.sync_focus();
+            effects.extend(update(state, Action::Activate));
+            effects
+        }
+        // Dialogs keep the keyboard until they close
+        Action::OpenPalette if !state.modals.is_empty() => Vec::new(),
+        Action::OpenPalette => {
+            state.palette = match state.palette {
+                Some(_) => None,
+                None => Some(CommandPalette::new(&state.recent_commands)),
+            };
+            vec![Effect::Render]
+        }
+        Action::SaveConfig => vec![Effect::SaveConfig],
+        Action::SaveLayout => vec![Effect::SaveLayout],
+        Action::RevertConfig => {
+            state.settings.revert(&mut state.config);
+            vec![Effect::ApplyConfig]
+        }
+        Action::Activate | Action::Increase | Action::Decrease => Vec::new(),
+        // Table actions outside the Data Browser
+        Action::GoToRow(_)
+        | Action::JumpToRow
+        | Action::ColumnLeft
+        | Action::ColumnRight
+        | Action::ToggleSort
+        | Action::HideColumn
+        | Action::ShowAllColumns
+        | Action::ToggleMark
+        | Action::ToggleMarkAll
+        | Action::ToggleDetail
+        | Action::OpenFile
+        | Action::Filter
+        | Action::Export
+        | Action::EditCell
+        | Action::UndoEdit
+        | Action::SaveData
+        | Action::ToggleChanges => Vec::new(),
+        // Panel actions outside the dashboard
+        Action::NextPanel
+        | Action::PrevPanel
+        | Action::MovePanel(_)
+        | Action::ResizePanel(_)
+        | Action::MaximisePanel => Vec::new(),
+        Action::Suspend => vec![Effect::Suspend],
+        // Asked already, e.g. Ctrl+C pressed while the question is open
+        Action::Quit
+            if state
+                .modals
+                .iter()
+                .any(|dialog| dialog.reply == Reply::Quit) =>
+        {
+            Vec::new()
+        }
+        Action::Quit if state.data.has_changes() => {
+            let message = format!("{} rows have unsaved changes.", state.data.edited_rows());
+            let options = ["Save and quit", "Quit without saving", "Cancel"];
+            state
+                .modals
+                .push(Dialog::choice("Quit?", message, &options, Reply::Quit));
+            vec![Effect::Render]
+        }
+        Action::Quit => {
+            state.should_quit = true;
+            Vec::new()
+        }
+    }
+}
+
+/// Focus the sidebar or the current view, e.g. after a click
+pub fn focus(state: &mut AppState, target: FocusId) -> Vec<Effect> {
+    let before = state.focus.focused();
+    if target.in_content() {
+        let ring = state.focus_ring();
+        state.focus.focus_content(&ring);
+    } else {
+        state.focus.focus(target);
+    }
+    state.sync_focus();
+
+    if state.focus.focused() == before {
+        Vec::new()
+    } else {
+        vec![Effect::Render]
+    }
+}
+
+/// Resize the sidebar to `width` columns, within the allowed range, as an unsaved setting
+pub fn resize_sidebar(state: &mut AppState, width: u16) -> Vec<Effect> {
+    let width = width.clamp(
+        *config::SIDEBAR_WIDTH_RANGE.start(),
+        *config::SIDEBAR_WIDTH_RANGE.end(),
+    );
+    if width == state.config.layout.sidebar_width {
+        return Vec::new();
+    }
+    state.config.layout.sidebar_width = width;
+    vec![Effect::ApplyConfig]
+}
+
+/// Apply a key press to whatever is capturing text input; `None` if nothing consumed it
+pub fn input(state: &mut AppState, key: KeyCombo) -> Option<Vec<Effect>> {
+    if !state.capturing_input() {
+        return None;
+    }
+
+    if let Some(dialog) = state.modals.last_mut() {
+        let effects = match dialog.input(key)? {
+            DialogOutcome::Edited => vec![Effect::Render],
+            DialogOutcome::Unchanged => Vec::new(),
+            DialogOutcome::Close(answer) => {
+                let dialog = state.modals.pop().expect("a dialog is open");
+                let mut effects = vec![Effect::Render];
+                effects.extend(answer_dialog(state, dialog, answer));
+                effects
+            }
+        };
+        return Some(effects);
+    }
+
+    if let Some(palette) = &mut state.palette {
+        let effects = match palette.input(key, &state.recent_commands)? {
+            PaletteOutcome::Edited => vec![Effect::Render],
+            PaletteOutcome::Unchanged => Vec::new(),
+            PaletteOutcome::Run(action) => {
+                state.palette = None;
+                palette::remember(&mut state.recent_commands, action);
+                let mut effects = run_command(state, action);
+                effects.push(Effect::SaveHistory);
+                effects
+            }
+            PaletteOutcome::Cancel => {
+                state.palette = None;
+                vec![Effect::Render]
+            }
+        };
+        return Some(effects);
+    }
+
+    if let Some(picker) = &mut state.file_picker {
+        let effects = match picker.input(key)? {
+            PickerOutcome::Moved => vec![Effect::Render],
+            PickerOutcome::Enter(dir) => vec![Effect::ListDirectory(dir)],
+            PickerOutcome::Choose(path) => {
+                state.file_picker = None;
+                vec![Effect::LoadData(path)]
+            }
+            PickerOutcome::Cancel => {
+                state.file_picker = None;
+                vec![Effect::Render]
+            }
+        };
+        return Some(effects);
+    }
+
+    if let Some(prompt) = &mut state.export_prompt {
+        let effects = match prompt.input(key)? {
+            PromptOutcome::Edited => vec![Effect::Render],
+            PromptOutcome::Unchanged => Vec::new(),
+            PromptOutcome::Submit(target) => {
+                let format = prompt.format;
+                state.export_prompt = None;
+                vec![Effect::Export(target, format)]
+            }
+            PromptOutcome::Cancel => {
+                state.export_prompt = None;
+                vec![Effect::Render]
+            }
+        };
+        return Some(effects);
+    }
+
+    if let Some(editor) = &mut state.cell_editor {
+        match editor.input(key)? {
+            EditOutcome::Edited => {}
+            EditOutcome::Unchanged => return Some(Vec::new()),
+            // Text that does not fit the column stays open to be fixed
+            EditOutcome::Commit => match editor.value(&state.data.dataset().columns[editor.column])
+            {
+                Ok(value) => {
+                    state.data.set_value(editor.row, editor.column, value);
+                    state.cell_editor = None;
+                }
+                Err(err) => editor.error = Some(err.to_string()),
+            },
+            EditOutcome::Cancel => state.cell_editor = None,
+        }
+        return Some(vec![Effect::Render]);
+    }
+
+    if let Some(bar) = &mut state.filter_bar {
+        match bar.input(key, &state.filter_history)? {
+            FilterOutcome::Edited => {
+                // Filter as you type, keeping the last filter that parsed while the text does not
+                match Filter::parse(&bar.text, &state.data.dataset().columns) {
+                    Ok(filter) => {
+                        bar.error = None;
+                        state.data.set_filter(filter);
+                    }
+                    Err(err) => bar.error = Some(err.to_string()),
+                }
+            }
+            FilterOutcome::Unchanged => return Some(Vec::new()),
+            // A filter that does not parse stays open to be fixed
+            FilterOutcome::Apply if bar.error.is_some() => return Some(Vec::new()),
+            FilterOutcome::Apply => {
+                filter::remember(&mut state.filter_history, &bar.text);
+                state.filter_bar = None;
+            }
+            FilterOutcome::Cancel => {
+                state.data.set_filter(bar.previous.take());
+                state.filter_bar = None;
+            }
+        }
+        return Some(vec![Effect::Render]);
+    }
+
+    if let Some(scroll) = state
+        .scroll
+        .get_mut(&state.current_view)
+        .filter(|scroll| scroll.search.is_some())
+    {
+        return Some(rendered(scroll.input(key)?));
+    }
+
+    let changed = state.settings.input(&mut state.config, key)?;
+    Some(config_changed(changed))
+}
+
+/// Carry on from a dialog with its answer
+fn answer_dialog(state: &mut AppState, dialog: Dialog, answer: Answer) -> Vec<Effect> {
+    match (dialog.reply, answer) {
+        (_, Answer::Dismissed) | (Reply::Acknowledge, _) => Vec::new(),
+        (Reply::Quit, Answer::Chosen(0)) => {
+            state.quit_after_save = true;
+            vec![Effect::SaveData]
+        }
+        (Reply::Quit, Answer::Chosen(1)) => {
+            state.should_quit = true;
+            Vec::new()
+        }
+        (Reply::OpenFile, Answer::Confirmed) => open_file_picker(state),
+        (Reply::GoToRow, Answer::Text(text)) => {
+            let rows = state.data.len();
+            match text.trim().parse::<usize>() {
+                Ok(row @ 1..) if row <= rows => {
+                    state.data.go_to(row - 1);
+                    Vec::new()
+                }
+                // Asked again, so the number can be fixed
+                _ => {
+                    let error = format!("Not a row number from 1 to {}", rows);
+                    state.modals.push(dialog.with_error(error));
+                    Vec::new()
+                }
+            }
+        }
+        (Reply::Quit | Reply::OpenFile | Reply::GoToRow, _) => Vec::new(),
+    }
+}
+
+/// Open the file picker, starting next to the file last opened
+fn open_file_picker(state: &mut AppState) -> Vec<Effect> {
+    let dir = match &state.load {
+        LoadStatus::Loading { path, .. }
+        | LoadStatus::Loaded { path }
+        | LoadStatus::Failed { path, .. } => path.parent().map(PathBuf::from).unwrap_or_default(),
+        LoadStatus::Sample => PathBuf::new(),
+    };
+    let dir = if dir.as_os_str().is_empty() {
+        PathBuf::from(".")
+    } else {
+        dir
+    };
+    state.file_picker = Some(FilePicker::new(dir.clone()));
+    vec![Effect::ListDirectory(dir)]
+}
+
+/// Carry out a command picked from the palette, first going to the view it works on
+fn run_command(state: &mut AppState, action: Action) -> Vec<Effect> {
+    // The palette closing needs a redraw, even if the command changes nothing
+    let mut effects = vec![Effect::Render];
+    if let Some(view) = action.view() {
+        effects.extend(update(state, Action::Navigate(view)));
+        effects.extend(focus(state, FocusId::Content));
+    }
+    effects.extend(update(state, action));
+    effects
+}
+
+/// Redraw only if something changed
+fn rendered(changed: bool) -> Vec<Effect> {
+    if changed {
+        vec![Effect::Render]
+    } else {
+        Vec::new()
+    }
+}
+
+fn config_changed(changed: bool) -> Vec<Effect> {
+    if changed {
+        vec![Effect::ApplyConfig]
+    } else {
+        vec![Effect::Render]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        dashboard::{PanelKind, Side},
+        export::{ExportFormat, ExportTarget},
+        modal::DialogKind,
+        picker::FileEntry,
+    };
+    use crossterm::event::{KeyCode, KeyModifiers};
+
+    #[test]
+    fn test_navigate_changes_view() {
+        let mut state = AppState::default();
+        let effects = update(&mut state, Action::Navigate(ViewId::Help));
+
+        assert_eq!(state.current_view, ViewId::Help);
+        assert_eq!(effects, vec![Effect::Render]);
+    }
+
+    #[test]
+    fn test_navigate_to_current_view_is_noop() {
+        let mut state = AppState::default();
+        let effects = update(&mut state, Action::Navigate(ViewId::Dashboard));
+
+        assert!(effects.is_empty());
+    }
+
+    #[test]
+    fn test_quit_sets_flag() {
+        let mut state = AppState::default();
+        update(&mut state, Action::Quit);
+
+        assert!(state.should_quit);
+    }
+
+    #[test]
+    fn test_toggle_theme() {
+        let mut state = AppState::default();
+        assert_eq!(
+            update(&mut state, Action::ToggleTheme),
+            vec![Effect::ApplyConfig]
+        );
+        assert_eq!(state.config.theme, "dark");
+        update(&mut state, Action::ToggleTheme);
+        assert_eq!(state.config.theme, "light");
+
+        // A custom theme is swapped for a built-in one rather than kept
+        state.config.theme = "solarized-dark".to_string();
+        update(&mut state, Action::ToggleTheme);
+        assert_eq!(state.config.theme, "light");
+    }
+
+    #[test]
+    fn test_settings_actions_edit_config() {
+        let mut state = AppState::default();
+        assert!(update(&mut state, Action::Activate).is_empty());
+
+        update(&mut state, Action::Navigate(ViewId::Settings));
+        update(&mut state, Action::FocusNext);
+        update(&mut state, Action::FocusNext);
+        update(&mut state, Action::Activate);
+        assert!(state.capturing_input());
+
+        let backspace = KeyCombo::new(KeyCode::Backspace, KeyModifiers::NONE);
+        let enter = KeyCombo::new(KeyCode::Enter, KeyModifiers::NONE);
+        for key in [
+            backspace,
+            backspace,
+            KeyCombo::new(KeyCode::Char('3'), KeyModifiers::NONE),
+        ] {
+            assert_eq!(input(&mut state, key), Some(vec![Effect::Render]));
+        }
+        assert_eq!(input(&mut state, enter), Some(vec![Effect::Render]));
+        assert!(state.settings.error.is_some());
+
+        input(
+            &mut state,
+            KeyCombo::new(KeyCode::Char('0'), KeyModifiers::NONE),
+        );
+        assert_eq!(input(&mut state, enter), Some(vec![Effect::ApplyConfig]));
+        assert_eq!(state.config.layout.sidebar_width, 30);
+        assert!(!state.capturing_input());
+    }
+
+    #[test]
+    fn test_revert_and_save() {
+        let mut state = AppState::default();
+        state.config.layout.compact = true;
+
+        assert_eq!(
+            update(&mut state, Action::SaveConfig),
+            vec![Effect::SaveConfig]
+        );
+        assert_eq!(
+            update(&mut state, Action::RevertConfig),
+            vec![Effect::ApplyConfig]
+        );
+        assert!(!state.config.layout.compact);
+    }
+
+    #[test]
+    fn test_scroll_stays_in_the_text_and_is_kept_per_view() {
+        let mut state = AppState::default();
+        assert!(update(&mut state, Action::ScrollDown).is_empty());
+
+        update(&mut state, Action::Navigate(ViewId::Help));
+        state.text_scroll().unwrap().lay_out(30, 10, &[], None);
+        assert!(update(&mut state, Action::ScrollUp).is_empty());
+        update(&mut state, Action::ScrollDown);
+        update(&mut state, Action::PageDown);
+        assert_eq!(state.scroll[&ViewId::Help].offset, 10);
+        assert_eq!(
+            update(&mut state, Action::ScrollBottom),
+            vec![Effect::Render]
+        );
+        assert!(update(&mut state, Action::ScrollDown).is_empty());
+
+        update(&mut state, Action::Navigate(ViewId::Settings));
+        assert_eq!(state.scroll[&ViewId::Settings].offset, 0);
+        update(&mut state, Action::Navigate(ViewId::Help));
+        assert_eq!(state.scroll[&ViewId::Help].offset, 20);
+    }
+
+    #[test]
+    fn test_search_bar_captures_keys_until_closed() {
+        let mut state = AppState::default();
+        assert!(update(&mut state, Action::Search).is_empty());
+
+        update(&mut state, Action::Navigate(ViewId::Help));
+        assert_eq!(update(&mut state, Action::Search), vec![Effect::Render]);
+        assert!(state.capturing_input());
+
+        // While searching 'q' is part of the query rather than quitting
+        let key = |code| KeyCombo::new(code, KeyModifiers::NONE);
+        assert_eq!(
+            input(&mut state, key(KeyCode::Char('q'))),
+            Some(vec![Effect::Render])
+        );
+        assert_eq!(
+            input(&mut state, key(KeyCode::Enter)),
+            Some(vec![Effect::Render])
+        );
+        assert!(!state.capturing_input());
+        assert_eq!(state.scroll[&ViewId::Help].query, "q");
+
+        // Matches are counted when drawn; until then there is nothing to move between
+        assert!(update(&mut state, Action::NextMatch).is_empty());
+        state.scroll.get_mut(&ViewId::Help).unwrap().matches = 2;
+        assert_eq!(update(&mut state, Action::PrevMatch), vec![Effect::Render]);
+    }
+
+    #[test]
+    fn test_palette_runs_commands_in_the_view_they_work_on() {
+        let mut state = AppState::default();
+        let key = |code| KeyCombo::new(code, KeyModifiers::NONE);
+        update(&mut state, Action::FocusPrev);
+        assert_eq!(state.focus.focused(), FocusId::Sidebar);
+
+        assert_eq!(
+            update(&mut state, Action::OpenPalette),
+            vec![Effect::Render]
+        );
+        assert!(state.capturing_input());
+        for c in "export".chars() {
+            input(&mut state, key(KeyCode::Char(c)));
+        }
+        let effects = input(&mut state, key(KeyCode::Enter)).unwrap();
+        assert_eq!(effects.last(), Some(&Effect::SaveHistory));
+        assert!(state.palette.is_none());
+        assert_eq!(state.current_view, ViewId::Data);
+        assert!(state.export_prompt.is_some());
+        assert_eq!(state.recent_commands, vec![Action::Export]);
+
+        // Opening the palette again closes it
+        update(&mut state, Action::OpenPalette);
+        assert_eq!(
+            update(&mut state, Action::OpenPalette),
+            vec![Effect::Render]
+        );
+        assert!(state.palette.is_none());
+    }
+
+    #[test]
+    fn test_change_setting_goes_to_the_field() {
+        let mut state = AppState::default();
+        let effects = update(&mut state, Action::ChangeSetting(SettingField::Compact));
+
+        assert_eq!(state.current_view, ViewId::Settings);
+        assert_eq!(state.settings.focused(), SettingField::Compact);
+        assert_eq!(effects.last(), Some(&Effect::ApplyConfig));
+        assert!(state.config.layout.compact);
+
+        // Numbers are typed, so the field is left open for them
+        update(
+            &mut state,
+            Action::ChangeSetting(SettingField::SidebarWidth),
+        );
+        assert_eq!(state.settings.focused(), SettingField::SidebarWidth);
+        assert!(state.settings.editing.is_some());
+    }
+
+    #[test]
+    fn test_data_actions_move_the_table() {
+        let mut state = AppState::default();
+        assert!(update(&mut state, Action::ToggleSort).is_empty());
+        assert!(update(&mut state, Action::ScrollBottom).is_empty());
+
+        update(&mut state, Action::Navigate(ViewId::Data));
+        assert_eq!(
+            update(&mut state, Action::ScrollBottom),
+            vec![Effect::Render]
+        );
+        assert_eq!(state.data.cursor(), 4);
+        assert!(state.scroll.values().all(|scroll| scroll.offset == 0));
+        assert!(update(&mut state, Action::ScrollDown).is_empty());
+
+        update(&mut state, Action::ToggleMark);
+        update(&mut state, Action::ToggleSort);
+        assert!(state.data.sort().is_some());
+        assert_eq!(state.data.marked_count(), 1);
+
+        // With the sidebar focused only scrolling reaches the table
+        update(&mut state, Action::FocusPrev);
+        assert!(update(&mut state, Action::ToggleDetail).is_empty());
+        assert_eq!(update(&mut state, Action::ScrollTop), vec![Effect::Render]);
+    }
+
+    #[test]
+    fn test_file_picker_captures_keys_until_a_file_is_chosen() {
+        let mut state = AppState::default();
+        update(&mut state, Action::Navigate(ViewId::Data));
+        assert_eq!(
+            update(&mut state, Action::OpenFile),
+            vec![Effect::ListDirectory(PathBuf::from("."))]
+        );
+        assert!(state.capturing_input());
+
+        // While the picker is open 'q' closes it rather than quitting
+        let key = |code| KeyCombo::new(code, KeyModifiers::NONE);
+        assert_eq!(
+            input(&mut state, key(KeyCode::Char('q'))),
+            Some(vec![Effect::Render])
+        );
+        assert!(!state.capturing_input());
+        assert!(!state.should_quit);
+
+        let file = PathBuf::from("/tmp/sales.csv");
+        update(&mut state, Action::OpenFile);
+        let entry = FileEntry {
+            name: "sales.csv".to_string(),
+            path: file.clone(),
+            is_dir: false,
+        };
+        if let Some(picker) = &mut state.file_picker {
+            picker.set_listing(Ok((PathBuf::from("/tmp"), vec![entry])));
+        }
+        assert_eq!(
+            input(&mut state, key(KeyCode::Enter)),
+            Some(vec![Effect::LoadData(file)])
+        );
+        assert!(state.file_picker.is_none());
+    }
+
+    #[test]
+    fn test_filter_bar_filters_as_you_type() {
+        let mut state = AppState::default();
+        update(&mut state, Action::Navigate(ViewId::Data));
+        update(&mut state, Action::Filter);
+        assert!(state.capturing_input());
+
+        let key = |code| KeyCombo::new(code, KeyModifiers::NONE);
+        let type_text = |state: &mut AppState, text: &str| {
+            for c in text.chars() {
+                input(state, key(KeyCode::Char(c)));
+            }
+        };
+        type_text(&mut state, "value > 5000");
+        assert_eq!(state.data.len(), 2);
+
+        // Text that does not parse keeps the last filter and cannot be applied
+        type_text(&mut state, "x");
+        assert!(state.filter_bar.as_ref().unwrap().error.is_some());
+        assert_eq!(state.data.len(), 2);
+        assert_eq!(input(&mut state, key(KeyCode::Enter)), Some(Vec::new()));
+
+        input(&mut state, key(KeyCode::Backspace));
+        assert_eq!(
+            input(&mut state, key(KeyCode::Enter)),
+            Some(vec![Effect::Render])
+        );
+        assert!(!state.capturing_input());
+        assert_eq!(state.filter_history, vec!["value > 5000"]);
+
+        // Esc puts back the filter in force when the bar opened
+        update(&mut state, Action::Filter);
+        assert_eq!(state.filter_bar.as_ref().unwrap().text, "value > 5000");
+        input(&mut state, key(KeyCode::Backspace));
+        assert_eq!(state.data.len(), 4);
+        input(&mut state, key(KeyCode::Esc));
+        assert_eq!(state.data.len(), 2);
+        assert!(!state.should_quit);
+    }
+
+    #[test]
+    fn test_dashboard_panels_are_selected_moved_and_maximised() {
+        let mut state = AppState::default();
+        assert_eq!(update(&mut state, Action::PrevPanel), vec![Effect::Render]);
+        assert_eq!(
+            state.config.dashboard.panels[state.panel].kind,
+            PanelKind::Clock
+        );
+
+        // Nothing is to the right of the clock; below it there is no room to grow
+        assert!(update(&mut state, Action::MovePanel(Side::Right)).is_empty());
+        assert!(update(&mut state, Action::ResizePanel(Side::Down)).is_empty());
+        assert_eq!(
+            update(&mut state, Action::MovePanel(Side::Left)),
+            vec![Effect::Render]
+        );
+        assert_eq!(state.config.dashboard.panels[state.panel].column, 2);
+        assert!(state.settings.is_dirty(&state.config));
+
+        update(&mut state, Action::MaximisePanel);
+        assert!(state.maximised);
+
+        // Panel keys do nothing away from the dashboard
+        update(&mut state, Action::Navigate(ViewId::Help));
+        assert!(update(&mut state, Action::NextPanel).is_empty());
+        assert!(update(&mut state, Action::MaximisePanel).is_empty());
+    }
+
+    #[test]
+    fn test_cells_are_edited_and_undone() {
+        let mut state = AppState::default();
+        update(&mut state, Action::Navigate(ViewId::Data));
+        update(&mut state, Action::ColumnRight);
+        update(&mut state, Action::ColumnRight);
+        update(&mut state, Action::ColumnRight);
+        update(&mut state, Action::EditCell);
+        assert!(state.capturing_input());
+
+        let key = |code| KeyCombo::new(code, KeyModifiers::NONE);
+        input(&mut state, key(KeyCode::Char('x')));
+        input(&mut state, key(KeyCode::Enter));
+        assert!(state.cell_editor.as_ref().unwrap().error.is_some());
+        assert!(!state.data.has_changes());
+
+        input(&mut state, key(KeyCode::Backspace));
+        input(&mut state, key(KeyCode::Char('5')));
+        input(&mut state, key(KeyCode::Enter));
+        assert!(state.cell_editor.is_none());
+        assert_eq!(state.data.value(0, 3), &crate::data::Value::Number(12345.0));
+        assert!(state.data.is_edited(0));
+
+        assert_eq!(update(&mut state, Action::UndoEdit), vec![Effect::Render]);
+        assert!(!state.data.has_changes());
+        assert!(update(&mut state, Action::SaveData).is_empty());
+    }
+
+    #[test]
+    fn test_quitting_with_unsaved_edits_asks_what_to_do() {
+        let mut state = AppState::default();
+        update(&mut state, Action::Navigate(ViewId::Data));
+        update(&mut state, Action::EditCell);
+        let key = |code| KeyCombo::new(code, KeyModifiers::NONE);
+        input(&mut state, key(KeyCode::Char('0')));
+        input(&mut state, key(KeyCode::Enter));
+
+        assert_eq!(update(&mut state, Action::Quit), vec![Effect::Render]);
+        assert!(!state.should_quit);
+        assert_eq!(
+            state.modals.last().map(|dialog| dialog.reply),
+            Some(Reply::Quit)
+        );
+        assert!(state.capturing_input());
+        // Keys go to the dialog rather than the keymap, and Esc stays
+        assert_eq!(input(&mut state, key(KeyCode::Char('q'))), Some(Vec::new()));
+        // Ctrl keys still reach the keymap, and quitting again does not ask twice
+        let ctrl = |c| KeyCombo::new(KeyCode::Char(c), KeyModifiers::CONTROL);
+        assert_eq!(input(&mut state, ctrl('z')), None);
+        assert_eq!(update(&mut state, Action::Quit), Vec::new());
+        assert_eq!(state.modals.len(), 1);
+        input(&mut state, key(KeyCode::Esc));
+        assert!(state.modals.is_empty() && !state.should_quit);
+
+        // Saving first quits once the save is done
+        update(&mut state, Action::Quit);
+        assert_eq!(
+            input(&mut state, key(KeyCode::Enter)),
+            Some(vec![Effect::Render, Effect::SaveData])
+        );
+        assert!(state.quit_after_save && !state.should_quit);
+
+        update(&mut state, Action::Quit);
+        input(&mut state, key(KeyCode::Char('2')));
+        assert!(state.should_quit);
+    }
+
+    #[test]
+    fn test_opening_a_file_over_edits_is_confirmed() {
+        let mut state = AppState::default();
+        update(&mut state, Action::Navigate(ViewId::Data));
+        update(&mut state, Action::EditCell);
+        let key = |code| KeyCombo::new(code, KeyModifiers::NONE);
+        input(&mut state, key(KeyCode::Char('0')));
+        input(&mut state, key(KeyCode::Enter));
+
+        update(&mut state, Action::OpenFile);
+        input(&mut state, key(KeyCode::Enter));
+        assert!(state.modals.is_empty() && state.file_picker.is_none());
+
+        update(&mut state, Action::OpenFile);
+        let effects = input(&mut state, key(KeyCode::Char('y'))).unwrap();
+        assert!(matches!(
+            effects[..],
+            [Effect::Render, Effect::ListDirectory(_)]
+        ));
+        assert!(state.file_picker.is_some());
+    }
+
+    #[test]
+    fn test_jumping_to_a_row_asks_until_the_number_fits() {
+        let mut state = AppState::default();
+        update(&mut state, Action::Navigate(ViewId::Data));
+        let key = |code| KeyCombo::new(code, KeyModifiers::NONE);
+        let rows = state.data.len();
+
+        update(&mut state, Action::JumpToRow);
+        input(&mut state, key(KeyCode::Backspace));
+        input(&mut state, key(KeyCode::Char('0')));
+        input(&mut state, key(KeyCode::Enter));
+        let Some(Dialog {
+            kind: DialogKind::Prompt { error, .. },
+            ..
+        }) = state.modals.last()
+        else {
+            panic!("the prompt should stay open");
+        };
+        assert_eq!(
+            error.as_deref(),
+            Some(format!("Not a row number from 1 to {}", rows).as_str())
+        );
+
+        input(&mut state, key(KeyCode::Backspace));
+        input(&mut state, key(KeyCode::Char('3')));
+        input(&mut state, key(KeyCode::Enter));
+        assert!(state.modals.is_empty());
+        assert_eq!(state.data.cursor(), 2);
+    }
+
+    #[test]
+    fn test_export_prompt_asks_for_a_file() {
+        let mut state = AppState::default();
+        update(&mut state, Action::Navigate(ViewId::Data));
+        update(&mut state, Action::Export);
+        assert!(state.capturing_input());
+
+        let key = |code| KeyCombo::new(code, KeyModifiers::NONE);
+        input(&mut state, key(KeyCode::Tab));
+        assert_eq!(
+            input(&mut state, key(KeyCode::Enter)),
+            Some(vec![Effect::Export(
+                ExportTarget::File(PathBuf::from("export.json")),
+                ExportFormat::Json
+            )])
+        );
+        assert!(state.export_prompt.is_none());
+
+        update(&mut state, Action::Export);
+        assert_eq!(
+            input(&mut state, key(KeyCode::Esc)),
+            Some(vec![Effect::Render])
+        );
+        assert!(!state.capturing_input());
+    }
+}
@@ -1,10 +1,43 @@
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
 use ratatui::{
     style::{Color, Modifier, Style},
     text::Span,
 };
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::colors::ColorDepth;
+
+/// Directory under the config dir that holds theme files
+pub const THEMES_DIR: &str = "themes";
+
+/// Names of the themes compiled into the binary
+pub const BUILTIN_THEMES: [&str; 2] = ["light", "dark"];
+
+/// Errors raised while loading a theme
+#[derive(Debug, Error)]
+pub enum ThemeError {
+    #[error("failed to read theme {path}: {source}")]
+    Io { path: PathBuf, source: io::Error },
+    #[error("invalid theme file: {0}")]
+    Parse(#[from] toml::de::Error),
+    #[error("invalid theme file {path}: {source}")]
+    Invalid {
+        path: PathBuf,
+        source: toml::de::Error,
+    },
+    #[error("unknown theme '{0}'; expected light, dark, a theme name or a file path")]
+    NotFound(String),
+}
 
 /// Theme configuration for the application
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct Theme {
     pub background: Color,
     pub foreground: Color,
@@ -14,6 +47,68 @@ pub struct Theme {
     pub warning: Color,
     pub error: Color,
     pub info: Color,
+    /// Adjustments to individual element styles, on top of those derived from the colours above
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub styles: BTreeMap<StyleType, StyleOverride>,
+}
+
+/// Replacement colours and modifiers for one `StyleType`; unset fields keep the derived style
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StyleOverride {
+    pub fg: Option<Color>,
+    pub bg: Option<Color>,
+    /// Replaces the derived modifiers; an empty list removes them
+    pub modifiers: Option<Vec<ModifierName>>,
+}
+
+/// Text modifiers as written in theme files
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModifierName {
+    Bold,
+    Dim,
+    Italic,
+    Underlined,
+    SlowBlink,
+    RapidBlink,
+    Reversed,
+    Hidden,
+    CrossedOut,
+}
+
+impl ModifierName {
+    pub fn modifier(&self) -> Modifier {
+        match self {
+            ModifierName::Bold => Modifier::BOLD,
+            ModifierName::Dim => Modifier::DIM,
+            ModifierName::Italic => Modifier::ITALIC,
+            ModifierName::Underlined => Modifier::UNDERLINED,
+            ModifierName::SlowBlink => Modifier::SLOW_BLINK,
+            ModifierName::RapidBlink => Modifier::RAPID_BLINK,
+            ModifierName::Reversed => Modifier::REVERSED,
+            ModifierName::Hidden => Modifier::HIDDEN,
+            ModifierName::CrossedOut => Modifier::CROSSED_OUT,
+        }
+    }
+}
+
+impl StyleOverride {
+    fn apply(&self, mut style: Style) -> Style {
+        if let Some(fg) = self.fg {
+            style.fg = Some(fg);
+        }
+        if let Some(bg) = self.bg {
+            style.bg = Some(bg);
+        }
+        if let Some(modifiers) = &self.modifiers {
+            style.add_modifier = modifiers
+                .iter()
+                .fold(Modifier::empty(), |all, name| all | name.modifier());
+            style.sub_modifier = Modifier::empty();
+        }
+        style
+    }
 }
 
 impl Default for Theme {
@@ -34,6 +129,7 @@ impl Theme {
             warning: Color::Rgb(255, 193, 7),
             error: Color::Rgb(220, 53, 69),
             info: Color::Rgb(23, 162, 184),
+            styles: BTreeMap::new(),
         }
     }
 
@@ -48,12 +144,101 @@ impl Theme {
             warning: Color::Rgb(255, 193, 7),
             error: Color::Rgb(220, 53, 69),
             info: Color::Rgb(23, 162, 184),
+            styles: BTreeMap::new(),
+        }
+    }
+
+    /// Look up a built-in theme by name
+    pub fn builtin(name: &str) -> Option<Self> {
+        match name {
+            "light" => Some(Self::light()),
+            "dark" => Some(Self::dark()),
+            _ => None,
+        }
+    }
+
+    /// Parse a theme written as TOML with one colour per slot, e.g. `primary = "#007acc"`,
+    /// and optional `[styles.<style_type>]` tables of overrides
+    pub fn from_toml(source: &str) -> Result<Self, ThemeError> {
+        Ok(toml::from_str(source)?)
+    }
+
+    /// Load a theme file
+    pub fn load_file(path: &Path) -> Result<Self, ThemeError> {
+        let source = fs::read_to_string(path).map_err(|source| ThemeError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        Self::from_toml(&source).map_err(|err| match err {
+            ThemeError::Parse(source) => ThemeError::Invalid {
+                path: path.to_path_buf(),
+                source,
+            },
+            err => err,
+        })
+    }
+
+    /// Resolve a built-in name, a theme in `themes_dir`, or a path to a theme file
+    pub fn resolve(spec: &str, themes_dir: Option<&Path>) -> Result<Self, ThemeError> {
+        match Self::locate(spec, themes_dir)? {
+            Some(path) => Self::load_file(&path),
+            None => Ok(Self::builtin(spec).expect("located theme is built in")),
+        }
+    }
+
+    /// The file a theme is loaded from, or `None` for a built-in theme
+    pub fn locate(spec: &str, themes_dir: Option<&Path>) -> Result<Option<PathBuf>, ThemeError> {
+        if Self::builtin(spec).is_some() {
+            return Ok(None);
+        }
+
+        let installed = themes_dir.and_then(|dir| {
+            theme_files(dir)
+                .into_iter()
+                .find(|(name, _)| name == spec)
+                .map(|(_, path)| path)
+        });
+        match installed {
+            Some(path) => Ok(Some(path)),
+            None if Path::new(spec).is_file() => Ok(Some(PathBuf::from(spec))),
+            None => Err(ThemeError::NotFound(spec.to_string())),
         }
     }
 }
 
+/// Built-in and installed theme names
+pub fn theme_names(themes_dir: Option<&Path>) -> Vec<String> {
+    let installed = themes_dir.map(theme_files).unwrap_or_default();
+
+    BUILTIN_THEMES
+        .iter()
+        .map(|name| name.to_string())
+        .chain(installed.into_iter().map(|(name, _)| name))
+        .collect()
+}
+
+/// Theme files in a directory as `(name, path)` pairs sorted by name
+pub fn theme_files(dir: &Path) -> Vec<(String, PathBuf)> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut themes: Vec<(String, PathBuf)> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+        .filter_map(|path| {
+            let name = path.file_stem()?.to_str()?.to_string();
+            Some((name, path))
+        })
+        .collect();
+    themes.sort();
+    themes
+}
+
 /// Style types for different UI elements
-#[derive(Debug, Clone, PartialEq)]
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum StyleType {
     Header,
     Sidebar,
@@ -73,12 +258,16 @@ pub enum StyleType {
     Info,
     Border,
     BorderFocus,
+    /// Text matched by a filter
+    Highlight,
 }
 
 /// Style manager for the application
 #[derive(Debug, Clone)]
 pub struct StyleManager {
     theme: Theme,
+    /// Colours are reduced to what the terminal can show when styles are handed out
+    depth: ColorDepth,
 }
 
 impl Default for StyleManager {
@@ -90,11 +279,50 @@ impl Default for StyleManager {
 impl StyleManager {
     /// Create a new style manager with the given theme
     pub fn new(theme: Theme) -> Self {
-        Self { theme }
+        Self {
+            theme,
+            depth: ColorDepth::TrueColor,
+        }
     }
 
-    /// Get a style for the given style type
+    /// Get a style for the given style type, including any override from the theme
     pub fn get_style(&self, style_type: StyleType) -> Style {
+        let style = self.base_style(style_type);
+        let style = match self.theme.styles.get(&style_type) {
+            Some(style_override) => style_override.apply(style),
+            None => style,
+        };
+        self.downgrade(style)
+    }
+
+    /// Fit a style's colours to the colour depth
+    ///
+    /// Without colour, elements drawn on a background of their own (header,
+    /// sidebar, buttons) are shown in reverse video so they still stand out.
+    fn downgrade(&self, mut style: Style) -> Style {
+        match self.depth {
+            ColorDepth::TrueColor => style,
+            ColorDepth::Mono => {
+                if style
+                    .bg
+                    .is_some_and(|bg| bg != self.theme.background && bg != Color::Reset)
+                {
+                    style = style.add_modifier(Modifier::REVERSED);
+                }
+                style.fg = style.fg.map(|_| Color::Reset);
+                style.bg = style.bg.map(|_| Color::Reset);
+                style
+            }
+            depth => {
+                style.fg = style.fg.map(|color| depth.quantize(color));
+                style.bg = style.bg.map(|color| depth.quantize(color));
+                style
+            }
+        }
+    }
+
+    /// The style derived from the theme colours alone
+    fn base_style(&self, style_type: StyleType) -> Style {
         match style_type {
             StyleType::Header => Style::default()
                 .fg(self.theme.foreground)
@@ -129,10 +357,8 @@ impl StyleManager {
             StyleType::Subtitle => Style::default()
                 .fg(self.theme.secondary)
                 .add_modifier(Modifier::BOLD),
-            StyleType::Text => Style::default()
-                .fg(self.theme.foreground),
-            StyleType::MutedText => Style::default()
-                .fg(self.theme.secondary),
+            StyleType::Text => Style::default().fg(self.theme.foreground),
+            StyleType::MutedText => Style::default().fg(self.theme.secondary),
             StyleType::Error => Style::default()
                 .fg(self.theme.error)
                 .add_modifier(Modifier::BOLD),
@@ -145,10 +371,11 @@ impl StyleManager {
             StyleType::Info => Style::default()
                 .fg(self.theme.info)
                 .add_modifier(Modifier::BOLD),
-            StyleType::Border => Style::default()
-                .fg(self.theme.secondary),
-            StyleType::BorderFocus => Style::default()
-                .fg(self.theme.primary),
+            StyleType::Border => Style::default().fg(self.theme.secondary),
+            StyleType::BorderFocus => Style::default().fg(self.theme.primary),
+            StyleType::Highlight => Style::default()
+                .fg(self.theme.warning)
+                .add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
         }
     }
 
@@ -158,6 +385,7 @@ impl StyleManager {
     }
 
     /// Get the current theme
+    #[cfg(test)]
     pub fn theme(&self) -> &Theme {
         &self.theme
     }
@@ -167,13 +395,12 @@ impl StyleManager {
         self.theme = theme;
     }
 
-    /// Toggle between light and dark theme
-    pub fn toggle_theme(&mut self) {
-        self.theme = if self.theme == Theme::light() {
-            Theme::dark()
-        } else {
-            Theme::light()
-        };
+    pub fn color_depth(&self) -> ColorDepth {
+        self.depth
+    }
+
+    pub fn set_color_depth(&mut self, depth: ColorDepth) {
+        self.depth = depth;
     }
 }
 
@@ -209,14 +436,96 @@ mod tests {
     }
 
     #[test]
-    fn test_theme_toggle() {
-        let mut style_manager = StyleManager::new(Theme::light());
-        assert_eq!(style_manager.theme(), &Theme::light());
+    fn test_theme_from_toml() {
+        let source = r##"
+            background = "#000000"
+            foreground = "white"
+            primary = "#ff8800"
+            secondary = "gray"
+            success = "green"
+            warning = "yellow"
+            error = "red"
+            info = "cyan"
+        "##;
+        let theme = Theme::from_toml(source).unwrap();
+
+        assert_eq!(theme.primary, Color::Rgb(255, 136, 0));
+        assert_eq!(theme.foreground, Color::White);
+        assert!(Theme::from_toml("primary = \"#ff8800\"").is_err());
+    }
+
+    #[test]
+    fn test_resolve_builtin_themes() {
+        assert_eq!(Theme::resolve("dark", None).unwrap(), Theme::dark());
+        assert!(matches!(
+            Theme::resolve("no-such-theme", None),
+            Err(ThemeError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_style_overrides() {
+        let source = r##"
+            background = "#000000"
+            foreground = "white"
+            primary = "blue"
+            secondary = "gray"
+            success = "green"
+            warning = "yellow"
+            error = "red"
+            info = "cyan"
+
+            [styles.header]
+            fg = "#102030"
+            modifiers = ["italic", "underlined"]
+
+            [styles.error]
+            bg = "black"
+        "##;
+        let manager = StyleManager::new(Theme::from_toml(source).unwrap());
+
+        let header = manager.get_style(StyleType::Header);
+        assert_eq!(header.fg, Some(Color::Rgb(16, 32, 48)));
+        assert_eq!(header.bg, Some(Color::Blue));
+        assert_eq!(header.add_modifier, Modifier::ITALIC | Modifier::UNDERLINED);
+
+        let error = manager.get_style(StyleType::Error);
+        assert_eq!(error.fg, Some(Color::Red));
+        assert_eq!(error.bg, Some(Color::Black));
+
+        let unknown = source.replace("[styles.error]", "[styles.nonsense]");
+        assert!(Theme::from_toml(&unknown).is_err());
+    }
+
+    #[test]
+    fn test_sample_theme_parses() {
+        let theme = Theme::from_toml(include_str!("../themes/solarized-dark.toml")).unwrap();
+        assert_eq!(theme.background, Color::Rgb(0, 43, 54));
+        assert!(theme.styles.contains_key(&StyleType::Header));
+    }
+
+    #[test]
+    fn test_styles_follow_color_depth() {
+        let mut style_manager = StyleManager::new(Theme::dark());
+
+        style_manager.set_color_depth(ColorDepth::Ansi256);
+        let content = style_manager.get_style(StyleType::Content);
+        assert_eq!(content.bg, Some(Color::Indexed(234)));
 
-        style_manager.toggle_theme();
-        assert_eq!(style_manager.theme(), &Theme::dark());
+        style_manager.set_color_depth(ColorDepth::Ansi16);
+        let error = style_manager.get_style(StyleType::Error);
+        assert_eq!(error.fg, Some(Color::Red));
 
-        style_manager.toggle_theme();
-        assert_eq!(style_manager.theme(), &Theme::light());
+        style_manager.set_color_depth(ColorDepth::Mono);
+        let header = style_manager.get_style(StyleType::Header);
+        assert_eq!(
+            (header.fg, header.bg),
+            (Some(Color::Reset), Some(Color::Reset))
+        );
+        assert!(header
+            .add_modifier
+            .contains(Modifier::BOLD | Modifier::REVERSED));
+        let content = style_manager.get_style(StyleType::Content);
+        assert!(!content.add_modifier.contains(Modifier::REVERSED));
     }
 }
@@ -0,0 +1,589 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::Arc,
+};
+
+use crate::{
+    data::{Dataset, Value},
+    filter::Filter,
+};
+
+/// Rows moved by PageUp/PageDown until the view reports how many it shows
+const DEFAULT_PAGE_SIZE: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortDirection {
+    Ascending,
+    Descending,
+}
+
+impl SortDirection {
+    pub fn indicator(&self) -> &'static str {
+        match self {
+            SortDirection::Ascending => "▲",
+            SortDirection::Descending => "▼",
+        }
+    }
+}
+
+/// A change to one cell, kept so it can be undone
+#[derive(Debug, Clone, PartialEq)]
+struct Edit {
+    row: usize,
+    column: usize,
+    /// The cell's pending value before the change, if it had one
+    previous: Option<Value>,
+}
+
+/// Browsing state of a dataset: row and column cursors, filter, sort order,
+/// hidden columns, marked rows and unsaved edits
+///
+/// Rows are referred to in two ways: a position is where a row is shown,
+/// among the rows passing the filter in sorted order, and an index is where
+/// it is in the dataset. Marks and edits are kept by index so they survive
+/// re-sorting and filtering.
+///
+/// Edits are held over the dataset rather than written into it, so the
+/// dataset can stay shared and cloning the table stays cheap.
+#[derive(Debug, Clone)]
+pub struct DataTable {
+    dataset: Arc<Dataset>,
+    /// Dataset indexes in display order
+    order: Vec<usize>,
+    cursor: usize,
+    column: usize,
+    sort: Option<(usize, SortDirection)>,
+    filter: Option<Filter>,
+    hidden: BTreeSet<usize>,
+    marked: BTreeSet<usize>,
+    /// Pending values by dataset index and column
+    edits: BTreeMap<(usize, usize), Value>,
+    undo: Vec<Edit>,
+    pub show_detail: bool,
+    pub show_changes: bool,
+    page_size: usize,
+}
+
+impl Default for DataTable {
+    fn default() -> Self {
+        Self::new(Dataset::sample())
+    }
+}
+
+impl DataTable {
+    pub fn new(dataset: impl Into<Arc<Dataset>>) -> Self {
+        let dataset = dataset.into();
+        Self {
+            order: (0..dataset.len()).collect(),
+            dataset,
+            cursor: 0,
+            column: 0,
+            sort: None,
+            filter: None,
+            hidden: BTreeSet::new(),
+            marked: BTreeSet::new(),
+            edits: BTreeMap::new(),
+            undo: Vec::new(),
+            show_detail: false,
+            show_changes: false,
+            page_size: DEFAULT_PAGE_SIZE,
+        }
+    }
+
+    pub fn dataset(&self) -> &Dataset {
+        &self.dataset
+    }
+
+    /// A cell by dataset index, with any pending edit applied
+    pub fn value(&self, row: usize, column: usize) -> &Value {
+        self.edits
+            .get(&(row, column))
+            .unwrap_or_else(|| self.dataset.value(row, column))
+    }
+
+    /// Number of rows shown
+    pub fn len(&self) -> usize {
+        self.order.len()
+    }
+
+    /// Position of the row under the cursor
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// Dataset index of the row shown at a position
+    pub fn row_index(&self, position: usize) -> Option<usize> {
+        self.order.get(position).copied()
+    }
+
+    /// Dataset index of the row under the cursor
+    pub fn current_row(&self) -> Option<usize> {
+        self.row_index(self.cursor)
+    }
+
+    /// The column under the column cursor
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    pub fn sort(&self) -> Option<(usize, SortDirection)> {
+        self.sort
+    }
+
+    pub fn filter(&self) -> Option<&Filter> {
+        self.filter.as_ref()
+    }
+
+    /// Columns that are shown, in dataset order
+    pub fn visible_columns(&self) -> Vec<usize> {
+        (0..self.dataset.columns.len())
+            .filter(|column| !self.hidden.contains(column))
+            .collect()
+    }
+
+    pub fn hidden_count(&self) -> usize {
+        self.hidden.len()
+    }
+
+    pub fn is_marked(&self, index: usize) -> bool {
+        self.marked.contains(&index)
+    }
+
+    pub fn marked_count(&self) -> usize {
+        self.marked.len()
+    }
+
+    /// Dataset indexes of the marked rows that are shown, or of every row
+    /// shown if none of them are marked, in display order
+    pub fn selection(&self) -> Vec<usize> {
+        let marked: Vec<usize> = self
+            .order
+            .iter()
+            .copied()
+            .filter(|index| self.marked.contains(index))
+            .collect();
+        if marked.is_empty() {
+            self.order.clone()
+        } else {
+            marked
+        }
+    }
+
+    /// Rows moved by a page, as last reported by the view
+    pub fn set_page_size(&mut self, rows: usize) {
+        self.page_size = rows.max(1);
+    }
+
+    /// Move the cursor by `delta` rows, stopping at either end; whether it moved
+    pub fn move_by(&mut self, delta: isize) -> bool {
+        let last = self.len().saturating_sub(1) as isize;
+        let target = (self.cursor as isize + delta).clamp(0, last.max(0));
+        self.go_to(target as usize)
+    }
+
+    pub fn page(&mut self, forward: bool) -> bool {
+        let delta = self.page_size as isize;
+        self.move_by(if forward { delta } else { -delta })
+    }
+
+    pub fn go_to_top(&mut self) -> bool {
+        self.go_to(0)
+    }
+
+    pub fn go_to_bottom(&mut self) -> bool {
+        self.go_to(self.len().saturating_sub(1))
+    }
+
+    /// Put the cursor on a row by position; whether it moved
+    pub fn go_to(&mut self, position: usize) -> bool {
+        if position >= self.len() || position == self.cursor {
+            return false;
+        }
+        self.cursor = position;
+        true
+    }
+
+    /// Move the column cursor to the next shown column either side; whether it moved
+    pub fn move_column(&mut self, forward: bool) -> bool {
+        let visible = self.visible_columns();
+        let next = if forward {
+            visible.iter().find(|column| **column > self.column)
+        } else {
+            visible.iter().rev().find(|column| **column < self.column)
+        };
+
+        match next {
+            Some(column) => {
+                self.column = *column;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Sort by the column under the cursor: ascending, then descending, then unsorted
+    ///
+    /// The cursor stays on the same row wherever it ends up.
+    pub fn toggle_sort(&mut self) {
+        self.sort = match self.sort {
+            Some((column, SortDirection::Ascending)) if column == self.column => {
+                Some((column, SortDirection::Descending))
+            }
+            Some((column, SortDirection::Descending)) if column == self.column => None,
+            _ => Some((self.column, SortDirection::Ascending)),
+        };
+        self.apply_order();
+    }
+
+    /// Show only the rows a filter matches, or every row for `None`
+    ///
+    /// The cursor stays on the same row if it still matches, and otherwise
+    /// goes to the first row that does.
+    pub fn set_filter(&mut self, filter: Option<Filter>) {
+        if self.filter != filter {
+            self.filter = filter;
+            self.apply_order();
+        }
+    }
+
+    /// Rebuild the display order: the rows passing the filter, sorted
+    fn apply_order(&mut self) {
+        let current = self.current_row();
+
+        let rows = 0..self.dataset.len();
+        let mut order: Vec<usize> = match &self.filter {
+            Some(filter) => rows.filter(|row| filter.matches(self, *row)).collect(),
+            None => rows.collect(),
+        };
+        if let Some((column, direction)) = self.sort {
+            // Stable, so equal values keep their dataset order in both directions
+            order.sort_by(|a, b| {
+                let ordering = self.value(*a, column).compare(self.value(*b, column));
+                match direction {
+                    SortDirection::Ascending => ordering,
+                    SortDirection::Descending => ordering.reverse(),
+                }
+            });
+        }
+        self.order = order;
+
+        self.cursor = current
+            .and_then(|index| self.order.iter().position(|row| *row == index))
+            .unwrap_or(0);
+    }
+
+    /// Change a cell by dataset index; whether its value changed
+    ///
+    /// Setting a cell back to its saved value drops the edit. The row may
+    /// move, or leave the rows shown, if it is sorted or filtered on.
+    pub fn set_value(&mut self, row: usize, column: usize, value: Value) -> bool {
+        if *self.value(row, column) == value {
+            return false;
+        }
+        let previous = if *self.dataset.value(row, column) == value {
+            self.edits.remove(&(row, column))
+        } else {
+            self.edits.insert((row, column), value)
+        };
+        self.undo.push(Edit {
+            row,
+            column,
+            previous,
+        });
+        self.apply_order();
+        true
+    }
+
+    /// Take back the last change, putting the cursor on its cell if it is shown;
+    /// whether there was one
+    pub fn undo(&mut self) -> bool {
+        let Some(edit) = self.undo.pop() else {
+            return false;
+        };
+        let saved = self.dataset.value(edit.row, edit.column);
+        match edit.previous.filter(|value| value != saved) {
+            Some(value) => self.edits.insert((edit.row, edit.column), value),
+            // Also when the value before was saved in the meantime
+            None => self.edits.remove(&(edit.row, edit.column)),
+        };
+        self.apply_order();
+
+        if let Some(position) = self.order.iter().position(|row| *row == edit.row) {
+            self.cursor = position;
+            if !self.hidden.contains(&edit.column) {
+                self.column = edit.column;
+            }
+        }
+        true
+    }
+
+    /// Whether there are edits that have not been saved
+    pub fn has_changes(&self) -> bool {
+        !self.edits.is_empty()
+    }
+
+    /// Whether a row, by dataset index, has unsaved edits
+    pub fn is_edited(&self, row: usize) -> bool {
+        self.edits.range((row, 0)..(row + 1, 0)).next().is_some()
+    }
+
+    /// Number of rows with unsaved edits
+    pub fn edited_rows(&self) -> usize {
+        let mut rows: Vec<usize> = self.edits.keys().map(|(row, _)| *row).collect();
+        rows.dedup();
+        rows.len()
+    }
+
+    /// Unsaved edits as dataset index, column and new value, in dataset order
+    pub fn edits(&self) -> impl Iterator<Item = (usize, usize, &Value)> {
+        self.edits
+            .iter()
+            .map(|((row, column), value)| (*row, *column, value))
+    }
+
+    /// The dataset with every pending edit applied, for saving
+    pub fn edited_dataset(&self) -> Dataset {
+        let mut dataset = Dataset::clone(&self.dataset);
+        for ((row, column), value) in &self.edits {
+            dataset.rows[*row][*column] = value.clone();
+        }
+        dataset
+    }
+
+    /// Take a saved copy of the dataset in place of the current one
+    ///
+    /// Edits it already holds are dropped, so any made while it was being
+    /// saved stay pending. Saved edits can no longer be undone.
+    pub fn set_saved(&mut self, dataset: Arc<Dataset>) {
+        self.edits
+            .retain(|(row, column), value| dataset.value(*row, *column) != value);
+        self.dataset = dataset;
+        let edits = &self.edits;
+        self.undo
+            .retain(|edit| edits.contains_key(&(edit.row, edit.column)));
+        self.apply_order();
+    }
+
+    /// Hide the column under the cursor, unless it is the last one shown; whether it was hidden
+    pub fn hide_column(&mut self) -> bool {
+        if self.visible_columns().len() <= 1 {
+            return false;
+        }
+        self.hidden.insert(self.column);
+        if !self.move_column(true) {
+            self.move_column(false);
+        }
+        true
+    }
+
+    /// Show every hidden column; whether any were hidden
+    pub fn show_all_columns(&mut self) -> bool {
+        if self.hidden.is_empty() {
+            return false;
+        }
+        self.hidden.clear();
+        true
+    }
+
+    /// Mark or unmark the row under the cursor and move on to the next one
+    pub fn toggle_mark(&mut self) -> bool {
+        let Some(index) = self.current_row() else {
+            return false;
+        };
+        if !self.marked.remove(&index) {
+            self.marked.insert(index);
+        }
+        self.move_by(1);
+        true
+    }
+
+    /// Mark every row shown, or unmark them if they are all marked already
+    pub fn toggle_mark_all(&mut self) -> bool {
+        if self.len() == 0 {
+            return false;
+        }
+        if self.order.iter().all(|index| self.marked.contains(index)) {
+            for index in &self.order {
+                self.marked.remove(index);
+            }
+        } else {
+            self.marked.extend(self.order.iter().copied());
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{Column, ColumnKind, Value};
+
+    fn names(table: &DataTable) -> Vec<String> {
+        (0..table.len())
+            .map(|position| {
+                let index = table.row_index(position).unwrap();
+                table.value(index, 1).to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_sort_cycles_and_cursor_follows_row() {
+        let mut table = DataTable::default();
+        // "Project Beta", with the second highest value
+        table.go_to(1);
+        table.move_column(true);
+        table.move_column(true);
+        table.move_column(true);
+        assert_eq!(table.column(), 3);
+
+        table.toggle_sort();
+        assert_eq!(table.sort(), Some((3, SortDirection::Ascending)));
+        assert_eq!(names(&table)[0], "Project Epsilon");
+        assert_eq!(table.current_row(), Some(1));
+        assert_eq!(table.cursor(), 3);
+
+        table.toggle_sort();
+        assert_eq!(names(&table)[0], "Project Gamma");
+        assert_eq!(table.cursor(), 1);
+
+        table.toggle_sort();
+        assert_eq!(table.sort(), None);
+        assert_eq!(names(&table)[0], "Project Alpha");
+        assert_eq!(table.current_row(), Some(1));
+    }
+
+    #[test]
+    fn test_cursor_stops_at_ends_and_pages() {
+        let rows = (0..100).map(|i| vec![Value::Number(i as f64)]).collect();
+        let mut table = DataTable::new(Dataset {
+            columns: vec![Column::new("n", ColumnKind::Number)],
+            rows,
+            originals: Vec::new(),
+        });
+        table.set_page_size(30);
+
+        assert!(!table.move_by(-1));
+        assert!(table.page(true));
+        assert_eq!(table.cursor(), 30);
+        table.page(true);
+        table.page(true);
+        table.page(true);
+        assert_eq!(table.cursor(), 99);
+        assert!(!table.move_by(1));
+        assert!(table.go_to_top());
+        assert!(!table.go_to(100));
+    }
+
+    #[test]
+    fn test_hidden_columns_are_skipped() {
+        let mut table = DataTable::default();
+        table.move_column(true);
+        assert!(table.hide_column());
+        assert_eq!(table.column(), 2);
+        assert_eq!(table.visible_columns(), vec![0, 2, 3, 4]);
+
+        table.move_column(false);
+        assert_eq!(table.column(), 0);
+
+        // The last column left cannot be hidden
+        for _ in 0..4 {
+            table.hide_column();
+        }
+        assert_eq!(table.visible_columns().len(), 1);
+        assert!(table.show_all_columns());
+        assert_eq!(table.hidden_count(), 0);
+    }
+
+    #[test]
+    fn test_marks_survive_sorting() {
+        let mut table = DataTable::default();
+        assert!(table.toggle_mark());
+        assert_eq!(table.cursor(), 1);
+        assert!(table.is_marked(0));
+
+        table.move_column(true);
+        table.toggle_sort();
+        table.toggle_sort();
+        assert!(table.is_marked(0));
+        assert_eq!(table.marked_count(), 1);
+
+        table.toggle_mark_all();
+        assert_eq!(table.marked_count(), 5);
+        table.toggle_mark_all();
+        assert_eq!(table.marked_count(), 0);
+    }
+
+    #[test]
+    fn test_edits_are_undone_and_saved() {
+        let mut table = DataTable::default();
+        table.move_column(true);
+        table.toggle_sort();
+        // "Project Beta" sorts after "Project Alpha"
+        assert!(table.set_value(1, 1, Value::Text("Project Zeta".into())));
+        assert!(!table.set_value(1, 1, Value::Text("Project Zeta".into())));
+        assert_eq!(names(&table)[4], "Project Zeta");
+        assert!(table.is_edited(1));
+        assert!(!table.is_edited(0));
+
+        table.set_value(1, 3, Value::Number(1.0));
+        table.set_value(2, 3, Value::Number(2.0));
+        assert_eq!(table.edited_rows(), 2);
+
+        // Setting a cell back to its saved value is not a change
+        table.set_value(2, 3, Value::Number(9012.0));
+        assert!(!table.is_edited(2));
+
+        assert!(table.undo());
+        assert_eq!(table.value(2, 3), &Value::Number(2.0));
+        assert_eq!(table.current_row(), Some(2));
+        assert_eq!(table.column(), 3);
+
+        let saved = table.edited_dataset();
+        assert_eq!(saved.value(1, 1), &Value::Text("Project Zeta".into()));
+        assert_eq!(
+            table.dataset().value(1, 1),
+            &Value::Text("Project Beta".into())
+        );
+
+        // An edit made while saving stays pending
+        table.set_value(0, 3, Value::Number(3.0));
+        table.set_saved(Arc::new(saved));
+        assert_eq!(
+            table
+                .edits()
+                .map(|(row, column, _)| (row, column))
+                .collect::<Vec<_>>(),
+            vec![(0, 3)]
+        );
+        assert_eq!(names(&table)[4], "Project Zeta");
+        assert!(table.undo());
+        assert!(!table.has_changes());
+        assert!(!table.undo());
+    }
+
+    #[test]
+    fn test_filter_narrows_sorted_rows() {
+        let mut table = DataTable::default();
+        let filter = |text| Filter::parse(text, &Dataset::sample().columns).unwrap();
+        // "Project Delta"
+        table.go_to(3);
+        table.move_column(true);
+        table.toggle_sort();
+
+        table.set_filter(filter("status = active"));
+        assert_eq!(names(&table), vec!["Project Alpha", "Project Delta"]);
+        assert_eq!(table.current_row(), Some(3));
+        assert_eq!(table.dataset().len(), 5);
+
+        // Only the rows shown are marked
+        table.toggle_mark_all();
+        table.set_filter(None);
+        assert_eq!(table.marked_count(), 2);
+        assert_eq!(table.len(), 5);
+
+        table.set_filter(filter("value > 9000"));
+        assert_eq!(names(&table), vec!["Project Gamma"]);
+        assert_eq!(table.cursor(), 0);
+    }
+}
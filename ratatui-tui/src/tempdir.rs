@@ -0,0 +1,62 @@
+//! A scratch directory for tests that touch the disk, removed when dropped so
+//! it goes even when an assertion fails first.
+
+use std::{
+    fs,
+    ops::Deref,
+    path::{Path, PathBuf},
+};
+
+#[derive(Debug)]
+pub struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    /// Create an empty directory under the system's temporary directory
+    pub fn new() -> Self {
+        let path = std::env::temp_dir().join(format!("ratatui-tui-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&path).unwrap();
+        Self { path }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl AsRef<Path> for TempDir {
+    fn as_ref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_directory_is_removed_on_drop() {
+        let dir = TempDir::new();
+        fs::write(dir.join("file"), "").unwrap();
+        let path = dir.path().to_path_buf();
+        assert!(path.is_dir());
+
+        drop(dir);
+        assert!(!path.exists());
+    }
+}
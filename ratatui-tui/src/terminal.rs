@@ -0,0 +1,168 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, IsTerminal, Stdout, Write},
+    ops::{Deref, DerefMut},
+    panic,
+};
+
+use anyhow::Result;
+use crossterm::{
+    cursor::Show,
+    event::{DisableMouseCapture, EnableMouseCapture},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use log::error;
+use ratatui::{backend::CrosstermBackend, Terminal};
+
+use crate::record::{Recorded, Recorder};
+
+/// The terminal type the application draws to
+pub type Tui = Terminal<CrosstermBackend<Output>>;
+
+/// Where the interface is drawn: stdout, or the controlling terminal when
+/// stdout is redirected, so exported rows can be piped elsewhere
+pub enum Output {
+    Stdout(Stdout),
+    Tty(File),
+    /// Either of the above, copied into a recording
+    Recorded(Box<Recorded<Output>>),
+}
+
+impl Output {
+    pub fn open() -> Self {
+        let stdout = io::stdout();
+        if stdout.is_terminal() {
+            return Output::Stdout(stdout);
+        }
+        match OpenOptions::new().write(true).open("/dev/tty") {
+            Ok(tty) => Output::Tty(tty),
+            Err(_) => Output::Stdout(stdout),
+        }
+    }
+
+    /// Keep a copy of everything written in `recorder`'s recording
+    pub fn recorded(self, recorder: Recorder) -> Self {
+        Output::Recorded(Box::new(Recorded::new(self, recorder)))
+    }
+}
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Output::Stdout(stdout) => stdout.write(buf),
+            Output::Tty(tty) => tty.write(buf),
+            Output::Recorded(output) => output.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Output::Stdout(stdout) => stdout.flush(),
+            Output::Tty(tty) => tty.flush(),
+            Output::Recorded(output) => output.flush(),
+        }
+    }
+}
+
+/// Switch the terminal into raw mode on the alternate screen
+pub fn setup_terminal(mouse_capture: bool) -> io::Result<()> {
+    enable_raw_mode()?;
+    execute!(Output::open(), EnterAlternateScreen)?;
+    if mouse_capture {
+        execute!(Output::open(), EnableMouseCapture)?;
+    }
+    Ok(())
+}
+
+/// Start or stop reporting mouse events while the application is running
+pub fn set_mouse_capture(enabled: bool) -> io::Result<()> {
+    if enabled {
+        execute!(Output::open(), EnableMouseCapture)
+    } else {
+        execute!(Output::open(), DisableMouseCapture)
+    }
+}
+
+/// Return the terminal to the state the shell expects
+///
+/// Safe to call more than once, and from a panic hook.
+pub fn restore_terminal() -> io::Result<()> {
+    disable_raw_mode()?;
+    execute!(
+        Output::open(),
+        LeaveAlternateScreen,
+        DisableMouseCapture,
+        Show
+    )
+}
+
+/// Restore the terminal before the default panic report is printed
+///
+/// Release builds abort on panic, so this hook is the only cleanup that runs.
+pub fn install_panic_hook() {
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        let _ = restore_terminal();
+        default_hook(info);
+    }));
+}
+
+/// Hand the terminal back to the shell and stop the process until it is resumed
+#[cfg(unix)]
+pub fn suspend(mouse_capture: bool) -> io::Result<()> {
+    restore_terminal()?;
+    signal_hook::low_level::emulate_default_handler(signal_hook::consts::SIGTSTP)?;
+    setup_terminal(mouse_capture)
+}
+
+/// Owns the terminal for the lifetime of the UI and restores it when dropped
+pub struct TerminalGuard {
+    terminal: Tui,
+}
+
+impl TerminalGuard {
+    /// Take over the terminal; with a recorder, what is drawn is also recorded
+    pub fn new(mouse_capture: bool, recorder: Option<Recorder>) -> Result<Self> {
+        install_panic_hook();
+        // Raw mode may already be on when a later step fails, and there is no guard yet to undo it
+        if let Err(err) = setup_terminal(mouse_capture) {
+            let _ = restore_terminal();
+            return Err(err.into());
+        }
+
+        let output = match recorder {
+            Some(recorder) => Output::open().recorded(recorder),
+            None => Output::open(),
+        };
+        match Terminal::new(CrosstermBackend::new(output)) {
+            Ok(terminal) => Ok(Self { terminal }),
+            Err(err) => {
+                let _ = restore_terminal();
+                Err(err.into())
+            }
+        }
+    }
+}
+
+impl Deref for TerminalGuard {
+    type Target = Tui;
+
+    fn deref(&self) -> &Self::Target {
+        &self.terminal
+    }
+}
+
+impl DerefMut for TerminalGuard {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.terminal
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        if let Err(err) = restore_terminal() {
+            error!("Failed to restore terminal: {}", err);
+        }
+    }
+}
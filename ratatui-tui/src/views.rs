@@ -1,118 +1,661 @@
+use crossterm::event::{Event, MouseButton, MouseEventKind};
 use ratatui::{
-    layout::{Constraint, Rect},
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    symbols::Marker,
     text::{Line, Span},
-    widgets::{Block, Borders, Paragraph, Row, Table},
+    widgets::{
+        Axis, Bar, BarChart, BarGroup, Block, Borders, Cell, Chart, Dataset, Gauge, GraphType,
+        LineGauge, Paragraph, Row, Sparkline, Table, TableState, Wrap,
+    },
     Frame,
 };
 
-use crate::styles::{StyleManager, StyleType};
+use chrono::{DateTime, Local};
+use std::str::FromStr;
+
+use crate::{
+    action::Action,
+    config::Config,
+    context::{DataContext, RenderContext},
+    dashboard::{DashboardConfig, PanelKind},
+    edit::CellEditor,
+    export::ExportPrompt,
+    filter::{Filter, FilterBar},
+    keymap::KeyHint,
+    metrics::{self, Metrics, Reading},
+    picker::FilePicker,
+    settings::{FieldKind, FormNotice, SettingField, SettingsForm},
+    source::LoadStatus,
+    styles::StyleType,
+    table::DataTable,
+    widgets::{self, EventResult},
+};
+
+/// Identifier for each view the application can route to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ViewId {
+    Dashboard,
+    Data,
+    Settings,
+    Help,
+}
+
+impl ViewId {
+    /// All views, in navigation order
+    pub const ALL: [ViewId; 4] = [
+        ViewId::Dashboard,
+        ViewId::Data,
+        ViewId::Settings,
+        ViewId::Help,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ViewId::Dashboard => "dashboard",
+            ViewId::Data => "data",
+            ViewId::Settings => "settings",
+            ViewId::Help => "help",
+        }
+    }
+
+    /// Short human readable name
+    pub fn title(&self) -> &'static str {
+        match self {
+            ViewId::Dashboard => "Dashboard",
+            ViewId::Data => "Data Browser",
+            ViewId::Settings => "Settings",
+            ViewId::Help => "Help",
+        }
+    }
+
+    /// Label shown in the navigation sidebar
+    pub fn label(&self) -> &'static str {
+        match self {
+            ViewId::Dashboard => "📊 Dashboard",
+            ViewId::Data => "📁 Data Browser",
+            ViewId::Settings => "⚙️ Settings",
+            ViewId::Help => "❓ Help",
+        }
+    }
+}
+
+impl FromStr for ViewId {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ViewId::ALL
+            .into_iter()
+            .find(|view| view.as_str() == s)
+            .ok_or_else(|| format!("unknown view '{}'", s))
+    }
+}
 
 /// Trait for all views in the application
 pub trait View {
     /// Render the view to the given area
-    fn render(&mut self, area: Rect, f: &mut Frame);
+    fn render(&mut self, area: Rect, f: &mut Frame, ctx: &RenderContext);
 
     /// Get the title of the view
     fn get_title(&self) -> &str;
 
     /// Get the description of the view
     fn get_description(&self) -> &str;
+
+    /// Text the content area scrolls and searches, for views that are a page of text
+    fn text(&self, _ctx: &RenderContext) -> Option<ViewText> {
+        None
+    }
+
+    /// Set the key bindings the view should advertise
+    fn set_key_hints(&mut self, _hints: &[KeyHint]) {}
+
+    /// Handle an event while the view, or one of its children, has focus
+    fn handle_event(&mut self, _event: &Event) -> EventResult {
+        EventResult::Ignored
+    }
 }
 
-/// Dashboard view
-#[derive(Debug, Clone)]
-pub struct DashboardView {
-    style_manager: StyleManager,
+/// A view's contents as lines of text
+#[derive(Debug, Clone, Default)]
+pub struct ViewText {
+    pub lines: Vec<Line<'static>>,
+    /// Line to keep on screen when it changes, such as the focused field
+    pub cursor: Option<usize>,
 }
 
-impl DashboardView {
-    pub fn new(style_manager: StyleManager) -> Self {
-        Self { style_manager }
+/// Draw a view's text from the top, for when it is rendered outside the content area
+fn render_text(view: &dyn View, area: Rect, f: &mut Frame, ctx: &RenderContext) {
+    if let Some(text) = view.text(ctx) {
+        f.render_widget(Paragraph::new(text.lines).wrap(Wrap { trim: false }), area);
     }
 }
 
-impl View for DashboardView {
-    fn render(&mut self, area: Rect, f: &mut Frame) {
-        let content_style = self.style_manager.get_style(StyleType::Content);
-        let block = Block::default()
-            .style(content_style)
-            .borders(Borders::ALL);
+/// Percentages from which a metric shows as a warning, and as an error
+const WARNING_PERCENT: f64 = 60.0;
+const ERROR_PERCENT: f64 = 85.0;
+
+/// How a percentage of some capacity is coloured
+fn level(percent: f64) -> StyleType {
+    if percent >= ERROR_PERCENT {
+        StyleType::Error
+    } else if percent >= WARNING_PERCENT {
+        StyleType::Warning
+    } else {
+        StyleType::Success
+    }
+}
 
-        let mut content = Vec::new();
+/// A label, its value and the value's style, as listed in the data panel
+type DataLine = (&'static str, String, StyleType);
 
-        // Title
-        let title_span = self.style_manager.get_span("📊 Dashboard", StyleType::Title);
-        content.push(Line::from(vec![title_span]));
-        content.push(Line::from(""));
+/// Dashboard view: a grid of panels showing live metrics, the open data, logs and notes
+#[derive(Debug, Clone, Default)]
+pub struct DashboardView {
+    key_hints: Vec<KeyHint>,
+    metrics: Metrics,
+    layout: DashboardConfig,
+    /// Index of the selected panel
+    selected: usize,
+    maximised: bool,
+    data: Vec<DataLine>,
+    log: Vec<String>,
+    time: Option<DateTime<Local>>,
+}
 
-        // Welcome message
-        let welcome_span = self.style_manager.get_span(
-            "Welcome to the Ratatui TUI Prototype!",
-            StyleType::Text,
-        );
-        content.push(Line::from(vec![welcome_span]));
-        content.push(Line::from(""));
+impl DashboardView {
+    pub fn set_metrics(&mut self, metrics: &Metrics) {
+        self.metrics = metrics.clone();
+    }
 
-        // Features overview
-        let features_title = self.style_manager.get_span("Features:", StyleType::Subtitle);
-        content.push(Line::from(vec![features_title]));
+    pub fn set_layout(&mut self, layout: &DashboardConfig, selected: usize, maximised: bool) {
+        self.layout = layout.clone();
+        self.selected = selected;
+        self.maximised = maximised;
+    }
 
-        let features = vec![
-            "• Modern TUI interface",
-            "• Responsive design",
-            "• Keyboard navigation",
-            "• Multiple views",
-            "• Styled components",
+    /// Summarise the Data Browser's rows for the data panel
+    pub fn set_data(&mut self, table: &DataTable, load: &LoadStatus) {
+        let source = load.name().unwrap_or_else(|| "Sample data".to_string());
+        let (status, style) = match load {
+            LoadStatus::Sample => ("Built in".to_string(), StyleType::MutedText),
+            LoadStatus::Loading { progress, .. } => {
+                (format!("Loading {:.0}%", progress * 100.0), StyleType::Info)
+            }
+            LoadStatus::Loaded { .. } => ("Loaded".to_string(), StyleType::Success),
+            LoadStatus::Failed { error, .. } => (error.clone(), StyleType::Error),
+        };
+        let total = table.dataset().len();
+        let rows = if table.filter().is_some() {
+            format!("{} of {}", table.len(), total)
+        } else {
+            total.to_string()
+        };
+        let columns = match table.hidden_count() {
+            0 => table.dataset().columns.len().to_string(),
+            hidden => format!("{} ({} hidden)", table.dataset().columns.len(), hidden),
+        };
+
+        self.data = vec![
+            ("Source", source, StyleType::Text),
+            ("Status", status, style),
+            ("Rows", rows, StyleType::Text),
+            ("Columns", columns, StyleType::Text),
         ];
+        if let Some(filter) = table.filter() {
+            self.data
+                .push(("Filter", filter.text().to_string(), StyleType::Info));
+        }
+        if table.has_changes() {
+            let changes = format!("{} rows unsaved", table.edited_rows());
+            self.data.push(("Changes", changes, StyleType::Warning));
+        }
+    }
 
-        for feature in features {
-            let feature_span = self.style_manager.get_span(feature, StyleType::Text);
-            content.push(Line::from(vec![feature_span]));
+    pub fn set_log(&mut self, lines: Vec<String>) {
+        self.log = lines;
+    }
+
+    /// Set the time the clock shows; whether the second shown changed
+    pub fn set_time(&mut self, time: DateTime<Local>) -> bool {
+        let changed = self.time.map(|shown| shown.timestamp()) != Some(time.timestamp());
+        self.time = Some(time);
+        changed
+    }
+
+    fn block<'a>(&self, title: String, selected: bool, ctx: &RenderContext) -> Block<'a> {
+        let (border, title_style) = if selected {
+            (StyleType::BorderFocus, StyleType::Title)
+        } else {
+            (StyleType::Border, StyleType::Subtitle)
+        };
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(ctx.styles.get_style(border))
+            .title(Span::styled(title, ctx.styles.get_style(title_style)))
+    }
+
+    fn render_panel(&self, index: usize, area: Rect, f: &mut Frame, ctx: &RenderContext) {
+        let panel = &self.layout.panels[index];
+        let block = self.block(format!(" {} ", panel.title()), index == self.selected, ctx);
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+
+        // Squeezed panels say how much room they need rather than draw garbled
+        let (width, height) = panel.min_size();
+        if !self.maximised && (area.width < width || area.height < height) {
+            let message = format!("Needs {}×{}", width, height);
+            let line = Line::from(vec![Span::styled(
+                message,
+                ctx.styles.get_style(StyleType::MutedText),
+            )]);
+            f.render_widget(Paragraph::new(line), inner);
+            return;
         }
 
-        content.push(Line::from(""));
+        match panel.kind {
+            PanelKind::Data => self.render_data(inner, f, ctx),
+            PanelKind::Log => self.render_log(inner, f, ctx),
+            PanelKind::Clock => self.render_clock(inner, f, ctx),
+            PanelKind::Note => {
+                let note = match &panel.text {
+                    Some(text) => {
+                        Paragraph::new(text.clone()).style(ctx.styles.get_style(StyleType::Text))
+                    }
+                    None => Paragraph::new("Set this note's text in config.toml")
+                        .style(ctx.styles.get_style(StyleType::MutedText)),
+                };
+                f.render_widget(note.wrap(Wrap { trim: false }), inner);
+            }
+            kind => {
+                // Rates need two readings, a second apart
+                let Some(reading) = self.metrics.latest else {
+                    let message = ctx
+                        .styles
+                        .get_span("Sampling system metrics…", StyleType::MutedText);
+                    f.render_widget(Paragraph::new(Line::from(vec![message])), inner);
+                    return;
+                };
+                match kind {
+                    PanelKind::Cpu => self.render_cpu(&reading, inner, f, ctx),
+                    PanelKind::Memory => self.render_memory(&reading, inner, f, ctx),
+                    PanelKind::Load => self.render_load(&reading, inner, f, ctx),
+                    PanelKind::Network => self.render_network(&reading, inner, f, ctx),
+                    _ => self.render_disk(&reading, inner, f, ctx),
+                }
+            }
+        }
+    }
+
+    fn render_gauge(
+        &self,
+        percent: f64,
+        label: String,
+        area: Rect,
+        f: &mut Frame,
+        ctx: &RenderContext,
+    ) {
+        let gauge = Gauge::default()
+            .gauge_style(ctx.styles.get_style(level(percent)))
+            .ratio((percent / 100.0).clamp(0.0, 1.0))
+            .label(label);
+        f.render_widget(gauge, area);
+    }
 
-        // Quick stats
-        let stats_title = self.style_manager.get_span("Quick Stats:", StyleType::Subtitle);
-        content.push(Line::from(vec![stats_title]));
+    /// CPU use now, and over the last couple of minutes
+    fn render_cpu(&self, reading: &Reading, area: Rect, f: &mut Frame, ctx: &RenderContext) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(1), // Gauge
+                Constraint::Min(0),    // History
+            ])
+            .split(area);
+        self.render_gauge(
+            reading.cpu,
+            format!("{:.0}%", reading.cpu),
+            chunks[0],
+            f,
+            ctx,
+        );
 
-        let stats = vec![
-            "• Views: 4",
-            "• Components: 4",
-            "• Themes: 2",
-        ];
+        let first = self
+            .metrics
+            .samples
+            .saturating_sub(self.metrics.history.len() as u64);
+        let points: Vec<(f64, f64)> = self
+            .metrics
+            .series(|reading| reading.cpu)
+            .into_iter()
+            .enumerate()
+            .map(|(index, value)| ((first + index as u64) as f64, value))
+            .collect();
+        let datasets = vec![Dataset::default()
+            .marker(Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(ctx.styles.get_style(level(reading.cpu)))
+            .data(&points)];
+
+        let end = self.metrics.samples.max(metrics::HISTORY_LEN as u64) as f64;
+        let muted = ctx.styles.get_style(StyleType::MutedText);
+        let chart = Chart::new(datasets)
+            .style(ctx.styles.get_style(StyleType::Text))
+            .x_axis(
+                Axis::default()
+                    .style(muted)
+                    .bounds([end - metrics::HISTORY_LEN as f64, end]),
+            )
+            .y_axis(
+                Axis::default()
+                    .style(muted)
+                    .bounds([0.0, 100.0])
+                    .labels(vec![Span::styled("0%", muted), Span::styled("100%", muted)]),
+            );
+        f.render_widget(chart, chunks[1]);
+    }
+
+    fn render_memory(&self, reading: &Reading, area: Rect, f: &mut Frame, ctx: &RenderContext) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(1), // Gauge
+                Constraint::Min(0),    // History
+            ])
+            .split(area);
+        let label = format!(
+            "{} of {}",
+            metrics::format_bytes(reading.memory_used as f64 * 1024.0),
+            metrics::format_bytes(reading.memory_total as f64 * 1024.0)
+        );
+        self.render_gauge(reading.memory, label, chunks[0], f, ctx);
+
+        // Memory is out of 100%
+        let values = self.metrics.series(|reading| reading.memory);
+        let label = format!("{:.0}% used", reading.memory);
+        sparkline(
+            label,
+            values,
+            level(reading.memory),
+            Some(100),
+            chunks[1],
+            f,
+            ctx,
+        );
+    }
 
-        for stat in stats {
-            let stat_span = self.style_manager.get_span(stat, StyleType::Info);
-            content.push(Line::from(vec![stat_span]));
+    fn render_load(&self, reading: &Reading, area: Rect, f: &mut Frame, ctx: &RenderContext) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(1), // CPU count
+                Constraint::Min(0),    // Bars
+            ])
+            .split(area);
+        let cpus = format!("{} CPUs", reading.cpus);
+        f.render_widget(
+            Paragraph::new(Line::from(vec![Span::styled(
+                cpus,
+                ctx.styles.get_style(StyleType::MutedText),
+            )])),
+            chunks[0],
+        );
+
+        let bars: Vec<Bar> = ["1m", "5m", "15m"]
+            .into_iter()
+            .enumerate()
+            .map(|(index, label)| {
+                let style = ctx.styles.get_style(level(reading.load_percent(index)));
+                Bar::default()
+                    .label(Line::from(label))
+                    .value((reading.load[index] * 100.0) as u64)
+                    .text_value(format!("{:.2}", reading.load[index]))
+                    .style(style)
+                    .value_style(style.add_modifier(Modifier::REVERSED))
+            })
+            .collect();
+        // Full height is every CPU busy, unless the load is higher still
+        let max = reading
+            .load
+            .iter()
+            .fold(reading.cpus as f64, |max, load| max.max(*load));
+
+        let chart = BarChart::default()
+            .data(BarGroup::default().bars(&bars))
+            .bar_width(4)
+            .bar_gap(2)
+            .max((max * 100.0) as u64)
+            .label_style(ctx.styles.get_style(StyleType::MutedText));
+        f.render_widget(chart, chunks[1]);
+    }
+
+    /// Bytes received and sent, each scaled to its own peak
+    fn render_network(&self, reading: &Reading, area: Rect, f: &mut Frame, ctx: &RenderContext) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Ratio(1, 2), // Received
+                Constraint::Ratio(1, 2), // Sent
+            ])
+            .split(area);
+        let received = format!("In {}", rate(reading.net_received));
+        let values = self.metrics.series(|reading| reading.net_received);
+        sparkline(received, values, StyleType::Info, None, chunks[0], f, ctx);
+        let sent = format!("Out {}", rate(reading.net_sent));
+        let values = self.metrics.series(|reading| reading.net_sent);
+        sparkline(sent, values, StyleType::Info, None, chunks[1], f, ctx);
+    }
+
+    fn render_disk(&self, reading: &Reading, area: Rect, f: &mut Frame, ctx: &RenderContext) {
+        let label = format!(
+            "Read {} · Write {}",
+            rate(reading.disk_read),
+            rate(reading.disk_written)
+        );
+        let values = self
+            .metrics
+            .series(|reading| reading.disk_read + reading.disk_written);
+        sparkline(label, values, StyleType::Info, None, area, f, ctx);
+    }
+
+    fn render_data(&self, area: Rect, f: &mut Frame, ctx: &RenderContext) {
+        let lines: Vec<Line> = self
+            .data
+            .iter()
+            .map(|(label, value, style)| {
+                Line::from(vec![
+                    Span::styled(
+                        format!("{:<9}", label),
+                        ctx.styles.get_style(StyleType::MutedText),
+                    ),
+                    Span::styled(value.clone(), ctx.styles.get_style(*style)),
+                ])
+            })
+            .collect();
+        f.render_widget(Paragraph::new(lines), area);
+    }
+
+    /// The newest log lines that fit, warnings and errors coloured
+    fn render_log(&self, area: Rect, f: &mut Frame, ctx: &RenderContext) {
+        if self.log.is_empty() {
+            let message = ctx
+                .styles
+                .get_span("Nothing logged yet", StyleType::MutedText);
+            f.render_widget(Paragraph::new(Line::from(vec![message])), area);
+            return;
         }
 
-        content.push(Line::from(""));
+        let lines: Vec<Line> = self.log[self.log.len().saturating_sub(area.height as usize)..]
+            .iter()
+            .map(|line| {
+                // Lines start with the time, then the level
+                let style = match line.split_whitespace().nth(1) {
+                    Some("ERROR") => StyleType::Error,
+                    Some("WARN") => StyleType::Warning,
+                    Some("INFO") => StyleType::Text,
+                    _ => StyleType::MutedText,
+                };
+                Line::from(vec![Span::styled(
+                    line.clone(),
+                    ctx.styles.get_style(style),
+                )])
+            })
+            .collect();
+        f.render_widget(Paragraph::new(lines), area);
+    }
 
-        // Instructions
-        let instructions_title = self.style_manager.get_span("Getting Started:", StyleType::Subtitle);
-        content.push(Line::from(vec![instructions_title]));
-
-        let instructions = vec![
-            "Use the number keys (1-4) to navigate between views:",
-            "1 - Dashboard (current)",
-            "2 - Data Browser",
-            "3 - Settings",
-            "4 - Help",
-            "",
-            "Press 'q' or Ctrl+C to quit.",
-        ];
+    fn render_clock(&self, area: Rect, f: &mut Frame, ctx: &RenderContext) {
+        let (time, date) = match self.time {
+            Some(now) => (
+                now.format("%H:%M:%S").to_string(),
+                now.format("%A %-d %B %Y").to_string(),
+            ),
+            None => ("--:--:--".to_string(), String::new()),
+        };
+        // Centred vertically as well as horizontally
+        let top = area.height.saturating_sub(2) / 2;
+        let mut lines = vec![Line::default(); top as usize];
+        lines.push(Line::from(vec![Span::styled(
+            time,
+            ctx.styles
+                .get_style(StyleType::Title)
+                .add_modifier(Modifier::BOLD),
+        )]));
+        lines.push(Line::from(vec![Span::styled(
+            date,
+            ctx.styles.get_style(StyleType::MutedText),
+        )]));
+        f.render_widget(Paragraph::new(lines).alignment(Alignment::Center), area);
+    }
 
-        for instruction in instructions {
-            let instruction_span = self.style_manager.get_span(instruction, StyleType::Text);
-            content.push(Line::from(vec![instruction_span]));
+    /// How to rearrange the panels, or why metrics are missing
+    fn footer(&self, ctx: &RenderContext) -> Line<'static> {
+        if let Some(error) = &self.metrics.error {
+            return Line::from(vec![Span::styled(
+                format!("⚠ {}", error),
+                ctx.styles.get_style(StyleType::Error),
+            )]);
         }
 
-        let paragraph = Paragraph::new(content)
-            .block(block)
-            .wrap(ratatui::widgets::Wrap { trim: true });
+        // Moves and resizes are listed once, with the keys for every side
+        let keys = |wanted: fn(&Action) -> bool| -> String {
+            let keys: Vec<String> = self
+                .key_hints
+                .iter()
+                .filter(|hint| wanted(&hint.action))
+                .map(|hint| hint.keys_label())
+                .collect();
+            keys.join("/")
+        };
+        let maximise = if self.maximised {
+            "restore"
+        } else {
+            "maximise"
+        };
+        let hints = [
+            (keys(|action| *action == Action::NextPanel), "next panel"),
+            (
+                keys(|action| matches!(action, Action::MovePanel(_))),
+                "move",
+            ),
+            (
+                keys(|action| matches!(action, Action::ResizePanel(_))),
+                "resize",
+            ),
+            (keys(|action| *action == Action::MaximisePanel), maximise),
+            (keys(|action| *action == Action::SaveLayout), "save layout"),
+        ];
+        let hints: Vec<String> = hints
+            .into_iter()
+            .filter(|(keys, _)| !keys.is_empty())
+            .map(|(keys, description)| format!("{} {}", keys, description))
+            .collect();
+        Line::from(vec![Span::styled(
+            hints.join(" · "),
+            ctx.styles.get_style(StyleType::MutedText),
+        )])
+    }
+}
 
-        f.render_widget(paragraph, area);
+/// A sparkline of the newest values that fit, under a line giving the latest
+fn sparkline(
+    label: String,
+    values: Vec<f64>,
+    style: StyleType,
+    max: Option<u64>,
+    area: Rect,
+    f: &mut Frame,
+    ctx: &RenderContext,
+) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1), // Latest value
+            Constraint::Min(0),    // Sparkline
+        ])
+        .split(area);
+    f.render_widget(
+        Paragraph::new(Line::from(vec![Span::styled(
+            label,
+            ctx.styles.get_style(StyleType::Text),
+        )])),
+        chunks[0],
+    );
+
+    // Sparklines draw from the oldest value, so keep only the newest that fit
+    let width = area.width as usize;
+    let data: Vec<u64> = values[values.len().saturating_sub(width)..]
+        .iter()
+        .map(|value| value.round() as u64)
+        .collect();
+    let mut sparkline = Sparkline::default()
+        .data(&data)
+        .style(ctx.styles.get_style(style));
+    if let Some(max) = max {
+        sparkline = sparkline.max(max);
+    }
+    f.render_widget(sparkline, chunks[1]);
+}
+
+/// A rate in bytes per second
+fn rate(bytes: f64) -> String {
+    format!("{}/s", metrics::format_bytes(bytes))
+}
+
+impl View for DashboardView {
+    fn render(&mut self, area: Rect, f: &mut Frame, ctx: &RenderContext) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Min(0),    // Panels
+                Constraint::Length(1), // Footer
+            ])
+            .split(area);
+        f.render_widget(Paragraph::new(self.footer(ctx)), chunks[1]);
+
+        if self.layout.panels.is_empty() {
+            let message = ctx.styles.get_span(
+                "No panels; add some under [dashboard] in config.toml",
+                StyleType::MutedText,
+            );
+            f.render_widget(Paragraph::new(Line::from(vec![message])), chunks[0]);
+            return;
+        }
+
+        let areas: Vec<(usize, Rect)> =
+            if self.maximised && self.selected < self.layout.panels.len() {
+                vec![(self.selected, chunks[0])]
+            } else {
+                self.layout
+                    .areas(chunks[0])
+                    .into_iter()
+                    .enumerate()
+                    .collect()
+            };
+        for (index, area) in areas {
+            if !area.is_empty() {
+                self.render_panel(index, area, f, ctx);
+            }
+        }
     }
 
     fn get_title(&self) -> &str {
@@ -120,78 +663,461 @@ impl View for DashboardView {
     }
 
     fn get_description(&self) -> &str {
-        "Overview and quick actions"
+        "Live system metrics"
+    }
+
+    fn set_key_hints(&mut self, hints: &[KeyHint]) {
+        self.key_hints = hints.to_vec();
     }
 }
 
+/// Widest a column grows to fit its header and the rows on screen
+const MAX_COLUMN_WIDTH: u16 = 30;
+
+/// Width of the row detail pane
+const DETAIL_WIDTH: u16 = 36;
+
 /// Data browser view
-#[derive(Debug, Clone)]
+///
+/// Only the rows that fit on screen are turned into table rows, so large
+/// datasets cost no more to draw than small ones. The rows themselves are
+/// borrowed through the render context for each frame.
+#[derive(Debug, Clone, Default)]
 pub struct DataBrowserView {
-    style_manager: StyleManager,
+    /// Position of the first row on screen, moved only to keep the cursor in sight
+    offset: usize,
+    /// Rows that fit below the header when last drawn
+    page_size: usize,
+    /// Where the table was last drawn, for mouse hit-testing
+    area: Rect,
+    /// Rows the table had when last drawn
+    rows: usize,
 }
 
 impl DataBrowserView {
-    pub fn new(style_manager: StyleManager) -> Self {
-        Self { style_manager }
+    /// Rows that fit on screen, for paging
+    pub fn page_size(&self) -> usize {
+        self.page_size
     }
-}
 
-impl View for DataBrowserView {
-    fn render(&mut self, area: Rect, f: &mut Frame) {
-        let content_style = self.style_manager.get_style(StyleType::Content);
+    /// Position of the data row drawn at a screen row, below the header
+    fn row_at(&self, column: u16, row: u16) -> Option<usize> {
+        if !widgets::contains(self.area, column, row) {
+            return None;
+        }
+        let position = (row - self.area.y).checked_sub(1)? as usize + self.offset;
+        (position < self.rows).then_some(position)
+    }
+
+    /// Scroll just far enough for the cursor to be on screen
+    fn scroll_to_cursor(&mut self, table: &DataTable) {
+        let cursor = table.cursor();
+        let rows = self.page_size.max(1);
+        if cursor < self.offset {
+            self.offset = cursor;
+        } else if cursor >= self.offset + rows {
+            self.offset = cursor + 1 - rows;
+        }
+        self.offset = self.offset.min(table.len().saturating_sub(rows));
+    }
+
+    fn row_style(table: &DataTable, index: usize, ctx: &RenderContext) -> Style {
+        let dataset = table.dataset();
+        let status = dataset
+            .columns
+            .iter()
+            .position(|column| column.name.eq_ignore_ascii_case("status"))
+            .map(|column| table.value(index, column).to_string());
+
+        match status.as_deref() {
+            Some("Active" | "Completed") => ctx.styles.get_style(StyleType::Success),
+            Some("Pending") => ctx.styles.get_style(StyleType::Warning),
+            Some("Cancelled") => ctx.styles.get_style(StyleType::Error),
+            _ => ctx.styles.get_style(StyleType::Text),
+        }
+    }
+
+    fn render_table(&mut self, data: &DataContext, area: Rect, f: &mut Frame, ctx: &RenderContext) {
+        let table = data.table;
+        self.area = area;
+        self.rows = table.len();
+        self.page_size = area.height.saturating_sub(1) as usize;
+        self.scroll_to_cursor(table);
+
+        let dataset = table.dataset();
+        let columns = table.visible_columns();
+        let end = (self.offset + self.page_size).min(table.len());
+        let indexes: Vec<usize> = (self.offset..end)
+            .filter_map(|position| table.row_index(position))
+            .collect();
+
+        let header_style = ctx.styles.get_style(StyleType::Button);
+        // Marks and unsaved edits
+        let mut widths = vec![Constraint::Length(2)];
+        let mut header = vec![Cell::from("")];
+        for &column in &columns {
+            let mut title = dataset.columns[column].name.clone();
+            if let Some((sorted, direction)) = table.sort() {
+                if sorted == column {
+                    title = format!("{} {}", title, direction.indicator());
+                }
+            }
+
+            let width = indexes
+                .iter()
+                .map(|&index| match data.editing(index, column) {
+                    Some(text) => text.chars().count() + 1,
+                    None => table.value(index, column).to_string().chars().count(),
+                })
+                .chain([title.chars().count()])
+                .max()
+                .unwrap_or(0) as u16;
+            widths.push(Constraint::Length(width.min(MAX_COLUMN_WIDTH)));
+
+            let style = if column == table.column() {
+                ctx.styles.get_style(StyleType::InputFocus)
+            } else {
+                header_style
+            };
+            header.push(Cell::from(title).style(style));
+        }
+
+        let filter = table.filter();
+        let rows: Vec<Row> = indexes
+            .iter()
+            .map(|&index| {
+                let marker = Line::from(vec![
+                    ctx.styles.get_span(
+                        if table.is_marked(index) { "●" } else { " " },
+                        StyleType::Info,
+                    ),
+                    ctx.styles.get_span(
+                        if table.is_edited(index) { "*" } else { " " },
+                        StyleType::Warning,
+                    ),
+                ]);
+                let cells = columns.iter().map(|&column| {
+                    if let Some(text) = data.editing(index, column) {
+                        return Cell::from(format!("{}_", text))
+                            .style(ctx.styles.get_style(StyleType::InputFocus));
+                    }
+                    let text = table.value(index, column).to_string();
+                    match filter {
+                        Some(filter) => Cell::from(highlighted(filter, column, text, ctx)),
+                        None => Cell::from(text),
+                    }
+                });
+                Row::new(std::iter::once(Cell::from(marker)).chain(cells))
+                    .style(Self::row_style(table, index, ctx))
+            })
+            .collect();
+
+        let mut state =
+            TableState::default().with_selected(Some(table.cursor().saturating_sub(self.offset)));
+        let widget = Table::new(rows, widths)
+            .header(Row::new(header).style(header_style))
+            .highlight_style(ctx.styles.get_style(StyleType::ButtonActive));
+        f.render_stateful_widget(widget, area, &mut state);
+    }
+
+    fn render_detail(&self, table: &DataTable, area: Rect, f: &mut Frame, ctx: &RenderContext) {
+        let dataset = table.dataset();
+        let mut content = Vec::new();
+
+        if let Some(index) = table.current_row() {
+            for (column, field) in dataset.columns.iter().enumerate() {
+                content.push(Line::from(vec![
+                    Span::styled(
+                        format!("{}: ", field.name),
+                        ctx.styles.get_style(StyleType::Subtitle),
+                    ),
+                    Span::styled(
+                        table.value(index, column).to_string(),
+                        ctx.styles.get_style(StyleType::Text),
+                    ),
+                ]));
+            }
+        }
+
         let block = Block::default()
-            .style(content_style)
-            .borders(Borders::ALL);
+            .borders(Borders::LEFT)
+            .border_style(ctx.styles.get_style(StyleType::Border))
+            .title(Span::styled(
+                format!(" Row {} ", table.cursor() + 1),
+                ctx.styles.get_style(StyleType::Subtitle),
+            ));
+        let paragraph = Paragraph::new(content)
+            .block(block)
+            .wrap(ratatui::widgets::Wrap { trim: true });
+        f.render_widget(paragraph, area);
+    }
 
+    /// Every unsaved edit, from the value on disk to the new one
+    fn render_changes(&self, table: &DataTable, area: Rect, f: &mut Frame, ctx: &RenderContext) {
+        let dataset = table.dataset();
         let mut content = Vec::new();
+        for (index, column, value) in table.edits() {
+            content.push(Line::from(vec![
+                Span::styled(
+                    format!("#{} {}: ", index + 1, dataset.columns[column].name),
+                    ctx.styles.get_style(StyleType::Subtitle),
+                ),
+                Span::styled(
+                    dataset.value(index, column).to_string(),
+                    ctx.styles.get_style(StyleType::MutedText),
+                ),
+                ctx.styles.get_span(" → ", StyleType::MutedText),
+                Span::styled(value.to_string(), ctx.styles.get_style(StyleType::Text)),
+            ]));
+        }
+        if content.is_empty() {
+            content.push(Line::from(vec![ctx
+                .styles
+                .get_span("No unsaved changes", StyleType::MutedText)]));
+        }
 
-        // Title
-        let title_span = self.style_manager.get_span("📁 Data Browser", StyleType::Title);
-        content.push(Line::from(vec![title_span]));
-        content.push(Line::from(""));
+        let count = table.edits().count();
+        let block = Block::default()
+            .borders(Borders::LEFT)
+            .border_style(ctx.styles.get_style(StyleType::Border))
+            .title(Span::styled(
+                format!(" {} changes ", count),
+                ctx.styles.get_style(StyleType::Subtitle),
+            ));
+        let paragraph = Paragraph::new(content)
+            .block(block)
+            .wrap(ratatui::widgets::Wrap { trim: true });
+        f.render_widget(paragraph, area);
+    }
 
-        // Sample data table
-        let table_title = self.style_manager.get_span("Sample Data Table:", StyleType::Subtitle);
-        content.push(Line::from(vec![table_title]));
-        content.push(Line::from(""));
+    fn render_picker(&self, picker: &FilePicker, area: Rect, f: &mut Frame, ctx: &RenderContext) {
+        let mut content = vec![Line::from(vec![ctx.styles.get_span(
+            "↑/↓ move · Enter open · Backspace up · Esc cancel",
+            StyleType::MutedText,
+        )])];
+        if let Some(error) = &picker.error {
+            content.push(Line::from(vec![Span::styled(
+                format!("⚠ {}", error),
+                ctx.styles.get_style(StyleType::Error),
+            )]));
+        } else if picker.entries.is_empty() {
+            content.push(Line::from(vec![ctx
+                .styles
+                .get_span("No data files here", StyleType::MutedText)]));
+        }
 
-        // Create table data
-        let table_data = vec![
-            ("001", "Project Alpha", "Active", "$1,234"),
-            ("002", "Project Beta", "Pending", "$5,678"),
-            ("003", "Project Gamma", "Completed", "$9,012"),
-            ("004", "Project Delta", "Active", "$3,456"),
-            ("005", "Project Epsilon", "Cancelled", "$0"),
+        // Keep the selected entry in sight below the hint line
+        let rows = area.height.saturating_sub(3).max(1) as usize;
+        let first = (picker.selected + 1).saturating_sub(rows);
+        for (index, entry) in picker.entries.iter().enumerate().skip(first).take(rows) {
+            let name = if entry.is_dir {
+                format!("{}/", entry.name)
+            } else {
+                entry.name.clone()
+            };
+            let line = if index == picker.selected {
+                Span::styled(
+                    format!("▸ {}", name),
+                    ctx.styles.get_style(StyleType::ButtonActive),
+                )
+            } else {
+                Span::styled(format!("  {}", name), ctx.styles.get_style(StyleType::Text))
+            };
+            content.push(Line::from(vec![line]));
+        }
+
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(ctx.styles.get_style(StyleType::BorderFocus))
+            .title(Span::styled(
+                format!(" Open {} ", picker.dir.display()),
+                ctx.styles.get_style(StyleType::Subtitle),
+            ));
+        f.render_widget(Paragraph::new(content).block(block), area);
+    }
+
+    fn render_filter_bar(&self, bar: &FilterBar, area: Rect, f: &mut Frame, ctx: &RenderContext) {
+        let mut spans = vec![
+            ctx.styles.get_span("/", StyleType::Title),
+            Span::styled(
+                format!("{}_", bar.text),
+                ctx.styles.get_style(StyleType::InputFocus),
+            ),
+            Span::raw("  "),
         ];
+        spans.push(match &bar.error {
+            Some(error) => Span::styled(
+                format!("⚠ {}", error),
+                ctx.styles.get_style(StyleType::Error),
+            ),
+            None => ctx.styles.get_span(
+                "Enter apply · Esc cancel · ↑/↓ history · col = value, col > value, col ~ text",
+                StyleType::MutedText,
+            ),
+        });
+        f.render_widget(Paragraph::new(Line::from(spans)), area);
+    }
 
-        let header_style = self.style_manager.get_style(StyleType::Button);
-        let header = Row::new(vec!["ID", "Name", "Status", "Value"])
-            .style(header_style);
-
-        let mut rows = Vec::new();
-        for (id, name, status, value) in table_data {
-            let status_style = match status {
-                "Active" | "Completed" => self.style_manager.get_style(StyleType::Success),
-                "Pending" => self.style_manager.get_style(StyleType::Warning),
-                "Cancelled" => self.style_manager.get_style(StyleType::Error),
-                _ => self.style_manager.get_style(StyleType::Text),
-            };
+    fn render_export_prompt(
+        &self,
+        table: &DataTable,
+        prompt: &ExportPrompt,
+        area: Rect,
+        f: &mut Frame,
+        ctx: &RenderContext,
+    ) {
+        let rows = table.selection().len();
+        let spans = vec![
+            Span::styled(
+                format!("Export {} rows as {} to: ", rows, prompt.format.name()),
+                ctx.styles.get_style(StyleType::Title),
+            ),
+            Span::styled(
+                format!("{}_", prompt.text),
+                ctx.styles.get_style(StyleType::InputFocus),
+            ),
+            Span::raw("  "),
+            ctx.styles.get_span(
+                "Tab format · Enter export · Esc cancel · - prints on exit",
+                StyleType::MutedText,
+            ),
+        ];
+        f.render_widget(Paragraph::new(Line::from(spans)), area);
+    }
 
-            let row = Row::new(vec![id, name, status, value])
-                .style(status_style);
-            rows.push(row);
+    fn render_editor(
+        &self,
+        table: &DataTable,
+        editor: &CellEditor,
+        area: Rect,
+        f: &mut Frame,
+        ctx: &RenderContext,
+    ) {
+        let column = &table.dataset().columns[editor.column];
+        let mut spans = vec![Span::styled(
+            format!("Edit {} ({}): ", column.name, column.kind.as_str()),
+            ctx.styles.get_style(StyleType::Title),
+        )];
+        spans.push(match &editor.error {
+            Some(error) => Span::styled(
+                format!("⚠ {}", error),
+                ctx.styles.get_style(StyleType::Error),
+            ),
+            None => ctx.styles.get_span(
+                "Enter keep · Esc cancel · empty clears the cell",
+                StyleType::MutedText,
+            ),
+        });
+        f.render_widget(Paragraph::new(Line::from(spans)), area);
+    }
+
+    fn render_footer(&self, data: &DataContext, area: Rect, f: &mut Frame, ctx: &RenderContext) {
+        if let LoadStatus::Loading { progress, .. } = data.load {
+            let label = format!("Loading {}", data.load.name().unwrap_or_default());
+            let gauge = LineGauge::default()
+                .label(label)
+                .ratio(progress.clamp(0.0, 1.0))
+                .style(ctx.styles.get_style(StyleType::Text))
+                .gauge_style(ctx.styles.get_style(StyleType::Info));
+            f.render_widget(gauge, area);
+        } else {
+            f.render_widget(Paragraph::new(self.footer(data, ctx)), area);
         }
+    }
 
-        let table = Table::new(rows, [
-            Constraint::Length(15),
-            Constraint::Length(20),
-            Constraint::Length(10),
-            Constraint::Length(8),
-        ])
-            .header(header)
-            .block(block);
+    fn footer(&self, data: &DataContext, ctx: &RenderContext) -> Line<'static> {
+        let table = data.table;
+        if let LoadStatus::Failed { error, .. } = data.load {
+            return Line::from(vec![Span::styled(
+                format!("⚠ {}", error),
+                ctx.styles.get_style(StyleType::Error),
+            )]);
+        }
 
-        f.render_widget(table, area);
+        let mut parts = vec![if table.len() == 0 {
+            "No rows".to_string()
+        } else {
+            format!("Row {} of {}", table.cursor() + 1, table.len())
+        }];
+        if table.marked_count() > 0 {
+            parts.push(format!("{} selected", table.marked_count()));
+        }
+        if table.has_changes() {
+            parts.push(format!("{} rows changed", table.edited_rows()));
+        }
+        if let Some(filter) = table.filter() {
+            parts.push(format!("filter: {}", filter.text()));
+        }
+        if let Some((column, direction)) = table.sort() {
+            let name = &table.dataset().columns[column].name;
+            parts.push(format!("sorted by {} {}", name, direction.indicator()));
+        }
+        if table.hidden_count() > 0 {
+            parts.push(format!("{} columns hidden", table.hidden_count()));
+        }
+        if let Some(name) = data.load.name() {
+            parts.insert(0, name);
+        }
+
+        Line::from(vec![Span::styled(
+            parts.join(" · "),
+            ctx.styles.get_style(StyleType::MutedText),
+        )])
+    }
+}
+
+impl View for DataBrowserView {
+    fn render(&mut self, area: Rect, f: &mut Frame, ctx: &RenderContext) {
+        let Some(data) = ctx.data else {
+            return;
+        };
+        let table = data.table;
+        let prompt_height = (data.filter_bar.is_some()
+            || data.export_prompt.is_some()
+            || data.editor.is_some()) as u16;
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Min(0),                // Table and details
+                Constraint::Length(prompt_height), // Filter bar, export prompt or cell editor
+                Constraint::Length(1),             // Footer
+            ])
+            .split(area);
+
+        let detail_width = if table.show_detail || table.show_changes {
+            DETAIL_WIDTH.min(chunks[0].width / 2)
+        } else {
+            0
+        };
+        let main_chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Min(0),               // Table
+                Constraint::Length(detail_width), // Row details or pending changes
+            ])
+            .split(chunks[0]);
+
+        match data.picker {
+            Some(picker) => self.render_picker(picker, chunks[0], f, ctx),
+            None => {
+                self.render_table(&data, main_chunks[0], f, ctx);
+                if table.show_detail {
+                    self.render_detail(table, main_chunks[1], f, ctx);
+                } else if table.show_changes {
+                    self.render_changes(table, main_chunks[1], f, ctx);
+                }
+            }
+        }
+        if let Some(bar) = data.filter_bar {
+            self.render_filter_bar(bar, chunks[1], f, ctx);
+        }
+        if let Some(prompt) = data.export_prompt {
+            self.render_export_prompt(table, prompt, chunks[1], f, ctx);
+        }
+        if let Some(editor) = data.editor {
+            self.render_editor(table, editor, chunks[1], f, ctx);
+        }
+        self.render_footer(&data, chunks[2], f, ctx);
     }
 
     fn get_title(&self) -> &str {
@@ -201,108 +1127,211 @@ impl View for DataBrowserView {
     fn get_description(&self) -> &str {
         "Browse and manage data"
     }
+
+    /// Move the cursor to a row by clicking it
+    fn handle_event(&mut self, event: &Event) -> EventResult {
+        let Event::Mouse(mouse) = event else {
+            return EventResult::Ignored;
+        };
+
+        match (mouse.kind, self.row_at(mouse.column, mouse.row)) {
+            (MouseEventKind::Down(MouseButton::Left), Some(position)) => {
+                EventResult::Action(Action::GoToRow(position))
+            }
+            _ => EventResult::Ignored,
+        }
+    }
+}
+
+/// A cell's text with the characters a filter matched picked out
+fn highlighted(filter: &Filter, column: usize, text: String, ctx: &RenderContext) -> Line<'static> {
+    let positions = filter.highlights(column, &text);
+    if positions.is_empty() {
+        return Line::from(text);
+    }
+    let style = ctx.styles.get_style(StyleType::Highlight);
+    Line::from(highlight_chars(&text, &positions, Style::default(), style))
+}
+
+/// `text` as spans, the characters at the sorted `positions` in the `matched` style and the rest in `base`
+pub fn highlight_chars(
+    text: &str,
+    positions: &[usize],
+    base: Style,
+    matched: Style,
+) -> Vec<Span<'static>> {
+    let mut spans: Vec<Span> = Vec::new();
+    let mut run = String::new();
+    let mut run_matched = false;
+    for (index, c) in text.chars().enumerate() {
+        let is_match = positions.binary_search(&index).is_ok();
+        if is_match != run_matched && !run.is_empty() {
+            let style = if run_matched { matched } else { base };
+            spans.push(Span::styled(std::mem::take(&mut run), style));
+        }
+        run_matched = is_match;
+        run.push(c);
+    }
+    let style = if run_matched { matched } else { base };
+    spans.push(Span::styled(run, style));
+    spans
 }
 
 /// Settings view
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct SettingsView {
-    style_manager: StyleManager,
+    key_hints: Vec<KeyHint>,
+    config: Config,
+    form: SettingsForm,
 }
 
 impl SettingsView {
-    pub fn new(style_manager: StyleManager) -> Self {
-        Self { style_manager }
+    /// Set the live configuration and the form's editing state
+    pub fn set_form(&mut self, config: &Config, form: &SettingsForm) {
+        self.config = config.clone();
+        self.form = form.clone();
+    }
+
+    fn field_line(&self, field: SettingField, ctx: &RenderContext) -> Line<'static> {
+        let focused = self.form.focused() == field;
+        let value = match (field.kind(), &self.form.editing) {
+            (FieldKind::Numeric, Some(text)) if focused => format!("[ {}_ ]", text),
+            (FieldKind::Numeric, _) => format!("[ {} ]", field.value(&self.config)),
+            (FieldKind::Select, _) => format!("◀ {} ▶", field.value(&self.config)),
+            (FieldKind::Toggle, _) if field.is_on(&self.config) => "[x]".to_string(),
+            (FieldKind::Toggle, _) => "[ ]".to_string(),
+        };
+
+        let marker = if focused { "▶ " } else { "  " };
+        let label_style = if focused {
+            ctx.styles.get_style(StyleType::InputFocus)
+        } else {
+            ctx.styles.get_style(StyleType::Text)
+        };
+        let value_style = if focused {
+            ctx.styles.get_style(StyleType::ButtonActive)
+        } else {
+            ctx.styles.get_style(StyleType::Input)
+        };
+
+        Line::from(vec![
+            Span::styled(format!("{}{:<18}", marker, field.label()), label_style),
+            Span::styled(value, value_style),
+        ])
     }
 }
 
 impl View for SettingsView {
-    fn render(&mut self, area: Rect, f: &mut Frame) {
-        let content_style = self.style_manager.get_style(StyleType::Content);
-        let block = Block::default()
-            .style(content_style)
-            .borders(Borders::ALL);
+    fn render(&mut self, area: Rect, f: &mut Frame, ctx: &RenderContext) {
+        render_text(self, area, f, ctx);
+    }
 
+    fn text(&self, ctx: &RenderContext) -> Option<ViewText> {
         let mut content = Vec::new();
+        let mut cursor = None;
 
         // Title
-        let title_span = self.style_manager.get_span("⚙️ Settings", StyleType::Title);
+        let title_span = ctx.styles.get_span("⚙️ Settings", StyleType::Title);
         content.push(Line::from(vec![title_span]));
         content.push(Line::from(""));
 
-        // Theme settings
-        let theme_title = self.style_manager.get_span("Theme Settings:", StyleType::Subtitle);
-        content.push(Line::from(vec![theme_title]));
-
-        let theme_items = vec![
-            "• Light Theme (current)",
-            "• Dark Theme",
-        ];
-
-        for item in theme_items {
-            let item_span = self.style_manager.get_span(item, StyleType::Text);
-            content.push(Line::from(vec![item_span]));
+        // Form fields, grouped by section, with validation errors under the field
+        let mut section = "";
+        for field in SettingField::ALL {
+            if field.section() != section {
+                if !section.is_empty() {
+                    content.push(Line::from(""));
+                }
+                section = field.section();
+                let section_span = Span::styled(
+                    format!("{}:", section),
+                    ctx.styles.get_style(StyleType::Subtitle),
+                );
+                content.push(Line::from(vec![section_span]));
+            }
+
+            if self.form.focused() == field {
+                cursor = Some(content.len());
+            }
+            content.push(self.field_line(field, ctx));
+
+            if field == SettingField::Theme {
+                if let Some(picker) = &self.form.picker {
+                    let hint = "    ↑/↓ preview · Enter keep · Esc cancel";
+                    content.push(Line::from(vec![ctx
+                        .styles
+                        .get_span(hint, StyleType::MutedText)]));
+                    for (index, name) in self.form.themes.iter().enumerate() {
+                        let line = if index == picker.selected {
+                            Span::styled(
+                                format!("    ▸ {}", name),
+                                ctx.styles.get_style(StyleType::ButtonActive),
+                            )
+                        } else {
+                            Span::styled(
+                                format!("      {}", name),
+                                ctx.styles.get_style(StyleType::Text),
+                            )
+                        };
+                        content.push(Line::from(vec![line]));
+                    }
+                }
+            }
+
+            if let Some((_, message)) = self
+                .form
+                .error
+                .as_ref()
+                .filter(|(error_field, _)| *error_field == field)
+            {
+                let error_span = Span::styled(
+                    format!("    ⚠ {}", message),
+                    ctx.styles.get_style(StyleType::Error),
+                );
+                content.push(Line::from(vec![error_span]));
+            }
         }
 
         content.push(Line::from(""));
 
-        // Display settings
-        let display_title = self.style_manager.get_span("Display Settings:", StyleType::Subtitle);
-        content.push(Line::from(vec![display_title]));
-
-        let display_items = vec![
-            "• Show borders",
-            "• Show status bar",
-            "• Show navigation",
-            "• Compact mode",
-        ];
-
-        for item in display_items {
-            let item_span = self.style_manager.get_span(item, StyleType::Text);
-            content.push(Line::from(vec![item_span]));
-        }
-
-        content.push(Line::from(""));
-
-        // Application settings
-        let app_title = self.style_manager.get_span("Application Settings:", StyleType::Subtitle);
-        content.push(Line::from(vec![app_title]));
-
-        let app_items = vec![
-            "• Auto-save: Enabled",
-            "• Notifications: Enabled",
-            "• Debug mode: Disabled",
-            "• Log level: Info",
-        ];
-
-        for item in app_items {
-            let item_span = self.style_manager.get_span(item, StyleType::Text);
-            content.push(Line::from(vec![item_span]));
-        }
+        // Save state
+        let (status, style) = match &self.form.notice {
+            Some(FormNotice::SaveFailed(err)) => (format!("⚠ {}", err), StyleType::Error),
+            _ if self.form.is_dirty(&self.config) => {
+                ("● Unsaved changes".to_string(), StyleType::Warning)
+            }
+            Some(FormNotice::Saved(path)) => {
+                (format!("✓ Saved to {}", path.display()), StyleType::Success)
+            }
+            Some(FormNotice::Reverted) => (
+                "✓ Reverted to saved settings".to_string(),
+                StyleType::Success,
+            ),
+            None => ("✓ All changes saved".to_string(), StyleType::MutedText),
+        };
+        content.push(Line::from(vec![Span::styled(
+            status,
+            ctx.styles.get_style(style),
+        )]));
 
         content.push(Line::from(""));
 
         // Keyboard shortcuts
-        let shortcuts_title = self.style_manager.get_span("Keyboard Shortcuts:", StyleType::Subtitle);
+        let shortcuts_title = ctx
+            .styles
+            .get_span("Keyboard Shortcuts:", StyleType::Subtitle);
         content.push(Line::from(vec![shortcuts_title]));
 
-        let shortcuts = vec![
-            "• 1-4 - Navigate views",
-            "• q - Quit application",
-            "• Ctrl+C - Force quit",
-            "• Tab - Focus next element",
-            "• Enter - Activate/Confirm",
-        ];
-
-        for shortcut in shortcuts {
-            let shortcut_span = self.style_manager.get_span(shortcut, StyleType::MutedText);
+        for hint in &self.key_hints {
+            let shortcut = format!("• {} - {}", hint.keys_label(), hint.action.description());
+            let shortcut_span = Span::styled(shortcut, ctx.styles.get_style(StyleType::MutedText));
             content.push(Line::from(vec![shortcut_span]));
         }
 
-        let paragraph = Paragraph::new(content)
-            .block(block)
-            .wrap(ratatui::widgets::Wrap { trim: true });
-
-        f.render_widget(paragraph, area);
+        Some(ViewText {
+            lines: content,
+            cursor,
+        })
     }
 
     fn get_title(&self) -> &str {
@@ -312,36 +1341,37 @@ impl View for SettingsView {
     fn get_description(&self) -> &str {
         "Application configuration"
     }
+
+    fn set_key_hints(&mut self, hints: &[KeyHint]) {
+        self.key_hints = hints.to_vec();
+    }
 }
 
 /// Help view
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct HelpView {
-    style_manager: StyleManager,
-}
-
-impl HelpView {
-    pub fn new(style_manager: StyleManager) -> Self {
-        Self { style_manager }
-    }
+    key_hints: Vec<KeyHint>,
 }
 
 impl View for HelpView {
-    fn render(&mut self, area: Rect, f: &mut Frame) {
-        let content_style = self.style_manager.get_style(StyleType::Content);
-        let block = Block::default()
-            .style(content_style)
-            .borders(Borders::ALL);
+    fn render(&mut self, area: Rect, f: &mut Frame, ctx: &RenderContext) {
+        render_text(self, area, f, ctx);
+    }
 
+    fn text(&self, ctx: &RenderContext) -> Option<ViewText> {
         let mut content = Vec::new();
 
         // Title
-        let title_span = self.style_manager.get_span("❓ Help & Documentation", StyleType::Title);
+        let title_span = ctx
+            .styles
+            .get_span("❓ Help & Documentation", StyleType::Title);
         content.push(Line::from(vec![title_span]));
         content.push(Line::from(""));
 
         // About section
-        let about_title = self.style_manager.get_span("About Ratatui TUI:", StyleType::Subtitle);
+        let about_title = ctx
+            .styles
+            .get_span("About Ratatui TUI:", StyleType::Subtitle);
         content.push(Line::from(vec![about_title]));
 
         let about_text = vec![
@@ -350,51 +1380,51 @@ impl View for HelpView {
         ];
 
         for text in about_text {
-            let text_span = self.style_manager.get_span(text, StyleType::Text);
+            let text_span = ctx.styles.get_span(text, StyleType::Text);
             content.push(Line::from(vec![text_span]));
         }
 
         content.push(Line::from(""));
 
         // Navigation help
-        let nav_title = self.style_manager.get_span("Navigation:", StyleType::Subtitle);
+        let nav_title = ctx.styles.get_span("Navigation:", StyleType::Subtitle);
         content.push(Line::from(vec![nav_title]));
 
-        let nav_items = vec![
-            "• 1 - Dashboard: Overview and quick actions",
-            "• 2 - Data Browser: View and manage data",
-            "• 3 - Settings: Configure application",
-            "• 4 - Help: This documentation",
-        ];
-
-        for item in nav_items {
-            let item_span = self.style_manager.get_span(item, StyleType::Text);
-            content.push(Line::from(vec![item_span]));
+        for hint in &self.key_hints {
+            if let Action::Navigate(view) = hint.action {
+                let summary = match view {
+                    ViewId::Dashboard => "Overview and quick actions",
+                    ViewId::Data => "View and manage data",
+                    ViewId::Settings => "Configure application",
+                    ViewId::Help => "This documentation",
+                };
+                let item = format!("• {} - {}: {}", hint.keys_label(), view.title(), summary);
+                let item_span = Span::styled(item, ctx.styles.get_style(StyleType::Text));
+                content.push(Line::from(vec![item_span]));
+            }
         }
 
         content.push(Line::from(""));
 
         // Keyboard shortcuts
-        let shortcuts_title = self.style_manager.get_span("Keyboard Shortcuts:", StyleType::Subtitle);
+        let shortcuts_title = ctx
+            .styles
+            .get_span("Keyboard Shortcuts:", StyleType::Subtitle);
         content.push(Line::from(vec![shortcuts_title]));
 
-        let shortcuts = vec![
-            "• q or Ctrl+C - Quit application",
-            "• 1-4 - Switch between views",
-            "• Tab - Focus next element",
-            "• Enter - Activate/Confirm",
-            "• Escape - Cancel/Go back",
-        ];
-
-        for shortcut in shortcuts {
-            let shortcut_span = self.style_manager.get_span(shortcut, StyleType::MutedText);
+        for hint in &self.key_hints {
+            if matches!(hint.action, Action::Navigate(_)) {
+                continue;
+            }
+            let shortcut = format!("• {} - {}", hint.keys_label(), hint.action.description());
+            let shortcut_span = Span::styled(shortcut, ctx.styles.get_style(StyleType::MutedText));
             content.push(Line::from(vec![shortcut_span]));
         }
 
         content.push(Line::from(""));
 
         // Features
-        let features_title = self.style_manager.get_span("Features:", StyleType::Subtitle);
+        let features_title = ctx.styles.get_span("Features:", StyleType::Subtitle);
         content.push(Line::from(vec![features_title]));
 
         let features = vec![
@@ -406,32 +1436,32 @@ impl View for HelpView {
         ];
 
         for feature in features {
-            let feature_span = self.style_manager.get_span(feature, StyleType::Text);
+            let feature_span = ctx.styles.get_span(feature, StyleType::Text);
             content.push(Line::from(vec![feature_span]));
         }
 
         content.push(Line::from(""));
 
         // Tips
-        let tips_title = self.style_manager.get_span("Tips:", StyleType::Subtitle);
+        let tips_title = ctx.styles.get_span("Tips:", StyleType::Subtitle);
         content.push(Line::from(vec![tips_title]));
 
         let tips = vec![
             "• Resize your terminal window to see responsive design",
             "• Use number keys for quick navigation",
+            "• Rebind keys in ~/.config/ratatui-tui/keymap.toml",
             "• Check the status bar for current view information",
         ];
 
         for tip in tips {
-            let tip_span = self.style_manager.get_span(tip, StyleType::Text);
+            let tip_span = ctx.styles.get_span(tip, StyleType::Text);
             content.push(Line::from(vec![tip_span]));
         }
 
-        let paragraph = Paragraph::new(content)
-            .block(block)
-            .wrap(ratatui::widgets::Wrap { trim: true });
-
-        f.render_widget(paragraph, area);
+        Some(ViewText {
+            lines: content,
+            cursor: None,
+        })
     }
 
     fn get_title(&self) -> &str {
@@ -441,10 +1471,14 @@ impl View for HelpView {
     fn get_description(&self) -> &str {
         "Documentation and help"
     }
+
+    fn set_key_hints(&mut self, hints: &[KeyHint]) {
+        self.key_hints = hints.to_vec();
+    }
 }
 
 /// View manager for handling all views
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct ViewManager {
     pub dashboard: DashboardView,
     pub data_browser: DataBrowserView,
@@ -453,22 +1487,12 @@ pub struct ViewManager {
 }
 
 impl ViewManager {
-    pub fn new(style_manager: StyleManager) -> Self {
-        Self {
-            dashboard: DashboardView::new(style_manager.clone()),
-            data_browser: DataBrowserView::new(style_manager.clone()),
-            settings: SettingsView::new(style_manager.clone()),
-            help: HelpView::new(style_manager),
-        }
-    }
-
-    pub fn get_view(&mut self, view_name: &str) -> &mut dyn View {
-        match view_name {
-            "dashboard" => &mut self.dashboard,
-            "data" => &mut self.data_browser,
-            "settings" => &mut self.settings,
-            "help" => &mut self.help,
-            _ => &mut self.dashboard,
+    pub fn get_view(&mut self, view: ViewId) -> &mut dyn View {
+        match view {
+            ViewId::Dashboard => &mut self.dashboard,
+            ViewId::Data => &mut self.data_browser,
+            ViewId::Settings => &mut self.settings,
+            ViewId::Help => &mut self.help,
         }
     }
 }
@@ -476,47 +1500,362 @@ impl ViewManager {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::styles::StyleManager;
+    use crate::{keymap::Keymap, styles::StyleManager};
+    use chrono::TimeZone;
+    use ratatui::{backend::TestBackend, style::Modifier, Terminal};
 
     #[test]
     fn test_dashboard_view_creation() {
-        let style_manager = StyleManager::default();
-        let dashboard = DashboardView::new(style_manager);
+        let dashboard = DashboardView::default();
         assert_eq!(dashboard.get_title(), "Dashboard");
-        assert_eq!(dashboard.get_description(), "Overview and quick actions");
+        assert_eq!(dashboard.get_description(), "Live system metrics");
     }
 
     #[test]
     fn test_data_browser_view_creation() {
-        let style_manager = StyleManager::default();
-        let data_browser = DataBrowserView::new(style_manager);
+        let data_browser = DataBrowserView::default();
         assert_eq!(data_browser.get_title(), "Data Browser");
         assert_eq!(data_browser.get_description(), "Browse and manage data");
     }
 
+    #[test]
+    fn test_dashboard_panels_chart_metrics_coloured_by_level() {
+        let reading = Reading {
+            cpu: 90.0,
+            memory: 50.0,
+            memory_used: 512 * 1024,
+            memory_total: 1024 * 1024,
+            load: [1.0, 0.5, 0.25],
+            cpus: 2,
+            net_received: 2048.0,
+            ..Reading::default()
+        };
+        let mut dashboard = DashboardView::default();
+        dashboard.set_metrics(&Metrics {
+            latest: Some(reading),
+            history: vec![reading; 10].into(),
+            samples: 10,
+            error: None,
+        });
+        dashboard.set_data(&DataTable::default(), &LoadStatus::default());
+        dashboard.set_log(vec!["09:05:01 WARN  Disk is filling up".to_string()]);
+        assert!(dashboard.set_time(Local.with_ymd_and_hms(2026, 10, 17, 9, 5, 3).unwrap()));
+        assert!(!dashboard.set_time(Local.with_ymd_and_hms(2026, 10, 17, 9, 5, 3).unwrap()));
+
+        let styles = StyleManager::default();
+        let mut terminal = Terminal::new(TestBackend::new(100, 24)).unwrap();
+        terminal
+            .draw(|f| dashboard.render(f.size(), f, &RenderContext::new(&styles)))
+            .unwrap();
+        let buffer = terminal.backend().buffer();
+        let text: String = buffer.content().iter().map(|cell| cell.symbol()).collect();
+
+        assert!(text.contains("90%"));
+        assert!(text.contains("512.0 MiB of 1.0 GiB"));
+        assert!(text.contains("2 CPUs"));
+        assert!(text.contains("1.00"));
+        assert!(text.contains("In 2.0 KiB/s"));
+        assert!(text.contains("Sample data"));
+        assert!(text.contains("Disk is filling up"));
+        assert!(text.contains("09:05:03"));
+
+        // A busy CPU gauge is drawn in the error colour, half-used memory in the success colour
+        let gauge_colour = |x| buffer.get(x, 1).style().fg;
+        assert_eq!(gauge_colour(10), styles.get_style(StyleType::Error).fg);
+        assert_eq!(gauge_colour(55), styles.get_style(StyleType::Success).fg);
+        // The selected panel's border stands out
+        assert_eq!(
+            buffer.get(0, 0).style().fg,
+            styles.get_style(StyleType::BorderFocus).fg
+        );
+        assert_eq!(
+            buffer.get(50, 0).style().fg,
+            styles.get_style(StyleType::Border).fg
+        );
+    }
+
+    #[test]
+    fn test_dashboard_panels_maximise_and_say_when_squeezed() {
+        let mut dashboard = DashboardView::default();
+        let styles = StyleManager::default();
+        let render = |dashboard: &mut DashboardView, width| {
+            let mut terminal = Terminal::new(TestBackend::new(width, 20)).unwrap();
+            terminal
+                .draw(|f| dashboard.render(f.size(), f, &RenderContext::new(&styles)))
+                .unwrap();
+            let buffer = terminal.backend().buffer();
+            buffer
+                .content()
+                .iter()
+                .map(|cell| cell.symbol())
+                .collect::<String>()
+        };
+
+        let text = render(&mut dashboard, 54);
+        assert!(text.contains(" Memory "));
+        assert!(text.contains("Needs 18×6"));
+        assert!(text.contains("Sampling system metrics"));
+
+        let layout = DashboardConfig::default();
+        dashboard.set_layout(&layout, 4, true);
+        let text = render(&mut dashboard, 54);
+        assert!(text.contains(" Load "));
+        assert!(!text.contains(" Memory "));
+        assert!(!text.contains("Needs"));
+    }
+
+    #[test]
+    fn test_data_browser_draws_rows_around_cursor() {
+        use crate::data::{Column, ColumnKind, Dataset, Value};
+
+        let rows = (0..10_000)
+            .map(|i| vec![Value::Number(i as f64), Value::Text(format!("row {}", i))])
+            .collect();
+        let mut table = DataTable::new(Dataset {
+            columns: vec![
+                Column::new("n", ColumnKind::Number),
+                Column::new("label", ColumnKind::Text),
+            ],
+            rows,
+            originals: Vec::new(),
+        });
+        table.go_to_bottom();
+        table.toggle_mark();
+        table.show_detail = true;
+
+        let mut data_browser = DataBrowserView::default();
+        let load = LoadStatus::default();
+        let styles = StyleManager::default();
+        let ctx = RenderContext::new(&styles).with_data(DataContext::new(&table, &load));
+        let mut terminal = Terminal::new(TestBackend::new(80, 12)).unwrap();
+        terminal
+            .draw(|f| data_browser.render(f.size(), f, &ctx))
+            .unwrap();
+        let text: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+
+        // A header, ten rows and the footer
+        assert_eq!(data_browser.page_size(), 10);
+        assert!(text.contains("row 9999"));
+        assert!(text.contains("row 9990"));
+        assert!(!text.contains("row 9989"));
+        assert!(text.contains("Row 10000 of 10000 · 1 selected"));
+        assert!(text.contains("label: row 9999"));
+    }
+
+    #[test]
+    fn test_data_browser_highlights_filter_matches() {
+        let mut table = DataTable::default();
+        let filter = Filter::parse("name ~ gam", &table.dataset().columns).unwrap();
+        table.set_filter(filter.clone());
+        let mut bar = FilterBar::new(filter);
+        bar.error = Some("no column named 'x'".to_string());
+
+        let mut data_browser = DataBrowserView::default();
+        let load = LoadStatus::default();
+        let data = DataContext {
+            filter_bar: Some(&bar),
+            ..DataContext::new(&table, &load)
+        };
+        let styles = StyleManager::default();
+        let mut terminal = Terminal::new(TestBackend::new(80, 8)).unwrap();
+        terminal
+            .draw(|f| {
+                data_browser.render(f.size(), f, &RenderContext::new(&styles).with_data(data))
+            })
+            .unwrap();
+        let buffer = terminal.backend().buffer();
+        let text: String = buffer.content().iter().map(|cell| cell.symbol()).collect();
+
+        assert!(text.contains("Project Gamma"));
+        assert!(!text.contains("Project Alpha"));
+        assert!(text.contains("/name ~ gam_  ⚠ no column named 'x'"));
+        assert!(text.contains("Row 1 of 1 · filter: name ~ gam"));
+
+        // Only "Gam" of "Project Gamma" is picked out, underlined
+        let highlighted: String = buffer
+            .content()
+            .iter()
+            .filter(|cell| cell.modifier.contains(Modifier::UNDERLINED))
+            .map(|cell| cell.symbol())
+            .collect();
+        assert_eq!(highlighted, "Gam");
+    }
+
+    #[test]
+    fn test_data_browser_shows_edits_and_the_cell_being_edited() {
+        use crate::data::Value;
+
+        let mut table = DataTable::default();
+        table.set_value(1, 3, Value::Number(42.0));
+        table.show_changes = true;
+        let mut editor = CellEditor::new(0, 1, table.value(0, 1));
+        editor.text.push('!');
+
+        let mut data_browser = DataBrowserView::default();
+        let load = LoadStatus::default();
+        let data = DataContext {
+            editor: Some(&editor),
+            ..DataContext::new(&table, &load)
+        };
+        let styles = StyleManager::default();
+        let mut terminal = Terminal::new(TestBackend::new(120, 10)).unwrap();
+        terminal
+            .draw(|f| {
+                data_browser.render(f.size(), f, &RenderContext::new(&styles).with_data(data))
+            })
+            .unwrap();
+        let lines: Vec<String> = terminal
+            .backend()
+            .buffer()
+            .content()
+            .chunks(120)
+            .map(|row| row.iter().map(|cell| cell.symbol()).collect())
+            .collect();
+
+        assert!(lines[1].contains("Project Alpha!_"));
+        assert!(lines[2].starts_with(" *"));
+        assert!(!lines[3].starts_with(" *"));
+        assert!(lines[0].contains("1 changes"));
+        assert!(lines[1].contains("#2 Value: 5678 → 42"));
+        assert!(lines[8].contains("Edit Name (text): Enter keep"));
+        assert!(lines[9].contains("Row 1 of 5 · 1 rows changed"));
+    }
+
     #[test]
     fn test_settings_view_creation() {
-        let style_manager = StyleManager::default();
-        let settings = SettingsView::new(style_manager);
+        let settings = SettingsView::default();
         assert_eq!(settings.get_title(), "Settings");
         assert_eq!(settings.get_description(), "Application configuration");
     }
 
+    #[test]
+    fn test_settings_form_shows_values_and_errors() {
+        let mut settings = SettingsView::default();
+        let mut config = Config::default();
+        let mut form = SettingsForm::new(config.clone(), vec!["light".to_string()]);
+        config.layout.compact = true;
+        form.error = Some((
+            SettingField::SidebarWidth,
+            "must be between 12 and 60".to_string(),
+        ));
+        settings.set_form(&config, &form);
+
+        let mut terminal = Terminal::new(TestBackend::new(80, 40)).unwrap();
+        terminal
+            .draw(|f| settings.render(f.size(), f, &RenderContext::new(&StyleManager::default())))
+            .unwrap();
+        let text: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+
+        assert!(text.contains("▶ Theme"));
+        assert!(text.contains("◀ light ▶"));
+        assert!(text.contains("Compact mode      [x]"));
+        assert!(text.contains("⚠ must be between 12 and 60"));
+        assert!(text.contains("● Unsaved changes"));
+    }
+
+    #[test]
+    fn test_settings_theme_picker_lists_themes() {
+        let mut settings = SettingsView::default();
+        let mut config = Config::default();
+        let mut form = SettingsForm::new(
+            config.clone(),
+            vec!["light".to_string(), "dark".to_string()],
+        );
+        form.activate(&mut config);
+        settings.set_form(&config, &form);
+
+        let mut terminal = Terminal::new(TestBackend::new(80, 40)).unwrap();
+        terminal
+            .draw(|f| settings.render(f.size(), f, &RenderContext::new(&StyleManager::default())))
+            .unwrap();
+        let text: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+
+        assert!(text.contains("Esc cancel"));
+        assert!(text.contains("▸ light"));
+        assert!(text.contains("  dark"));
+    }
+
     #[test]
     fn test_help_view_creation() {
-        let style_manager = StyleManager::default();
-        let help = HelpView::new(style_manager);
+        let help = HelpView::default();
         assert_eq!(help.get_title(), "Help");
         assert_eq!(help.get_description(), "Documentation and help");
     }
 
     #[test]
     fn test_view_manager_creation() {
-        let style_manager = StyleManager::default();
-        let view_manager = ViewManager::new(style_manager);
+        let view_manager = ViewManager::default();
         assert_eq!(view_manager.dashboard.get_title(), "Dashboard");
         assert_eq!(view_manager.data_browser.get_title(), "Data Browser");
         assert_eq!(view_manager.settings.get_title(), "Settings");
         assert_eq!(view_manager.help.get_title(), "Help");
     }
+
+    #[test]
+    fn test_help_lists_live_bindings() {
+        let mut help = HelpView::default();
+        help.set_key_hints(
+            &Keymap::from_toml("[global]\n\"F1\" = \"navigate:help\"\n")
+                .unwrap()
+                .hints(ViewId::Help),
+        );
+        let mut terminal = Terminal::new(TestBackend::new(80, 40)).unwrap();
+        terminal
+            .draw(|f| help.render(f.size(), f, &RenderContext::new(&StyleManager::default())))
+            .unwrap();
+
+        let text: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+        assert!(text.contains("4/F1 - Help: This documentation"));
+        assert!(text.contains("q/Esc/Ctrl+C - Quit"));
+    }
+
+    #[test]
+    fn test_view_id_as_str() {
+        assert_eq!(ViewId::Dashboard.as_str(), "dashboard");
+        assert_eq!(ViewId::Data.as_str(), "data");
+        assert_eq!(ViewId::Settings.as_str(), "settings");
+        assert_eq!(ViewId::Help.as_str(), "help");
+    }
+
+    #[test]
+    fn test_get_view_routes_by_id() {
+        let mut view_manager = ViewManager::default();
+        assert_eq!(
+            view_manager.get_view(ViewId::Dashboard).get_title(),
+            "Dashboard"
+        );
+        assert_eq!(
+            view_manager.get_view(ViewId::Data).get_title(),
+            "Data Browser"
+        );
+        assert_eq!(
+            view_manager.get_view(ViewId::Settings).get_title(),
+            "Settings"
+        );
+        assert_eq!(view_manager.get_view(ViewId::Help).get_title(), "Help");
+    }
 }
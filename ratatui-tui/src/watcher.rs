@@ -0,0 +1,144 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use tokio::{
+    sync::watch,
+    task::JoinHandle,
+    time::{self, MissedTickBehavior},
+};
+
+use crate::{
+    event::{TaskMessage, TaskSender},
+    styles,
+};
+
+/// How often theme files are checked for changes
+pub const THEME_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Posts `TaskMessage::ThemesChanged` when a theme file is added, removed or edited
+///
+/// Polls file metadata, which needs no platform-specific notification backend
+/// and also catches editors that replace the file on save.
+#[derive(Debug)]
+pub struct ThemeWatcher {
+    active: watch::Sender<Option<PathBuf>>,
+    task: JoinHandle<()>,
+}
+
+impl ThemeWatcher {
+    /// Watch every theme in `themes_dir` plus the active theme file, which may live elsewhere
+    pub fn spawn(
+        themes_dir: Option<PathBuf>,
+        active: Option<PathBuf>,
+        tasks: TaskSender,
+        interval: Duration,
+    ) -> Self {
+        let (tx, rx) = watch::channel(active);
+        let task = tokio::spawn(poll(themes_dir, rx, tasks, interval));
+        Self { active: tx, task }
+    }
+
+    /// Follow a different active theme file; `None` for a built-in theme
+    pub fn set_active(&self, path: Option<PathBuf>) {
+        self.active.send_if_modified(|active| {
+            let changed = *active != path;
+            *active = path;
+            changed
+        });
+    }
+}
+
+impl Drop for ThemeWatcher {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Modification time and size of each watched file
+type Snapshot = Vec<(PathBuf, Option<(SystemTime, u64)>)>;
+
+async fn poll(
+    themes_dir: Option<PathBuf>,
+    mut active: watch::Receiver<Option<PathBuf>>,
+    tasks: TaskSender,
+    interval: Duration,
+) {
+    let mut ticks = time::interval(interval);
+    ticks.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    let mut last: Option<Snapshot> = None;
+
+    loop {
+        ticks.tick().await;
+
+        // Switching themes is not an edit; start comparing afresh
+        if active.has_changed().unwrap_or(false) {
+            last = None;
+        }
+        let current = snapshot(themes_dir.as_deref(), active.borrow_and_update().as_deref());
+
+        if last.as_ref().is_some_and(|last| *last != current) {
+            tasks.send(TaskMessage::ThemesChanged);
+        }
+        last = Some(current);
+    }
+}
+
+fn snapshot(themes_dir: Option<&Path>, active: Option<&Path>) -> Snapshot {
+    let mut paths: Vec<PathBuf> = themes_dir
+        .map(styles::theme_files)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(_, path)| path)
+        .collect();
+    paths.extend(active.map(Path::to_path_buf));
+
+    paths
+        .into_iter()
+        .map(|path| {
+            let stamp = fs::metadata(&path)
+                .and_then(|meta| Ok((meta.modified()?, meta.len())))
+                .ok();
+            (path, stamp)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        event::{Event, EventLoop},
+        tempdir::TempDir,
+    };
+
+    #[tokio::test]
+    async fn test_edits_to_theme_files_are_reported() {
+        let dir = TempDir::new();
+        let theme = dir.join("custom.toml");
+        fs::write(&theme, "a").unwrap();
+
+        let mut events = EventLoop::without_input(Duration::from_secs(60), Duration::from_secs(60));
+        let _watcher = ThemeWatcher::spawn(
+            Some(dir.to_path_buf()),
+            Some(theme.clone()),
+            events.sender(),
+            Duration::from_millis(5),
+        );
+        time::sleep(Duration::from_millis(50)).await;
+        fs::write(&theme, "edited").unwrap();
+
+        let reported = time::timeout(Duration::from_secs(5), async {
+            loop {
+                if let Some(Event::Task(TaskMessage::ThemesChanged)) = events.next().await {
+                    break;
+                }
+            }
+        })
+        .await;
+
+        assert!(reported.is_ok());
+    }
+}
@@ -1,21 +1,84 @@
+use std::{
+    collections::BTreeMap,
+    time::{Duration, Instant},
+};
+
+use crossterm::event::{Event, KeyCode, KeyEventKind};
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
-    style::Style,
+    style::Modifier,
     text::{Line, Span},
-    widgets::{Block, Borders, List, ListItem, Paragraph},
+    widgets::{
+        Block, Borders, Clear, List, ListItem, Paragraph, Scrollbar, ScrollbarOrientation,
+        ScrollbarState, Wrap,
+    },
     Frame,
 };
 
-use crate::styles::{StyleManager, StyleType};
+use crate::{
+    action::Action,
+    config::LayoutConfig,
+    context::RenderContext,
+    focus::FocusId,
+    keymap::KeyHint,
+    modal::{Dialog, DialogKind},
+    palette::CommandPalette,
+    scroll::{self, TextScroll},
+    styles::StyleType,
+    views::{self, View, ViewId, ViewText},
+};
+
+/// What a widget or view did with an event
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventResult {
+    /// Not handled; the event moves on to the parent
+    Ignored,
+    /// Handled within the widget
+    Consumed,
+    /// Handled by asking the application to dispatch an action
+    Action(Action),
+}
+
+/// How long a toast stays on screen
+const TOAST_DURATION: Duration = Duration::from_secs(4);
+
+/// Rows above the first navigation item: the border, the heading and a blank line
+const SIDEBAR_ITEMS_TOP: u16 = 3;
+
+/// Where a screen position falls, as last drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hit {
+    Header,
+    SidebarItem(ViewId),
+    Sidebar,
+    /// The sidebar's right edge, which can be dragged to resize it
+    SidebarBorder,
+    Content,
+    StatusBar,
+}
+
+/// Areas of the screen computed by the last `render_all`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ScreenAreas {
+    pub header: Rect,
+    pub sidebar: Rect,
+    pub content: Rect,
+    pub status_bar: Rect,
+}
+
+/// Whether a terminal cell lies inside `area`
+pub fn contains(area: Rect, column: u16, row: u16) -> bool {
+    column >= area.x && column < area.right() && row >= area.y && row < area.bottom()
+}
 
 /// Trait for all widgets in the application
 pub trait Widget {
     /// Render the widget to the given area
-    fn render(&mut self, area: Rect, f: &mut Frame);
+    fn render(&mut self, area: Rect, f: &mut Frame, ctx: &RenderContext);
 
-    /// Handle events for the widget
-    fn handle_event(&mut self, event: crossterm::event::Event) -> bool {
-        false // Default implementation does nothing
+    /// Handle an event while the widget has focus
+    fn handle_event(&mut self, _event: &Event) -> EventResult {
+        EventResult::Ignored // Default implementation does nothing
     }
 }
 
@@ -23,21 +86,17 @@ pub trait Widget {
 #[derive(Debug, Clone)]
 pub struct HeaderWidget {
     title: String,
-    style_manager: StyleManager,
 }
 
 impl HeaderWidget {
-    pub fn new(title: String, style_manager: StyleManager) -> Self {
-        Self {
-            title,
-            style_manager,
-        }
+    pub fn new(title: String) -> Self {
+        Self { title }
     }
 }
 
 impl Widget for HeaderWidget {
-    fn render(&mut self, area: Rect, f: &mut Frame) {
-        let header_style = self.style_manager.get_style(StyleType::Header);
+    fn render(&mut self, area: Rect, f: &mut Frame, ctx: &RenderContext) {
+        let header_style = ctx.styles.get_style(StyleType::Header);
         let block = Block::default()
             .title(self.title.as_str())
             .style(header_style)
@@ -51,66 +110,111 @@ impl Widget for HeaderWidget {
 #[derive(Debug, Clone)]
 pub struct SidebarWidget {
     items: Vec<SidebarItem>,
-    current_view: String,
-    style_manager: StyleManager,
+    current_view: ViewId,
+    shortcuts: Vec<String>,
+    /// Item under the cursor while the sidebar has focus
+    selected: usize,
+    focused: bool,
+    /// Where the sidebar was last drawn, for mouse hit-testing
+    area: Rect,
 }
 
 #[derive(Debug, Clone)]
 pub struct SidebarItem {
-    pub id: String,
+    pub id: ViewId,
     pub label: String,
     pub key: String,
 }
 
-impl SidebarWidget {
-    pub fn new(style_manager: StyleManager) -> Self {
+impl Default for SidebarWidget {
+    fn default() -> Self {
+        let items = ViewId::ALL
+            .iter()
+            .map(|view| SidebarItem {
+                id: *view,
+                label: view.label().to_string(),
+                key: String::new(),
+            })
+            .collect();
+
         Self {
-            items: vec![
-                SidebarItem {
-                    id: "dashboard".to_string(),
-                    label: "📊 Dashboard".to_string(),
-                    key: "1".to_string(),
-                },
-                SidebarItem {
-                    id: "data".to_string(),
-                    label: "📁 Data Browser".to_string(),
-                    key: "2".to_string(),
-                },
-                SidebarItem {
-                    id: "settings".to_string(),
-                    label: "⚙️ Settings".to_string(),
-                    key: "3".to_string(),
-                },
-                SidebarItem {
-                    id: "help".to_string(),
-                    label: "❓ Help".to_string(),
-                    key: "4".to_string(),
-                },
-            ],
-            current_view: "dashboard".to_string(),
-            style_manager,
+            items,
+            current_view: ViewId::Dashboard,
+            shortcuts: Vec::new(),
+            selected: 0,
+            focused: false,
+            area: Rect::default(),
+        }
+    }
+}
+
+impl SidebarWidget {
+    /// Show the keys bound to each view and to the remaining actions
+    pub fn set_key_hints(&mut self, hints: &[KeyHint]) {
+        for item in &mut self.items {
+            item.key = hints
+                .iter()
+                .find(|hint| hint.action == Action::Navigate(item.id))
+                .map(|hint| hint.keys[0].clone())
+                .unwrap_or_default();
+        }
+
+        self.shortcuts = hints
+            .iter()
+            .filter(|hint| !matches!(hint.action, Action::Navigate(_)))
+            .map(|hint| format!("{} - {}", hint.keys_label(), hint.action.description()))
+            .collect();
+    }
+
+    pub fn set_current_view(&mut self, view: ViewId) {
+        if view != self.current_view {
+            self.current_view = view;
+            self.select_current_view();
+        }
+    }
+
+    /// Show the cursor and border of a focused sidebar; the cursor starts on the current view
+    pub fn set_focused(&mut self, focused: bool) {
+        if focused && !self.focused {
+            self.select_current_view();
         }
+        self.focused = focused;
     }
 
-    pub fn set_current_view(&mut self, view: String) {
-        self.current_view = view;
+    /// The navigation item drawn at a screen position
+    pub fn item_at(&self, column: u16, row: u16) -> Option<ViewId> {
+        let inner = Block::default().borders(Borders::ALL).inner(self.area);
+        if !contains(inner, column, row) {
+            return None;
+        }
+        let index = row.checked_sub(self.area.y + SIDEBAR_ITEMS_TOP)?;
+        self.items.get(index as usize).map(|item| item.id)
+    }
+
+    fn select_current_view(&mut self) {
+        self.selected = self
+            .items
+            .iter()
+            .position(|item| item.id == self.current_view)
+            .unwrap_or(0);
     }
 }
 
 impl Widget for SidebarWidget {
-    fn render(&mut self, area: Rect, f: &mut Frame) {
-        let sidebar_style = self.style_manager.get_style(StyleType::Sidebar);
+    fn render(&mut self, area: Rect, f: &mut Frame, ctx: &RenderContext) {
+        self.area = area;
+        let sidebar_style = ctx.styles.get_style(StyleType::Sidebar);
 
         // Create navigation items
         let mut list_items = Vec::new();
 
         // Add title
-        let title_span = Span::styled("Navigation", self.style_manager.get_style(StyleType::Subtitle));
+        let title_span = Span::styled("Navigation", ctx.styles.get_style(StyleType::Subtitle));
         list_items.push(ListItem::new(Line::from(vec![title_span])));
         list_items.push(ListItem::new("")); // Empty line
 
-        // Add navigation items
-        for item in &self.items {
+        // Add navigation items, marking the cursor while focused
+        for (index, item) in self.items.iter().enumerate() {
             let is_active = item.id == self.current_view;
             let style_type = if is_active {
                 StyleType::ButtonActive
@@ -118,105 +222,548 @@ impl Widget for SidebarWidget {
                 StyleType::Button
             };
 
-            let item_text = format!("{} {}", item.key, item.label);
-            let item_span = Span::styled(item_text, self.style_manager.get_style(style_type));
+            let cursor = if self.focused && index == self.selected {
+                "▶"
+            } else {
+                ""
+            };
+            let item_text = format!("{}{} {}", cursor, item.key, item.label);
+            let item_span = Span::styled(item_text, ctx.styles.get_style(style_type));
             list_items.push(ListItem::new(Line::from(vec![item_span])));
         }
 
         // Add separator
         list_items.push(ListItem::new(""));
-        list_items.push(ListItem::new("─".repeat(area.width as usize - 4)));
+        list_items.push(ListItem::new(
+            "─".repeat((area.width as usize).saturating_sub(4)),
+        ));
         list_items.push(ListItem::new(""));
 
         // Add shortcuts
-        let shortcuts_title = Span::styled("Shortcuts:", self.style_manager.get_style(StyleType::MutedText));
+        let shortcuts_title =
+            Span::styled("Shortcuts:", ctx.styles.get_style(StyleType::MutedText));
         list_items.push(ListItem::new(Line::from(vec![shortcuts_title])));
 
-        let shortcuts = vec![
-            "q - Quit",
-            "1-4 - Navigate",
-            "Ctrl+C - Quit",
-        ];
-
-        for shortcut in shortcuts {
-            let shortcut_span = Span::styled(shortcut, self.style_manager.get_style(StyleType::MutedText));
+        for shortcut in &self.shortcuts {
+            let shortcut_span = Span::styled(
+                shortcut.as_str(),
+                ctx.styles.get_style(StyleType::MutedText),
+            );
             list_items.push(ListItem::new(Line::from(vec![shortcut_span])));
         }
 
-        let list = List::new(list_items)
-            .block(Block::default().style(sidebar_style).borders(Borders::ALL));
+        let list = List::new(list_items).block(
+            Block::default()
+                .style(sidebar_style)
+                .borders(Borders::ALL)
+                .border_style(border_style(self.focused, ctx)),
+        );
 
         f.render_widget(list, area);
     }
+
+    /// Move the cursor with Up/Down (or k/j, Home/End) and go to the view under it with Enter
+    fn handle_event(&mut self, event: &Event) -> EventResult {
+        let Event::Key(key) = event else {
+            return EventResult::Ignored;
+        };
+        if key.kind != KeyEventKind::Press || !key.modifiers.is_empty() {
+            return EventResult::Ignored;
+        }
+
+        let last = self.items.len().saturating_sub(1);
+        self.selected = match key.code {
+            KeyCode::Up | KeyCode::Char('k') => self.selected.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => (self.selected + 1).min(last),
+            KeyCode::Home => 0,
+            KeyCode::End => last,
+            KeyCode::Enter | KeyCode::Char(' ') => {
+                return match self.items.get(self.selected) {
+                    Some(item) => EventResult::Action(Action::Navigate(item.id)),
+                    None => EventResult::Ignored,
+                };
+            }
+            _ => return EventResult::Ignored,
+        };
+        EventResult::Consumed
+    }
+}
+
+/// Border style of a block that can hold focus
+fn border_style(focused: bool, ctx: &RenderContext) -> ratatui::style::Style {
+    if focused {
+        ctx.styles.get_style(StyleType::BorderFocus)
+    } else {
+        ctx.styles.get_style(StyleType::Border)
+    }
 }
 
 /// Content widget for the main content area
-#[derive(Debug, Clone)]
+///
+/// Draws the bordered content frame and hands the inner area to the
+/// current view.
+#[derive(Debug, Clone, Default)]
 pub struct ContentWidget {
-    content: String,
-    style_manager: StyleManager,
+    focused: bool,
+    /// Scroll and search state of a view that is a page of text
+    scroll: Option<TextScroll>,
 }
 
 impl ContentWidget {
-    pub fn new(style_manager: StyleManager) -> Self {
-        Self {
-            content: String::new(),
-            style_manager,
-        }
+    pub fn set_focused(&mut self, focused: bool) {
+        self.focused = focused;
     }
 
-    pub fn set_content(&mut self, content: String) {
-        self.content = content;
+    pub fn set_scroll(&mut self, scroll: Option<TextScroll>) {
+        self.scroll = scroll;
     }
-}
 
-impl Widget for ContentWidget {
-    fn render(&mut self, area: Rect, f: &mut Frame) {
-        let content_style = self.style_manager.get_style(StyleType::Content);
+    /// The scroll state as settled by the last render, to keep in the app state
+    pub fn scroll(&self) -> Option<&TextScroll> {
+        self.scroll.as_ref()
+    }
+
+    pub fn render_view(
+        &mut self,
+        view: &mut dyn View,
+        area: Rect,
+        f: &mut Frame,
+        ctx: &RenderContext,
+    ) {
+        let content_style = ctx.styles.get_style(StyleType::Content);
         let block = Block::default()
+            .title(view.get_title())
             .style(content_style)
-            .borders(Borders::ALL);
+            .borders(Borders::ALL)
+            .border_style(border_style(self.focused, ctx));
+
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+        match (view.text(ctx), self.scroll.as_mut()) {
+            (Some(text), Some(scroll)) if inner.width > 1 && inner.height > 0 => {
+                render_text(text, scroll, inner, f, ctx)
+            }
+            _ => view.render(inner, f, ctx),
+        }
+    }
+}
+
+/// Draw the rows of wrapped text in view, with a scrollbar when it does not all fit
+/// and the search bar under it while searching
+fn render_text(
+    text: ViewText,
+    scroll: &mut TextScroll,
+    area: Rect,
+    f: &mut Frame,
+    ctx: &RenderContext,
+) {
+    let searching = scroll.search.is_some() || !scroll.query.is_empty();
+    let bar_height = if searching && area.height > 1 { 1 } else { 0 };
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(1),             // Text
+            Constraint::Length(bar_height), // Search bar
+        ])
+        .split(area);
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Min(1),    // Text
+            Constraint::Length(1), // Scrollbar
+        ])
+        .split(chunks[0]);
 
-        let paragraph = Paragraph::new(self.content.as_str())
-            .block(block)
-            .wrap(ratatui::widgets::Wrap { trim: true });
+    let wrapped = scroll::wrap(text.lines, columns[0].width as usize);
+    let matches = scroll::find(&wrapped.rows, &scroll.query);
+    let cursor = text
+        .cursor
+        .and_then(|line| Some((line, *wrapped.starts.get(line)?)));
+    scroll.lay_out(
+        wrapped.rows.len(),
+        columns[0].height as usize,
+        &matches,
+        cursor,
+    );
+
+    let match_style = ctx.styles.get_style(StyleType::Highlight);
+    let current_style = match_style.add_modifier(Modifier::REVERSED);
+    let visible: Vec<Line> = wrapped
+        .rows
+        .into_iter()
+        .enumerate()
+        .skip(scroll.offset)
+        .take(scroll.viewport)
+        .map(|(row, line)| {
+            let found: Vec<_> = matches
+                .iter()
+                .enumerate()
+                .filter(|(_, found)| found.row == row)
+                .map(|(index, found)| {
+                    let style = if scroll.current == Some(index) {
+                        current_style
+                    } else {
+                        match_style
+                    };
+                    (*found, style)
+                })
+                .collect();
+            scroll::highlight(line, &found)
+        })
+        .collect();
+    f.render_widget(Paragraph::new(visible), columns[0]);
+
+    if scroll.rows > scroll.viewport {
+        let mut state = ScrollbarState::new(scroll.max_offset() + 1)
+            .position(scroll.offset)
+            .viewport_content_length(scroll.viewport);
+        let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
+            .begin_symbol(None)
+            .end_symbol(None)
+            .track_style(ctx.styles.get_style(StyleType::Border))
+            .thumb_style(ctx.styles.get_style(StyleType::BorderFocus));
+        f.render_stateful_widget(scrollbar, columns[1], &mut state);
+    }
 
-        f.render_widget(paragraph, area);
+    if chunks[1].height == 0 {
+        return;
     }
+    let mut spans = match &scroll.search {
+        Some(bar) => vec![
+            ctx.styles.get_span("/", StyleType::Highlight),
+            Span::styled(
+                format!("{}_", bar.text),
+                ctx.styles.get_style(StyleType::Input),
+            ),
+        ],
+        None => vec![
+            ctx.styles.get_span("/", StyleType::MutedText),
+            Span::styled(scroll.query.clone(), ctx.styles.get_style(StyleType::Text)),
+        ],
+    };
+    let count = match (scroll.matches, scroll.current) {
+        _ if scroll.query.is_empty() => String::new(),
+        (0, _) => "  no matches".to_string(),
+        (matches, Some(current)) => format!("  {} of {}", current + 1, matches),
+        (matches, None) => format!("  {} matches", matches),
+    };
+    let count_style = if scroll.matches == 0 {
+        StyleType::Error
+    } else {
+        StyleType::MutedText
+    };
+    spans.push(Span::styled(count, ctx.styles.get_style(count_style)));
+    if scroll.search.is_none() {
+        spans.push(
+            ctx.styles
+                .get_span("  n/N next/previous", StyleType::MutedText),
+        );
+    }
+    f.render_widget(Paragraph::new(Line::from(spans)), chunks[1]);
 }
 
 /// Status bar widget
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct StatusBarWidget {
     status_text: String,
-    style_manager: StyleManager,
+    /// Rows matching the Data Browser's filter, out of all rows, while one is applied
+    matches: Option<(usize, usize)>,
 }
 
 impl StatusBarWidget {
-    pub fn new(style_manager: StyleManager) -> Self {
-        Self {
-            status_text: String::new(),
-            style_manager,
-        }
-    }
-
     pub fn set_status(&mut self, status: String) {
         self.status_text = status;
     }
+
+    pub fn set_matches(&mut self, matches: Option<(usize, usize)>) {
+        self.matches = matches;
+    }
 }
 
 impl Widget for StatusBarWidget {
-    fn render(&mut self, area: Rect, f: &mut Frame) {
-        let status_style = self.style_manager.get_style(StyleType::StatusBar);
-        let block = Block::default()
-            .style(status_style)
-            .borders(Borders::ALL);
+    fn render(&mut self, area: Rect, f: &mut Frame, ctx: &RenderContext) {
+        // A single line, so no border that would hide the text
+        let status_style = ctx.styles.get_style(StyleType::StatusBar);
+        let count = self
+            .matches
+            .map(|(shown, total)| format!(" {} of {} rows match ", shown, total))
+            .unwrap_or_default();
+        let chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Min(0),                               // Status
+                Constraint::Length(count.chars().count() as u16), // Match count
+            ])
+            .split(area);
 
         let paragraph = Paragraph::new(self.status_text.as_str())
-            .block(block)
+            .style(status_style)
             .alignment(ratatui::layout::Alignment::Center);
+        f.render_widget(paragraph, chunks[0]);
 
-        f.render_widget(paragraph, area);
+        if !count.is_empty() {
+            let style = status_style.patch(ctx.styles.get_style(StyleType::Info));
+            f.render_widget(Paragraph::new(count).style(style), chunks[1]);
+        }
+    }
+}
+
+/// A short message drawn over the corner of the content, e.g. to confirm an
+/// export; errors open a dialog instead, so they are not missed
+#[derive(Debug, Clone)]
+pub struct Toast {
+    message: String,
+    shown_at: Instant,
+}
+
+impl Toast {
+    pub fn success(message: String) -> Self {
+        Self {
+            message,
+            shown_at: Instant::now(),
+        }
+    }
+
+    fn render(&self, area: Rect, f: &mut Frame, ctx: &RenderContext) {
+        // Bottom right, clear of the content's border, wrapping long messages
+        let length = self.message.chars().count() as u16 + 2;
+        let width = (length + 2).min(area.width.saturating_sub(2));
+        let lines = length.div_ceil(width.saturating_sub(2).max(1));
+        let height = (lines + 2).min(area.height.saturating_sub(2));
+        let rect = Rect {
+            x: area.right().saturating_sub(width + 1),
+            y: area.bottom().saturating_sub(height + 1),
+            width,
+            height,
+        };
+
+        let style = ctx.styles.get_style(StyleType::Success);
+        let block = Block::default().borders(Borders::ALL).border_style(style);
+        let text = Span::styled(format!(" {} ", self.message), style);
+        f.render_widget(Clear, rect);
+        f.render_widget(
+            Paragraph::new(text).block(block).wrap(Wrap { trim: false }),
+            rect,
+        );
+    }
+}
+
+/// The command palette, drawn over the top of the screen while open
+#[derive(Debug, Clone, Default)]
+pub struct PaletteWidget {
+    palette: Option<CommandPalette>,
+    /// Keys bound to each command, as shown beside it
+    bindings: BTreeMap<Action, String>,
+}
+
+impl PaletteWidget {
+    pub fn set_palette(
+        &mut self,
+        palette: Option<&CommandPalette>,
+        bindings: BTreeMap<Action, String>,
+    ) {
+        self.palette = palette.cloned();
+        self.bindings = bindings;
+    }
+
+    fn render(&self, area: Rect, f: &mut Frame, ctx: &RenderContext) {
+        let Some(palette) = &self.palette else {
+            return;
+        };
+        // Centred across the screen, a little below the top, tall enough for the matches
+        let width = 64.min(area.width.saturating_sub(4));
+        let height = (palette.matches.len().max(1) as u16 + 3).min(area.height.saturating_sub(2));
+        let rect = Rect {
+            x: area.x + (area.width - width) / 2,
+            y: area.y + (area.height - height).min(2),
+            width,
+            height,
+        };
+
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(ctx.styles.get_style(StyleType::BorderFocus))
+            .title(
+                ctx.styles
+                    .get_span(" Command Palette ", StyleType::Subtitle),
+            )
+            .title(
+                ratatui::widgets::block::Title::from(
+                    ctx.styles
+                        .get_span(" ↑/↓ move · Enter run · Esc close ", StyleType::MutedText),
+                )
+                .position(ratatui::widgets::block::Position::Bottom),
+            );
+        let inner = block.inner(rect);
+        f.render_widget(Clear, rect);
+        f.render_widget(block, rect);
+
+        let mut lines = vec![Line::from(vec![
+            ctx.styles.get_span("> ", StyleType::Highlight),
+            Span::styled(
+                format!("{}_", palette.query),
+                ctx.styles.get_style(StyleType::Input),
+            ),
+        ])];
+        if palette.matches.is_empty() {
+            lines.push(Line::from(
+                ctx.styles
+                    .get_span("No matching commands", StyleType::MutedText),
+            ));
+        }
+
+        // Keep the selected command in sight below the query
+        let rows = inner.height.saturating_sub(1).max(1) as usize;
+        let first = (palette.selected + 1).saturating_sub(rows);
+        let highlight = ctx.styles.get_style(StyleType::Highlight);
+        for (index, command) in palette.matches.iter().enumerate().skip(first).take(rows) {
+            let selected = index == palette.selected;
+            let base = ctx.styles.get_style(if selected {
+                StyleType::ButtonActive
+            } else {
+                StyleType::Text
+            });
+            let title = command.action.description();
+            let keys = self
+                .bindings
+                .get(&command.action)
+                .map(String::as_str)
+                .unwrap_or("");
+
+            let mut spans = vec![Span::styled(if selected { "▸ " } else { "  " }, base)];
+            spans.extend(views::highlight_chars(
+                &title,
+                &command.positions,
+                base,
+                base.patch(highlight),
+            ));
+            // Bindings line up at the right edge
+            let used = 2 + title.chars().count() + keys.chars().count() + 1;
+            let gap = (inner.width as usize).saturating_sub(used).max(1);
+            spans.push(Span::styled(" ".repeat(gap), base));
+            spans.push(Span::styled(
+                format!("{} ", keys),
+                base.patch(ctx.styles.get_style(StyleType::MutedText)),
+            ));
+            lines.push(Line::from(spans));
+        }
+        f.render_widget(Paragraph::new(lines), inner);
+    }
+}
+
+/// Dialogs open over the whole screen, each centred over the ones before it
+#[derive(Debug, Clone, Default)]
+pub struct ModalWidget {
+    dialogs: Vec<Dialog>,
+}
+
+impl ModalWidget {
+    pub fn set_dialogs(&mut self, dialogs: &[Dialog]) {
+        self.dialogs = dialogs.to_vec();
+    }
+
+    fn render(&self, area: Rect, f: &mut Frame, ctx: &RenderContext) {
+        for dialog in &self.dialogs {
+            Self::render_dialog(dialog, area, f, ctx);
+        }
+    }
+
+    fn render_dialog(dialog: &Dialog, area: Rect, f: &mut Frame, ctx: &RenderContext) {
+        // Text sits a column in from the border on each side
+        let width = 56.min(area.width.saturating_sub(4));
+        let text_width = width.saturating_sub(4) as usize;
+        let text = ctx.styles.get_style(StyleType::Text);
+        let message = Line::from(Span::styled(dialog.message.clone(), text));
+        let mut lines = scroll::wrap(vec![message], text_width).rows;
+        // A blank row between the message and what answers it
+        if dialog.kind != DialogKind::Alert {
+            lines.push(Line::default());
+        }
+
+        match &dialog.kind {
+            DialogKind::Alert => {}
+            DialogKind::Confirm { label, confirm } => {
+                let button = |label: &str, selected: bool| {
+                    let style = if selected {
+                        StyleType::ButtonActive
+                    } else {
+                        StyleType::Button
+                    };
+                    Span::styled(format!(" {} ", label), ctx.styles.get_style(style))
+                };
+                lines.push(
+                    Line::from(vec![
+                        button(label, *confirm),
+                        Span::raw("   "),
+                        button("Cancel", !*confirm),
+                    ])
+                    .alignment(ratatui::layout::Alignment::Center),
+                );
+            }
+            DialogKind::Prompt { text, error } => {
+                lines.push(Line::from(vec![
+                    ctx.styles.get_span("> ", StyleType::Highlight),
+                    Span::styled(format!("{}_", text), ctx.styles.get_style(StyleType::Input)),
+                ]));
+                if let Some(error) = error {
+                    lines.push(Line::from(Span::styled(
+                        format!("⚠ {}", error),
+                        ctx.styles.get_style(StyleType::Error),
+                    )));
+                }
+            }
+            DialogKind::Choice { options, selected } => {
+                for (index, option) in options.iter().enumerate() {
+                    let chosen = index == *selected;
+                    let style = if chosen {
+                        StyleType::ButtonActive
+                    } else {
+                        StyleType::Text
+                    };
+                    let marker = if chosen { "▸" } else { " " };
+                    let option = format!("{} {} {}", marker, index + 1, option);
+                    lines.push(Line::from(Span::styled(
+                        option,
+                        ctx.styles.get_style(style),
+                    )));
+                }
+            }
+        }
+
+        // Centred on the screen, as tall as its text
+        let height = (lines.len() as u16 + 2).min(area.height);
+        let rect = Rect {
+            x: area.x + (area.width - width) / 2,
+            y: area.y + (area.height - height) / 2,
+            width,
+            height,
+        };
+        let border = match dialog.kind {
+            DialogKind::Alert => StyleType::Error,
+            _ => StyleType::BorderFocus,
+        };
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(ctx.styles.get_style(border))
+            .title(Span::styled(
+                format!(" {} ", dialog.title),
+                ctx.styles.get_style(StyleType::Subtitle),
+            ))
+            .title(
+                ratatui::widgets::block::Title::from(
+                    ctx.styles.get_span(dialog.hint(), StyleType::MutedText),
+                )
+                .position(ratatui::widgets::block::Position::Bottom),
+            );
+        let inner = block.inner(rect);
+        f.render_widget(Clear, rect);
+        f.render_widget(block, rect);
+        let text_area = Rect {
+            x: inner.x + 1,
+            width: inner.width.saturating_sub(2),
+            ..inner
+        };
+        f.render_widget(Paragraph::new(lines), text_area);
     }
 }
 
@@ -227,89 +774,380 @@ pub struct WidgetManager {
     pub sidebar: SidebarWidget,
     pub content: ContentWidget,
     pub status_bar: StatusBarWidget,
+    pub palette: PaletteWidget,
+    pub modals: ModalWidget,
+    toast: Option<Toast>,
+    layout: LayoutConfig,
+    areas: ScreenAreas,
 }
 
-impl WidgetManager {
-    pub fn new(style_manager: StyleManager) -> Self {
+impl Default for WidgetManager {
+    fn default() -> Self {
         Self {
-            header: HeaderWidget::new("Ratatui TUI".to_string(), style_manager.clone()),
-            sidebar: SidebarWidget::new(style_manager.clone()),
-            content: ContentWidget::new(style_manager.clone()),
-            status_bar: StatusBarWidget::new(style_manager),
+            header: HeaderWidget::new("Ratatui TUI".to_string()),
+            sidebar: SidebarWidget::default(),
+            content: ContentWidget::default(),
+            status_bar: StatusBarWidget::default(),
+            palette: PaletteWidget::default(),
+            modals: ModalWidget::default(),
+            toast: None,
+            layout: LayoutConfig::default(),
+            areas: ScreenAreas::default(),
         }
     }
+}
+
+impl WidgetManager {
+    /// Set which parts of the screen are shown, and how large
+    pub fn set_layout(&mut self, layout: &LayoutConfig) {
+        self.layout = layout.clone();
+    }
+
+    /// Mark the sidebar or content frame holding focus
+    pub fn set_focus(&mut self, focus: FocusId) {
+        self.sidebar.set_focused(focus == FocusId::Sidebar);
+        self.content.set_focused(focus.in_content());
+    }
+
+    /// Show a toast in place of any already showing
+    pub fn show_toast(&mut self, toast: Toast) {
+        self.toast = Some(toast);
+    }
+
+    /// Drop the toast once it has been shown long enough; whether one was dropped
+    pub fn expire_toast(&mut self) -> bool {
+        let expired = self
+            .toast
+            .as_ref()
+            .is_some_and(|toast| toast.shown_at.elapsed() >= TOAST_DURATION);
+        if expired {
+            self.toast = None;
+        }
+        expired
+    }
+
+    /// Where each part of the screen was drawn in the last frame
+    pub fn areas(&self) -> ScreenAreas {
+        self.areas
+    }
+
+    pub fn sidebar_area(&self) -> Rect {
+        self.areas.sidebar
+    }
+
+    /// What was drawn at a screen position in the last frame
+    pub fn hit_test(&self, column: u16, row: u16) -> Option<Hit> {
+        let areas = &self.areas;
+        if contains(areas.sidebar, column, row) {
+            if column == areas.sidebar.right() - 1 {
+                return Some(Hit::SidebarBorder);
+            }
+            return Some(
+                self.sidebar
+                    .item_at(column, row)
+                    .map_or(Hit::Sidebar, Hit::SidebarItem),
+            );
+        }
+
+        [
+            (areas.header, Hit::Header),
+            (areas.content, Hit::Content),
+            (areas.status_bar, Hit::StatusBar),
+        ]
+        .into_iter()
+        .find(|(area, _)| contains(*area, column, row))
+        .map(|(_, hit)| hit)
+    }
 
-    pub fn render_all(&mut self, f: &mut Frame, area: Rect) {
+    pub fn render_all(
+        &mut self,
+        f: &mut Frame,
+        area: Rect,
+        view: &mut dyn View,
+        ctx: &RenderContext,
+    ) {
+        let header_height = if self.layout.compact { 0 } else { 3 };
+        let status_height = if self.layout.show_status_bar { 1 } else { 0 };
         let chunks = Layout::default()
             .direction(Direction::Vertical)
             .constraints([
-                Constraint::Length(3), // Header
-                Constraint::Min(0),    // Main content
-                Constraint::Length(1), // Status bar
+                Constraint::Length(header_height), // Header
+                Constraint::Min(0),                // Main content
+                Constraint::Length(status_height), // Status bar
             ])
             .split(area);
 
+        // Hidden parts get empty areas so hit-testing never finds them
+        self.areas = ScreenAreas {
+            header: chunks[0],
+            status_bar: chunks[2],
+            ..ScreenAreas::default()
+        };
+
         // Render header
-        self.header.render(chunks[0], f);
+        if !self.layout.compact {
+            self.header.render(chunks[0], f, ctx);
+        }
 
         // Render main content area
+        let sidebar_width = if self.layout.show_sidebar {
+            self.layout.sidebar_width
+        } else {
+            0
+        };
         let main_chunks = Layout::default()
             .direction(Direction::Horizontal)
             .constraints([
-                Constraint::Length(20), // Sidebar
-                Constraint::Min(0),     // Content
+                Constraint::Length(sidebar_width), // Sidebar
+                Constraint::Min(0),                // Content
             ])
             .split(chunks[1]);
 
         // Render sidebar
-        self.sidebar.render(main_chunks[0], f);
+        if self.layout.show_sidebar {
+            self.areas.sidebar = main_chunks[0];
+            self.sidebar.render(main_chunks[0], f, ctx);
+        }
+        self.areas.content = main_chunks[1];
 
-        // Render content
-        self.content.render(main_chunks[1], f);
+        // Render the current view into the content area
+        self.content.render_view(view, main_chunks[1], f, ctx);
 
         // Render status bar
-        self.status_bar.render(chunks[2], f);
+        if self.layout.show_status_bar {
+            self.status_bar.render(chunks[2], f, ctx);
+        }
+
+        if let Some(toast) = &self.toast {
+            toast.render(main_chunks[1], f, ctx);
+        }
+        self.palette.render(area, f, ctx);
+        self.modals.render(area, f, ctx);
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::styles::StyleManager;
+    use crate::{keymap::Keymap, styles::StyleManager, views::HelpView};
+    use ratatui::{backend::TestBackend, Terminal};
+
+    fn buffer_text(terminal: &Terminal<TestBackend>) -> String {
+        terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect()
+    }
 
     #[test]
     fn test_header_widget_creation() {
-        let style_manager = StyleManager::default();
-        let header = HeaderWidget::new("Test".to_string(), style_manager);
+        let header = HeaderWidget::new("Test".to_string());
         assert_eq!(header.title, "Test");
     }
 
     #[test]
     fn test_sidebar_widget_creation() {
-        let style_manager = StyleManager::default();
-        let sidebar = SidebarWidget::new(style_manager);
+        let sidebar = SidebarWidget::default();
         assert_eq!(sidebar.items.len(), 4);
-        assert_eq!(sidebar.current_view, "dashboard");
+        assert_eq!(sidebar.current_view, ViewId::Dashboard);
+        assert_eq!(sidebar.items[1].id, ViewId::Data);
     }
 
     #[test]
-    fn test_content_widget_creation() {
-        let style_manager = StyleManager::default();
-        let content = ContentWidget::new(style_manager);
-        assert_eq!(content.content, "");
+    fn test_sidebar_uses_key_hints() {
+        let mut sidebar = SidebarWidget::default();
+        sidebar.set_key_hints(&Keymap::default().hints(ViewId::Dashboard));
+
+        assert_eq!(sidebar.items[1].key, "2");
+        assert!(sidebar
+            .shortcuts
+            .contains(&"q/Esc/Ctrl+C - Quit".to_string()));
+    }
+
+    #[test]
+    fn test_content_widget_renders_view() {
+        let styles = StyleManager::default();
+        let ctx = RenderContext::new(&styles);
+        let mut content = ContentWidget::default();
+        let mut view = HelpView::default();
+        let mut terminal = Terminal::new(TestBackend::new(60, 10)).unwrap();
+
+        terminal
+            .draw(|f| content.render_view(&mut view, f.size(), f, &ctx))
+            .unwrap();
+
+        assert!(buffer_text(&terminal).contains("Help & Documentation"));
     }
 
     #[test]
     fn test_status_bar_widget_creation() {
-        let style_manager = StyleManager::default();
-        let status_bar = StatusBarWidget::new(style_manager);
+        let status_bar = StatusBarWidget::default();
         assert_eq!(status_bar.status_text, "");
     }
 
+    #[test]
+    fn test_status_bar_shows_match_count() {
+        let styles = StyleManager::default();
+        let ctx = RenderContext::new(&styles);
+        let mut status_bar = StatusBarWidget::default();
+        status_bar.set_status("status text".to_string());
+        status_bar.set_matches(Some((2, 5)));
+        let mut terminal = Terminal::new(TestBackend::new(60, 1)).unwrap();
+
+        terminal
+            .draw(|f| status_bar.render(f.size(), f, &ctx))
+            .unwrap();
+
+        let text = buffer_text(&terminal);
+        assert!(text.contains("status text"));
+        assert!(text.ends_with(" 2 of 5 rows match "));
+    }
+
+    #[test]
+    fn test_toast_is_drawn_until_it_expires() {
+        let styles = StyleManager::default();
+        let ctx = RenderContext::new(&styles);
+        let mut widget_manager = WidgetManager::default();
+        let mut view = HelpView::default();
+        let mut terminal = Terminal::new(TestBackend::new(80, 20)).unwrap();
+
+        widget_manager.show_toast(Toast::success("Exported 5 rows".to_string()));
+        assert!(!widget_manager.expire_toast());
+        terminal
+            .draw(|f| widget_manager.render_all(f, f.size(), &mut view, &ctx))
+            .unwrap();
+        assert!(buffer_text(&terminal).contains("│ Exported 5 rows │"));
+
+        if let Some(toast) = &mut widget_manager.toast {
+            toast.shown_at -= TOAST_DURATION;
+        }
+        assert!(widget_manager.expire_toast());
+        assert!(widget_manager.toast.is_none());
+    }
+
     #[test]
     fn test_widget_manager_creation() {
-        let style_manager = StyleManager::default();
-        let widget_manager = WidgetManager::new(style_manager);
+        let widget_manager = WidgetManager::default();
         assert_eq!(widget_manager.header.title, "Ratatui TUI");
     }
+
+    #[test]
+    fn test_layout_follows_config() {
+        let styles = StyleManager::default();
+        let ctx = RenderContext::new(&styles);
+        let mut widget_manager = WidgetManager::default();
+        let mut view = HelpView::default();
+        let mut terminal = Terminal::new(TestBackend::new(80, 20)).unwrap();
+        widget_manager
+            .status_bar
+            .set_status("status text".to_string());
+
+        terminal
+            .draw(|f| widget_manager.render_all(f, f.size(), &mut view, &ctx))
+            .unwrap();
+        let text = buffer_text(&terminal);
+        // The sidebar heading plus the help view's own section
+        assert_eq!(text.matches("Navigation").count(), 2);
+        assert!(text.contains("status text"));
+
+        widget_manager.set_layout(&LayoutConfig {
+            show_sidebar: false,
+            show_status_bar: false,
+            ..LayoutConfig::default()
+        });
+        terminal
+            .draw(|f| widget_manager.render_all(f, f.size(), &mut view, &ctx))
+            .unwrap();
+        let text = buffer_text(&terminal);
+        assert_eq!(text.matches("Navigation").count(), 1);
+        assert!(!text.contains("status text"));
+    }
+
+    #[test]
+    fn test_sidebar_cursor_navigates() {
+        let mut sidebar = SidebarWidget::default();
+        sidebar.set_focused(true);
+        let key = |code| Event::Key(crossterm::event::KeyEvent::from(code));
+
+        assert_eq!(
+            sidebar.handle_event(&key(KeyCode::Down)),
+            EventResult::Consumed
+        );
+        assert_eq!(
+            sidebar.handle_event(&key(KeyCode::End)),
+            EventResult::Consumed
+        );
+        assert_eq!(
+            sidebar.handle_event(&key(KeyCode::Enter)),
+            EventResult::Action(Action::Navigate(ViewId::Help))
+        );
+        assert_eq!(
+            sidebar.handle_event(&key(KeyCode::Char('x'))),
+            EventResult::Ignored
+        );
+
+        // Regaining focus puts the cursor back on the current view
+        sidebar.set_focused(false);
+        sidebar.set_focused(true);
+        assert_eq!(sidebar.selected, 0);
+    }
+
+    #[test]
+    fn test_hit_test_follows_layout() {
+        let styles = StyleManager::default();
+        let ctx = RenderContext::new(&styles);
+        let mut widget_manager = WidgetManager::default();
+        let mut view = HelpView::default();
+        let mut terminal = Terminal::new(TestBackend::new(80, 20)).unwrap();
+        terminal
+            .draw(|f| widget_manager.render_all(f, f.size(), &mut view, &ctx))
+            .unwrap();
+
+        assert_eq!(widget_manager.hit_test(5, 1), Some(Hit::Header));
+        assert_eq!(
+            widget_manager.hit_test(5, 6),
+            Some(Hit::SidebarItem(ViewId::Dashboard))
+        );
+        assert_eq!(
+            widget_manager.hit_test(5, 7),
+            Some(Hit::SidebarItem(ViewId::Data))
+        );
+        assert_eq!(widget_manager.hit_test(5, 4), Some(Hit::Sidebar));
+        assert_eq!(widget_manager.hit_test(19, 10), Some(Hit::SidebarBorder));
+        assert_eq!(widget_manager.hit_test(40, 10), Some(Hit::Content));
+        assert_eq!(widget_manager.hit_test(40, 19), Some(Hit::StatusBar));
+
+        widget_manager.set_layout(&LayoutConfig {
+            show_sidebar: false,
+            ..LayoutConfig::default()
+        });
+        terminal
+            .draw(|f| widget_manager.render_all(f, f.size(), &mut view, &ctx))
+            .unwrap();
+        assert_eq!(widget_manager.hit_test(5, 6), Some(Hit::Content));
+    }
+
+    #[test]
+    fn test_focused_block_uses_focus_border() {
+        let styles = StyleManager::default();
+        let ctx = RenderContext::new(&styles);
+        let mut widget_manager = WidgetManager::default();
+        let mut view = HelpView::default();
+        let mut terminal = Terminal::new(TestBackend::new(80, 20)).unwrap();
+        let focus_fg = styles.get_style(StyleType::BorderFocus).fg;
+
+        for (focus, sidebar_focused) in [(FocusId::Sidebar, true), (FocusId::Content, false)] {
+            widget_manager.set_focus(focus);
+            terminal
+                .draw(|f| widget_manager.render_all(f, f.size(), &mut view, &ctx))
+                .unwrap();
+            let buffer = terminal.backend().buffer();
+            // Top-left corners of the sidebar and the content frame
+            assert_eq!(buffer.get(0, 3).fg == focus_fg.unwrap(), sidebar_focused);
+            assert_eq!(buffer.get(20, 3).fg == focus_fg.unwrap(), !sidebar_focused);
+        }
+    }
 }